@@ -1,9 +1,19 @@
-use clap::{Parser, Subcommand};
-use fake::{faker::lorem::en::Sentence, Fake};
-use std::io;
-mod commands;
-use crate::utils::{command_runner::RealCommandRunner, filesystem_operations::RealFileSystemOps};
-use commands::{all, circuit, compile, movejs, proofs, setup, token, verifier};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::{env, path::Path};
+pub(crate) mod commands;
+use crate::project::{Project, SetupOptions};
+use crate::utils::{
+    command_runner::{CommandRunner, DryRunCommandRunner, RealCommandRunner},
+    config::{Config, InputFormat, OutputFormat, ProgressMode, Protocol},
+    error::WhitelistError,
+    filesystem_operations::{DryRunFileSystemOps, FileSystemOps, RealFileSystemOps},
+    interrupt,
+    pipeline::Step,
+};
+use commands::{
+    all, circuit, clean, doctor, export, movejs, proofs, scaffold, serve, setup, simulate, token,
+    verifier,
+};
 
 /// Represents the command line interface for the Zero Knowledge Whitelist Tool.
 /// Deriving `Parser` from clap allows for automatic parsing of command line arguments.
@@ -15,11 +25,148 @@ use commands::{all, circuit, compile, movejs, proofs, setup, token, verifier};
     about = "This tool orchestrates the management of an address whitelist using Zero-Knowledge (ZK) proofs.\nSimply input the addresses, and it will generate the corresponding Solidity code.\nIt streamlines the process of maintaining a secure and efficient whitelist for your decentralized application."
 )]
 pub struct Cli {
+    /// Settings overriding the project configuration file.
+    #[clap(flatten)]
+    pub config: ConfigArgs,
+    /// Streams the output of circom, snarkjs and node as they run
+    #[clap(long, short, global = true)]
+    pub verbose: bool,
+    /// Prints the commands and file writes that would happen, without running or writing anything
+    #[clap(long, global = true)]
+    pub dry_run: bool,
     /// The subcommand to be executed, parsed from the command line arguments.
     #[clap(subcommand)]
     pub subcmd: SubCommand,
 }
 
+/// Global options overriding the values of `zk_whitelist.toml`.
+#[derive(Args, PartialEq, Debug, Default)]
+pub struct ConfigArgs {
+    /// Project configuration file [default: zk_whitelist.toml in the current or a parent directory]
+    #[clap(long = "config", global = true)]
+    pub config_file: Option<String>,
+    /// Circom file of the circuit
+    #[clap(long, global = true)]
+    pub circuit: Option<String>,
+    /// Power of two of the maximum number of constraints of the ceremony
+    #[clap(long, global = true, value_parser = clap::value_parser!(u8).range(1..=28))]
+    pub ptau_power: Option<u8>,
+    /// Proving system of the setup and the proofs
+    #[clap(long, global = true, value_enum)]
+    pub protocol: Option<Protocol>,
+    /// Directory of the compiled circuit, the ceremony files and the scratch files of the proofs
+    #[clap(long, global = true)]
+    pub artifact_dir: Option<String>,
+    /// Working directory separating the generated files into private/, public/ and tmp/
+    #[clap(long, global = true)]
+    pub workdir: Option<String>,
+    /// Format of the file listing the addresses
+    #[clap(long, global = true, value_enum)]
+    pub input_format: Option<InputFormat>,
+    /// Format of the generated proofs file
+    #[clap(long, global = true, value_enum)]
+    pub output_format: Option<OutputFormat>,
+    /// Encrypts the zkeys and ptau files with a passphrase, read from ZK_WHITELIST_PASSPHRASE or prompted
+    #[clap(long, global = true)]
+    pub encrypt: bool,
+    /// Encrypts the zkeys and ptau files with an age identity file instead of a passphrase
+    #[clap(long, global = true)]
+    pub keyfile: Option<String>,
+    /// The circom binary [default: circom on the PATH]
+    #[clap(long, global = true, env = "ZK_WHITELIST_CIRCOM")]
+    pub circom_bin: Option<String>,
+    /// The snarkjs binary [default: snarkjs on the PATH]
+    #[clap(long, global = true, env = "ZK_WHITELIST_SNARKJS")]
+    pub snarkjs_bin: Option<String>,
+    /// The node binary [default: node on the PATH]
+    #[clap(long, global = true, env = "ZK_WHITELIST_NODE")]
+    pub node_bin: Option<String>,
+    /// Directory the tools run in, e.g. the one with the node_modules of snarkjs
+    #[clap(long, global = true, env = "ZK_WHITELIST_TOOLS_DIR")]
+    pub tools_dir: Option<String>,
+    /// Prints no progress while generating proofs
+    #[clap(long, short, global = true, conflicts_with = "json_log")]
+    pub quiet: bool,
+    /// Prints the progress of the proofs as JSON events, one per line
+    #[clap(long, global = true)]
+    pub json_log: bool,
+    /// Seconds after which an external tool is killed
+    #[clap(long, global = true, env = "ZK_WHITELIST_TIMEOUT")]
+    pub timeout: Option<u64>,
+}
+
+impl ConfigArgs {
+    /// Loads the project configuration and applies the options given on the command line.
+    ///
+    /// When the tools run in another directory, the paths of the configuration are made
+    /// absolute so that the tools still find the artifacts.
+    ///
+    /// # Returns
+    /// The configuration, or an error message if the configuration file is invalid.
+    pub fn load(&self) -> Result<Config, String> {
+        let mut config = match &self.config_file {
+            Some(path) => Config::from_file(&RealFileSystemOps, Path::new(path))?,
+            None => Config::discover(
+                &RealFileSystemOps,
+                &env::current_dir().map_err(|e| e.to_string())?,
+            )?,
+        };
+        if let Some(circuit) = &self.circuit {
+            config.circuit.path = circuit.clone();
+        }
+        if let Some(ptau_power) = self.ptau_power {
+            config.circuit.ptau_power = ptau_power;
+        }
+        if let Some(protocol) = self.protocol {
+            config.circuit.protocol = protocol;
+        }
+        if let Some(artifact_dir) = &self.artifact_dir {
+            config.artifacts.dir = artifact_dir.clone();
+        }
+        if let Some(workdir) = &self.workdir {
+            config.artifacts.workdir = Some(workdir.clone());
+        }
+        if let Some(input_format) = self.input_format {
+            config.input.format = input_format;
+        }
+        if let Some(output_format) = self.output_format {
+            config.output.format = output_format;
+        }
+        if self.encrypt {
+            config.secrets.encrypt = true;
+        }
+        if let Some(keyfile) = &self.keyfile {
+            config.secrets.encrypt = true;
+            config.secrets.keyfile = Some(keyfile.clone());
+        }
+        if let Some(circom) = &self.circom_bin {
+            config.tools.circom = Some(circom.clone());
+        }
+        if let Some(snarkjs) = &self.snarkjs_bin {
+            config.tools.snarkjs = Some(snarkjs.clone());
+        }
+        if let Some(node) = &self.node_bin {
+            config.tools.node = Some(node.clone());
+        }
+        if let Some(tools_dir) = &self.tools_dir {
+            config.tools.working_dir = Some(tools_dir.clone());
+        }
+        if self.quiet {
+            config.output.progress = ProgressMode::Quiet;
+        }
+        if self.json_log {
+            config.output.progress = ProgressMode::Json;
+        }
+        if let Some(timeout) = self.timeout {
+            config.tools.timeout = Some(timeout);
+        }
+        if config.tools.working_dir.is_some() {
+            config = config.resolve_paths(&env::current_dir().map_err(|e| e.to_string())?);
+        }
+        Ok(config)
+    }
+}
+
 /// Enumerates the available subcommands.
 /// Deriving `Subcommand` from clap provides automatic subcommand handling.
 #[derive(Subcommand, PartialEq, Debug)]
@@ -30,58 +177,392 @@ pub enum SubCommand {
     Compile,
     /// Executes the setup ceremony to generate secure setup
     Setup,
-    /// Exports a Solidity verifier
-    Verifier,
+    /// Generates a Solidity or Rust verifier from the verification key
+    Verifier(VerifierCommand),
     /// Moves the contents of `circuit_js` on parent directory
     Movejs,
     /// Generates a sample token solidity contract, to be used together with verifier.
-    Token,
+    Token(TokenCommand),
     /// Generates proofs using an input file, with a default value of "addresses.txt".
     Proofs(ProofsCommand),
-    /// Run all the commands one after the other, {circuit, compile, setup, verifier, movejs, token, proofs} using an input file, with a default value of "addresses.txt"
+    /// Mints with every proof against the compiled token and verifier on a local EVM, reporting gas used
+    Simulate(SimulateCommand),
+    /// Generates a Foundry project with the verifier, the token and tests using the real proofs
+    Scaffold(ScaffoldCommand),
+    /// Checks the installed tools and the consistency of the project artifacts
+    Doctor,
+    /// Removes the generated files, starting with the toxic waste of the ceremony
+    Clean(CleanCommand),
+    /// Packages the public artifacts into a release bundle with a manifest of hashes
+    Export(ExportCommand),
+    /// Serves the proof of each address over HTTP, and optionally proves new addresses
+    Serve(ServeCommand),
+    /// Runs the out of date steps of the pipeline {circuit, compile, setup, verifier, token, movejs, proofs} using an input file, with a default value of "addresses.txt"
     All(AllCommand),
 }
 
 #[derive(Parser, PartialEq, Debug)]
 pub struct ProofsCommand {
-    #[clap(long, default_value = "addresses.txt")]
-    pub input_file: String,
+    /// File listing the whitelisted addresses [default: addresses.txt]
+    #[clap(long)]
+    pub input_file: Option<String>,
+    /// Keeps running, updating the proofs whenever the input file changes
+    #[clap(long)]
+    pub watch: bool,
+}
+
+/// The language of the generated verifier.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum VerifierTarget {
+    /// A Solidity contract for EVM chains
+    Solidity,
+    /// A `no_std` Rust module over arkworks BN254, for non-EVM chains
+    Rust,
+}
+
+#[derive(Parser, Clone, PartialEq, Debug)]
+pub struct VerifierCommand {
+    /// Language of the generated verifier
+    #[clap(long, value_enum, default_value = "solidity")]
+    pub target: VerifierTarget,
+    /// Name of the generated verifier contract [default: Groth16Verifier]
+    #[clap(long)]
+    pub contract_name: Option<String>,
+    /// Solidity version constraint written in the `pragma solidity` line [default: >=0.7.0 <0.9.0]
+    #[clap(long)]
+    pub pragma: Option<String>,
+    /// SPDX license identifier written in the header of the contract, one of GPL-3.0,
+    /// GPL-3.0-only or GPL-3.0-or-later since the verifier is derived from snarkJS [default: GPL-3.0]
+    #[clap(long)]
+    pub license: Option<String>,
+    /// Path of the generated verifier [default: verifier.sol or verifier.rs, depending on the target]
+    #[clap(long)]
+    pub output: Option<String>,
+    /// Proofs file used to generate a unit test for the Rust verifier [default: the proofs file of the input file, if it exists]
+    #[clap(long)]
+    pub proofs: Option<String>,
+}
+
+impl Default for VerifierCommand {
+    fn default() -> Self {
+        VerifierCommand::parse_from(["verifier"])
+    }
+}
+
+/// The kind of contract generated by the `token` subcommand.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum TokenKind {
+    /// An ERC20 token minted on demand
+    Erc20,
+    /// An ERC721 collection for NFT allowlist mints
+    Erc721,
+    /// An ERC1155 drop of a single token id
+    Erc1155,
+    /// An airdrop of an existing ERC20 token from a pre-funded treasury
+    Airdrop,
+    /// An abstract contract with an `onlyWhitelisted` modifier for existing contracts to inherit
+    Library,
+}
+
+#[derive(Parser, Clone, PartialEq, Debug)]
+pub struct TokenCommand {
+    /// Kind of contract to generate
+    #[clap(long, value_enum, default_value = "erc20")]
+    pub kind: TokenKind,
+    /// Name of the generated contract [default: ZKWhitelisted for library, ZKToken otherwise]
+    #[clap(long)]
+    pub contract_name: Option<String>,
+    /// Name of the verifier contract imported from `verifier.sol` [default: Groth16Verifier]
+    #[clap(long)]
+    pub verifier_contract: Option<String>,
+    /// Solidity version constraint written in the `pragma solidity` line
+    #[clap(long, default_value = "^0.8.20")]
+    pub pragma: String,
+    /// Name of the token
+    #[clap(long, default_value = "YourToken")]
+    pub name: String,
+    /// Symbol of the token
+    #[clap(long, default_value = "YTK")]
+    pub symbol: String,
+    /// Number of whole tokens minted or transferred for every proof [default: 10 for erc20 and airdrop, 1 otherwise]
+    #[clap(long)]
+    pub amount: Option<u64>,
+    /// Number of decimals of an erc20 token, from 0 to 36 [default: 18]
+    #[clap(long, value_parser = clap::value_parser!(u8).range(0..=36))]
+    pub decimals: Option<u8>,
+    /// Lets the contract owner mint tokens without a proof (not available for airdrop)
+    #[clap(long)]
+    pub owner_mintable: bool,
+    /// Maximum supply of the token, in whole tokens (not available for airdrop)
+    #[clap(long)]
+    pub cap: Option<u64>,
+    /// Metadata URI of an erc1155 token [default: ""]
+    #[clap(long)]
+    pub uri: Option<String>,
+    /// Token id of an erc1155 drop [default: 0]
+    #[clap(long)]
+    pub token_id: Option<u64>,
+    /// Public signal of the circuit holding the address of the proven account
+    #[clap(long, default_value = "sameAddressButPublic")]
+    pub address_signal: String,
+}
+
+impl Default for TokenCommand {
+    fn default() -> Self {
+        TokenCommand::parse_from(["token"])
+    }
+}
+
+#[derive(Parser, PartialEq, Debug)]
+pub struct SimulateCommand {
+    /// Proofs file whose entries are minted [default: addresses.txt.proofs.json]
+    #[clap(long)]
+    pub proofs: Option<String>,
+    /// Token contract compiled together with the verifier it imports [default: zkToken.sol]
+    #[clap(long)]
+    pub token: Option<String>,
+    /// Name of the token contract to deploy [default: ZKToken]
+    #[clap(long)]
+    pub contract: Option<String>,
+    /// Kind of the token contract to deploy, as passed to `token --kind`
+    #[clap(long, value_enum, default_value = "erc20")]
+    pub kind: TokenKind,
+    /// Name of the function called with every proof [default: claim for airdrop, mint otherwise]
+    #[clap(long)]
+    pub function: Option<String>,
+    /// Import remapping passed to solc, can be repeated
+    #[clap(
+        long = "remapping",
+        default_value = "@openzeppelin/=node_modules/@openzeppelin/"
+    )]
+    pub remappings: Vec<String>,
+    /// Directory where solc writes the compiled contracts [default: build]
+    #[clap(long)]
+    pub build_dir: Option<String>,
+}
+
+impl Default for SimulateCommand {
+    fn default() -> Self {
+        SimulateCommand::parse_from(["simulate"])
+    }
+}
+
+/// The framework of the project generated by the `scaffold` subcommand.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum ScaffoldTarget {
+    /// A Foundry project, tested with `forge test`
+    Foundry,
+}
+
+#[derive(Parser, PartialEq, Debug)]
+pub struct ScaffoldCommand {
+    /// Framework of the generated project
+    #[clap(value_enum)]
+    pub target: ScaffoldTarget,
+    /// Proofs file the tests are generated from [default: addresses.txt.proofs.json]
+    #[clap(long)]
+    pub proofs: Option<String>,
+    /// Verifier contract copied into the project [default: verifier.sol]
+    #[clap(long)]
+    pub verifier: Option<String>,
+    /// Token contract copied into the project [default: zkToken.sol]
+    #[clap(long)]
+    pub token: Option<String>,
+    /// Name of the token contract under test [default: ZKToken]
+    #[clap(long)]
+    pub contract: Option<String>,
+    /// Kind of the token contract under test, as passed to `token --kind`
+    #[clap(long, value_enum, default_value = "erc20")]
+    pub kind: TokenKind,
+    /// Name of the function called with every proof [default: claim for airdrop, mint otherwise]
+    #[clap(long)]
+    pub function: Option<String>,
+    /// Directory the project is written to
+    #[clap(long, default_value = ".")]
+    pub out_dir: String,
+}
+
+impl Default for ScaffoldCommand {
+    fn default() -> Self {
+        ScaffoldCommand::parse_from(["scaffold", "foundry"])
+    }
+}
+
+#[derive(Parser, PartialEq, Debug, Default)]
+pub struct CleanCommand {
+    /// Keeps the contracts, the verification key and the proofs
+    #[clap(long)]
+    pub keep_public: bool,
+    /// Overwrites the content of every file before removing it
+    #[clap(long)]
+    pub secure: bool,
+}
+
+#[derive(Parser, PartialEq, Debug)]
+pub struct ExportCommand {
+    /// The path of the bundle to write
+    #[clap(long, default_value = "release.tar.gz")]
+    pub out: String,
+    /// Adds a public file to the bundle, such as release notes
+    #[clap(long)]
+    pub include: Vec<String>,
+}
+
+#[derive(Parser, PartialEq, Debug)]
+pub struct ServeCommand {
+    /// Proofs file whose entries are served [default: addresses.txt.proofs.json]
+    #[clap(long)]
+    pub proofs: Option<String>,
+    /// Address and port the server listens on
+    #[clap(long, default_value = "127.0.0.1:8080")]
+    pub addr: String,
+    /// Token enabling `POST /prove`, sent as `Authorization: Bearer <token>`
+    #[clap(long, env = "ZK_WHITELIST_ADMIN_TOKEN", hide_env_values = true)]
+    pub admin_token: Option<String>,
+    /// Origin allowed to call the server from a browser
+    #[clap(long, default_value = "*")]
+    pub cors_origin: String,
+    /// Name of the function whose calldata `GET /proof/{address}?format=calldata` returns
+    #[clap(long, default_value = "mint")]
+    pub function: String,
+}
+
+impl Default for ServeCommand {
+    fn default() -> Self {
+        ServeCommand::parse_from(["serve"])
+    }
 }
 
 #[derive(Parser, PartialEq, Debug)]
 pub struct AllCommand {
-    #[clap(long, default_value = "addresses.txt")]
-    pub input_file: String,
+    /// File listing the whitelisted addresses [default: addresses.txt]
+    #[clap(long)]
+    pub input_file: Option<String>,
+    /// Reruns this step and every step depending on it, even when up to date
+    #[clap(long, value_enum, conflicts_with = "only")]
+    pub from: Option<Step>,
+    /// Runs only these steps, even when up to date
+    #[clap(long, value_enum, value_delimiter = ',')]
+    pub only: Vec<Step>,
 }
 
 /// The entry point of the application.
 /// Parses command line arguments and executes the corresponding subcommand.
-pub fn run_cli() -> std::io::Result<()> {
+///
+/// With `--dry-run` the subcommand runs with recording implementations, which print the
+/// commands and file writes instead of running them.
+///
+/// # Returns
+/// The error of the subcommand, whose `exit_code` is the exit code of the process.
+pub fn run_cli() -> Result<(), WhitelistError> {
     let args = Cli::parse();
-    let runner = RealCommandRunner;
-    let file_system_ops = RealFileSystemOps;
-    let random_name: String = Sentence(2..3).fake();
-    let random_text: String = Sentence(3..4).fake();
-
-    match args.subcmd {
-        SubCommand::Circuit => circuit::handle_circuit_subcommand()?,
-        SubCommand::Compile => compile::handle_compile_subcommand(&runner)?,
-        SubCommand::Setup => setup::handle_setup_subcommand(&runner, random_name, random_text)?,
-        SubCommand::Verifier => verifier::handle_verifier_subcommand(&runner)?,
-        SubCommand::Movejs => movejs::handle_movejs_subcommand(&file_system_ops)?,
+    let config = args.config.load().map_err(WhitelistError::InvalidInput)?;
+
+    if args.dry_run {
+        return run_subcommand(
+            args.subcmd,
+            &DryRunCommandRunner::new(config.tools.clone()),
+            |_| Ok(DryRunCommandRunner::new(config.tools.clone())),
+            &DryRunFileSystemOps::new(),
+            true,
+            &config,
+        );
+    }
+    interrupt::install_handler()?;
+    let runner = RealCommandRunner::new(args.verbose, Some(config.log_dir()))
+        .with_tools(config.tools.clone());
+    // The runner of each subcommand running external tools logs their output to its own file.
+    run_subcommand(
+        args.subcmd,
+        &runner,
+        |step| runner.for_step(step),
+        &RealFileSystemOps,
+        false,
+        &config,
+    )
+}
+
+/// Executes a subcommand with the given command runners and file system operations.
+///
+/// # Parameters
+/// - `subcmd`: The parsed subcommand.
+/// - `runner`: The command runner of the subcommands probing the tools.
+/// - `step_runner`: Returns the command runner of the subcommand or step with the given name.
+/// - `file_system_ops`: The file system operations of every subcommand.
+/// - `dry_run`: Whether the subcommand runs with the recording implementations.
+/// - `config`: The project configuration.
+///
+/// # Returns
+/// The error of the subcommand, whose `exit_code` is the exit code of the process.
+fn run_subcommand<R, F>(
+    subcmd: SubCommand,
+    runner: &R,
+    step_runner: impl Fn(&str) -> Result<R, WhitelistError>,
+    file_system_ops: &F,
+    dry_run: bool,
+    config: &Config,
+) -> Result<(), WhitelistError>
+where
+    R: CommandRunner,
+    F: FileSystemOps,
+{
+    for dir in config.layout_dirs() {
+        file_system_ops.create_dir_all(&dir)?;
+    }
+    // The steps of the pipeline go through a project borrowing the file system operations,
+    // with the command runner of the step.
+    let project = |step: &str| {
+        step_runner(step).map(|runner| Project::with_ops(config.clone(), runner, file_system_ops))
+    };
+
+    match subcmd {
+        SubCommand::Circuit => circuit::handle_circuit_subcommand(file_system_ops, config)?,
+        SubCommand::Compile => project("compile")?.compile_circuit()?,
+        SubCommand::Setup => {
+            setup::handle_setup_subcommand(&project("setup")?, SetupOptions::default())?
+        }
+        SubCommand::Verifier(verifier_command) => {
+            verifier::handle_verifier_subcommand(&project("verifier")?, &verifier_command)?
+        }
+        SubCommand::Movejs => movejs::handle_movejs_subcommand(file_system_ops, config)?,
         SubCommand::Proofs(proofs_command) => {
-            proofs::handle_proofs_subcommand(&runner, &proofs_command.input_file, &file_system_ops)
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            let input_file = proofs_command
+                .input_file
+                .as_deref()
+                .unwrap_or(&config.input.file);
+            let project = project("proofs")?;
+            match proofs_command.watch {
+                true => proofs::watch_proofs_subcommand(&project, input_file)?,
+                false => proofs::handle_proofs_subcommand(&project, input_file)?,
+            }
+        }
+        SubCommand::Token(token_command) => {
+            token::handle_token_subcommand(file_system_ops, &token_command, config)?
+        }
+        SubCommand::Simulate(simulate_command) => simulate::handle_simulate_subcommand(
+            &step_runner("simulate")?,
+            file_system_ops,
+            &simulate_command,
+            config,
+        )?,
+        SubCommand::Scaffold(scaffold_command) => {
+            scaffold::handle_scaffold_subcommand(file_system_ops, &scaffold_command, config)?
         }
-        SubCommand::Token => token::handle_token_subcommand()?,
+        SubCommand::Doctor => doctor::handle_doctor_subcommand(runner, file_system_ops, config)?,
+        SubCommand::Clean(clean_command) => {
+            clean::handle_clean_subcommand(file_system_ops, &clean_command, config)?
+        }
+        SubCommand::Export(export_command) => {
+            export::handle_export_subcommand(file_system_ops, &export_command, config)?
+        }
+        SubCommand::Serve(serve_command) => serve::handle_serve_subcommand(
+            &step_runner("serve")?,
+            file_system_ops,
+            &serve_command,
+            config,
+        )?,
         SubCommand::All(all_command) => {
-            all::handle_all_command(
-                runner,
-                random_name,
-                random_text,
-                file_system_ops,
-                all_command,
-            )?;
+            all::handle_all_command(step_runner, file_system_ops, all_command, dry_run, config)?;
         }
     };
 
@@ -94,75 +575,465 @@ mod tests {
 
     #[test]
     fn test_parse_circuit_subcommand() {
-        let args = Cli::parse_from(&["zk_whitelist", "circuit"]);
+        let args = Cli::parse_from(["zk_whitelist", "circuit"]);
         assert_eq!(args.subcmd, SubCommand::Circuit);
     }
 
     #[test]
     fn test_parse_compile_subcommand() {
-        let args = Cli::parse_from(&["zk_whitelist", "compile"]);
+        let args = Cli::parse_from(["zk_whitelist", "compile"]);
         assert_eq!(args.subcmd, SubCommand::Compile);
     }
 
     #[test]
     fn test_parse_setup_subcommand() {
-        let args = Cli::parse_from(&["zk_whitelist", "setup"]);
+        let args = Cli::parse_from(["zk_whitelist", "setup"]);
         assert_eq!(args.subcmd, SubCommand::Setup);
     }
 
     #[test]
     fn test_parse_verifier_subcommand() {
-        let args = Cli::parse_from(&["zk_whitelist", "verifier"]);
-        assert_eq!(args.subcmd, SubCommand::Verifier);
+        let args = Cli::parse_from(["zk_whitelist", "verifier"]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Verifier(VerifierCommand {
+                target: VerifierTarget::Solidity,
+                contract_name: None,
+                pragma: None,
+                license: None,
+                output: None,
+                proofs: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_verifier_subcommand_with_custom_values() {
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "verifier",
+            "--contract-name",
+            "AirdropVerifier",
+            "--pragma",
+            "^0.8.20",
+            "--license",
+            "GPL-3.0-or-later",
+            "--output",
+            "src/AirdropVerifier.sol",
+        ]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Verifier(VerifierCommand {
+                target: VerifierTarget::Solidity,
+                contract_name: Some("AirdropVerifier".to_string()),
+                pragma: Some("^0.8.20".to_string()),
+                license: Some("GPL-3.0-or-later".to_string()),
+                output: Some("src/AirdropVerifier.sol".to_string()),
+                proofs: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_verifier_subcommand_with_rust_target() {
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "verifier",
+            "--target",
+            "rust",
+            "--proofs",
+            "addresses.txt.proofs.json",
+        ]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Verifier(VerifierCommand {
+                target: VerifierTarget::Rust,
+                proofs: Some("addresses.txt.proofs.json".to_string()),
+                ..VerifierCommand::default()
+            })
+        );
     }
 
     #[test]
     fn test_movejs_subcommand() {
-        let args = Cli::parse_from(&["zk_whitelist", "movejs"]);
+        let args = Cli::parse_from(["zk_whitelist", "movejs"]);
         assert_eq!(args.subcmd, SubCommand::Movejs);
     }
 
     #[test]
     fn test_parse_proofs_subcommand_with_default_value() {
-        let args = Cli::parse_from(&["zk_whitelist", "proofs"]);
+        let args = Cli::parse_from(["zk_whitelist", "proofs"]);
         assert_eq!(
             args.subcmd,
             SubCommand::Proofs(ProofsCommand {
-                input_file: "addresses.txt".to_string()
+                input_file: None,
+                watch: false,
             })
         );
     }
 
     #[test]
     fn test_parse_proofs_subcommand_with_custom_value() {
-        let args = Cli::parse_from(&["zk_whitelist", "proofs", "--input-file", "custom.txt"]);
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "proofs",
+            "--input-file",
+            "custom.txt",
+            "--watch",
+        ]);
         assert_eq!(
             args.subcmd,
             SubCommand::Proofs(ProofsCommand {
-                input_file: "custom.txt".to_string()
+                input_file: Some("custom.txt".to_string()),
+                watch: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_token_subcommand_with_default_values() {
+        let args = Cli::parse_from(["zk_whitelist", "token"]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Token(TokenCommand {
+                kind: TokenKind::Erc20,
+                contract_name: None,
+                verifier_contract: None,
+                pragma: "^0.8.20".to_string(),
+                name: "YourToken".to_string(),
+                symbol: "YTK".to_string(),
+                amount: None,
+                decimals: None,
+                owner_mintable: false,
+                cap: None,
+                uri: None,
+                token_id: None,
+                address_signal: "sameAddressButPublic".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_token_subcommand_with_custom_values() {
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "token",
+            "--name",
+            "Airdrop Token",
+            "--symbol",
+            "ADT",
+            "--amount",
+            "250",
+            "--decimals",
+            "6",
+            "--owner-mintable",
+            "--cap",
+            "1000000",
+        ]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Token(TokenCommand {
+                name: "Airdrop Token".to_string(),
+                symbol: "ADT".to_string(),
+                amount: Some(250),
+                decimals: Some(6),
+                owner_mintable: true,
+                cap: Some(1_000_000),
+                ..TokenCommand::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_token_subcommand_with_kind() {
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "token",
+            "--kind",
+            "erc1155",
+            "--uri",
+            "ipfs://drop/{id}.json",
+            "--token-id",
+            "7",
+        ]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Token(TokenCommand {
+                kind: TokenKind::Erc1155,
+                uri: Some("ipfs://drop/{id}.json".to_string()),
+                token_id: Some(7),
+                ..TokenCommand::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_token_subcommand_with_too_many_decimals() {
+        assert!(Cli::try_parse_from(["zk_whitelist", "token", "--decimals", "36"]).is_ok());
+        assert!(Cli::try_parse_from(["zk_whitelist", "token", "--decimals", "37"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_simulate_subcommand_with_default_values() {
+        let args = Cli::parse_from(["zk_whitelist", "simulate"]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Simulate(SimulateCommand {
+                proofs: None,
+                token: None,
+                contract: None,
+                kind: TokenKind::Erc20,
+                function: None,
+                remappings: vec!["@openzeppelin/=node_modules/@openzeppelin/".to_string()],
+                build_dir: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_simulate_subcommand_with_custom_values() {
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "simulate",
+            "--proofs",
+            "custom.txt.proofs.json",
+            "--remapping",
+            "@openzeppelin/=lib/openzeppelin-contracts/",
+            "--remapping",
+            "forge-std/=lib/forge-std/src/",
+        ]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Simulate(SimulateCommand {
+                proofs: Some("custom.txt.proofs.json".to_string()),
+                remappings: vec![
+                    "@openzeppelin/=lib/openzeppelin-contracts/".to_string(),
+                    "forge-std/=lib/forge-std/src/".to_string(),
+                ],
+                ..SimulateCommand::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_scaffold_subcommand_with_default_values() {
+        let args = Cli::parse_from(["zk_whitelist", "scaffold", "foundry"]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Scaffold(ScaffoldCommand {
+                target: ScaffoldTarget::Foundry,
+                proofs: None,
+                verifier: None,
+                token: None,
+                contract: None,
+                kind: TokenKind::Erc20,
+                function: None,
+                out_dir: ".".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_scaffold_subcommand_without_target() {
+        assert!(Cli::try_parse_from(["zk_whitelist", "scaffold"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_doctor_subcommand() {
+        let args = Cli::parse_from(["zk_whitelist", "doctor"]);
+        assert_eq!(args.subcmd, SubCommand::Doctor);
+    }
+
+    #[test]
+    fn test_parse_clean_subcommand() {
+        let args = Cli::parse_from(["zk_whitelist", "clean", "--keep-public", "--secure"]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Clean(CleanCommand {
+                keep_public: true,
+                secure: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_export_subcommand() {
+        let args = Cli::parse_from(["zk_whitelist", "export"]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Export(ExportCommand {
+                out: "release.tar.gz".to_string(),
+                include: vec![],
+            })
+        );
+
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "export",
+            "--out",
+            "v1.tar.gz",
+            "--include",
+            "NOTES.md",
+        ]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Export(ExportCommand {
+                out: "v1.tar.gz".to_string(),
+                include: vec!["NOTES.md".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_serve_subcommand() {
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "serve",
+            "--proofs",
+            "allowlist.txt.proofs.json",
+            "--addr",
+            "0.0.0.0:3000",
+            "--admin-token",
+            "secret",
+        ]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::Serve(ServeCommand {
+                proofs: Some("allowlist.txt.proofs.json".to_string()),
+                addr: "0.0.0.0:3000".to_string(),
+                admin_token: Some("secret".to_string()),
+                cors_origin: "*".to_string(),
+                function: "mint".to_string(),
             })
         );
     }
 
     #[test]
     fn test_parse_all_subcommand_with_default_value() {
-        let args = Cli::parse_from(&["zk_whitelist", "all"]);
+        let args = Cli::parse_from(["zk_whitelist", "all"]);
         assert_eq!(
             args.subcmd,
             SubCommand::All(AllCommand {
-                input_file: "addresses.txt".to_string()
+                input_file: None,
+                from: None,
+                only: vec![],
             })
         );
     }
 
     #[test]
     fn test_parse_all_subcommand_with_custom_value() {
-        let args = Cli::parse_from(&["zk_whitelist", "all", "--input-file", "custom.txt"]);
+        let args = Cli::parse_from(["zk_whitelist", "all", "--input-file", "custom.txt"]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::All(AllCommand {
+                input_file: Some("custom.txt".to_string()),
+                from: None,
+                only: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_all_subcommand_with_pipeline_options() {
+        let args = Cli::parse_from(["zk_whitelist", "all", "--from", "setup", "--dry-run"]);
+        assert_eq!(
+            args.subcmd,
+            SubCommand::All(AllCommand {
+                input_file: None,
+                from: Some(Step::Setup),
+                only: vec![],
+            })
+        );
+        assert!(args.dry_run);
+
+        let args = Cli::parse_from(["zk_whitelist", "all", "--only", "token,proofs"]);
         assert_eq!(
             args.subcmd,
             SubCommand::All(AllCommand {
-                input_file: "custom.txt".to_string()
+                input_file: None,
+                from: None,
+                only: vec![Step::Token, Step::Proofs],
             })
         );
+
+        let result =
+            Cli::try_parse_from(["zk_whitelist", "all", "--from", "setup", "--only", "proofs"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_tools() {
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "setup",
+            "--snarkjs-bin",
+            "node_modules/.bin/snarkjs",
+        ]);
+
+        let config = args.config.load().unwrap();
+
+        assert_eq!(
+            config.tools.snarkjs,
+            Some("node_modules/.bin/snarkjs".to_string())
+        );
+        assert_eq!(config.circuit.path, "circuit.circom");
+    }
+
+    #[test]
+    fn test_parse_workdir() {
+        let args = Cli::parse_from(["zk_whitelist", "movejs", "--workdir", "whitelist"]);
+        assert_eq!(args.subcmd, SubCommand::Movejs);
+        assert_eq!(args.config.workdir, Some("whitelist".to_string()));
+
+        let config = args.config.load().unwrap();
+        assert_eq!(config.artifact_dir(), "whitelist/private");
+        assert_eq!(config.public_dir(), "whitelist/public");
+        assert_eq!(config.tmp_dir(), "whitelist/tmp");
+    }
+
+    #[test]
+    fn test_parse_encryption_options() {
+        let args = Cli::parse_from(["zk_whitelist", "setup", "--keyfile", "whitelist.key"]);
+        let config = args.config.load().unwrap();
+        assert!(config.secrets.encrypt);
+        assert_eq!(config.secrets.keyfile, Some("whitelist.key".to_string()));
+
+        let args = Cli::parse_from(["zk_whitelist", "proofs", "--encrypt"]);
+        let config = args.config.load().unwrap();
+        assert!(config.secrets.encrypt);
+        assert_eq!(config.secrets.keyfile, None);
+    }
+
+    #[test]
+    fn test_parse_config_overrides() {
+        let args = Cli::parse_from([
+            "zk_whitelist",
+            "setup",
+            "--config",
+            "project/zk_whitelist.toml",
+            "--ptau-power",
+            "14",
+            "--artifact-dir",
+            "build",
+            "--input-format",
+            "csv",
+        ]);
+        assert_eq!(args.subcmd, SubCommand::Setup);
+        assert_eq!(
+            args.config,
+            ConfigArgs {
+                config_file: Some("project/zk_whitelist.toml".to_string()),
+                ptau_power: Some(14),
+                artifact_dir: Some("build".to_string()),
+                input_format: Some(InputFormat::Csv),
+                ..ConfigArgs::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_config_overrides_with_invalid_ptau_power() {
+        assert!(Cli::try_parse_from(["zk_whitelist", "setup", "--ptau-power", "40"]).is_err());
     }
 }
@@ -1,25 +1,120 @@
+use super::{circuit, movejs, proofs, setup, token, verifier};
 use crate::{
-    cli::AllCommand,
-    utils::{command_runner::RealCommandRunner, filesystem_operations::RealFileSystemOps},
+    cli::{AllCommand, TokenCommand, VerifierCommand},
+    project::{Project, SetupOptions},
+    utils::{
+        command_runner::CommandRunner,
+        config::Config,
+        error::WhitelistError,
+        filesystem_operations::FileSystemOps,
+        pipeline::{plan, read_state, record_step, Step},
+    },
 };
-use std::io;
 
-use super::{circuit, compile, movejs, proofs, setup, token, verifier};
+/// Runs a single step of the pipeline, through a `Project` for the steps it covers.
+fn run_step<R: CommandRunner, F: FileSystemOps>(
+    step: Step,
+    runner: R,
+    file_system_ops: &F,
+    input_file: &str,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    let project = Project::with_ops(config.clone(), runner, file_system_ops);
+    match step {
+        Step::Circuit => circuit::handle_circuit_subcommand(file_system_ops, config),
+        Step::Compile => project.compile_circuit(),
+        Step::Setup => setup::handle_setup_subcommand(&project, SetupOptions::default()),
+        Step::Verifier => {
+            verifier::handle_verifier_subcommand(&project, &VerifierCommand::default())
+        }
+        Step::Token => {
+            token::handle_token_subcommand(file_system_ops, &TokenCommand::default(), config)
+        }
+        Step::Movejs => movejs::handle_movejs_subcommand(file_system_ops, config),
+        Step::Proofs => proofs::handle_proofs_subcommand(&project, input_file),
+    }
+}
 
-pub fn handle_all_command(
-    runner: RealCommandRunner,
-    random_name: String,
-    random_text: String,
-    file_system_ops: RealFileSystemOps,
+/// Handles the `all` subcommand.
+///
+/// Plans the pipeline and runs the steps that are out of date, or were selected with `--from`
+/// or `--only`. After each step the hashes of its inputs are recorded, so that a later run
+/// skips it while they stay unchanged. With `--dry-run` the steps run with the dry run command
+/// runner and file system operations, and a step needing an artifact that only a tool of an
+/// earlier step would write, or a missing input file, is reported and skipped.
+///
+/// # Parameters
+/// - `step_runner`: Returns the command runner of the step with the given name.
+/// - `file_system_ops`: The file system operations used by the steps and to inspect the files.
+/// - `all_command`: The parsed options of the `all` subcommand.
+/// - `dry_run`: Whether the steps run with the dry run implementations.
+/// - `config`: The project configuration.
+///
+/// # Returns
+/// A `Result<(), WhitelistError>` which is `Ok(())` if every planned step succeeded, or the error of the
+/// first step that failed.
+pub fn handle_all_command<R, F>(
+    step_runner: impl Fn(&str) -> Result<R, WhitelistError>,
+    file_system_ops: &F,
     all_command: AllCommand,
-) -> Result<(), io::Error> {
-    circuit::handle_circuit_subcommand()?;
-    compile::handle_compile_subcommand(&runner)?;
-    setup::handle_setup_subcommand(&runner, random_name.clone(), random_text.clone())?;
-    verifier::handle_verifier_subcommand(&runner)?;
-    token::handle_token_subcommand()?;
-    movejs::handle_movejs_subcommand(&file_system_ops)?;
-    proofs::handle_proofs_subcommand(&runner, &all_command.input_file, &file_system_ops)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    dry_run: bool,
+    config: &Config,
+) -> Result<(), WhitelistError>
+where
+    R: CommandRunner,
+    F: FileSystemOps,
+{
+    let input_file = all_command
+        .input_file
+        .unwrap_or_else(|| config.input.file.clone());
+    let planned = plan(
+        file_system_ops,
+        config,
+        &input_file,
+        all_command.from,
+        &all_command.only,
+    );
+
+    let mut state = read_state(file_system_ops, config);
+    for planned_step in &planned {
+        println!("==> {}", planned_step);
+        if !planned_step.runs() {
+            continue;
+        }
+        let result = run_step(
+            planned_step.step,
+            step_runner(&planned_step.step.to_string())?,
+            file_system_ops,
+            &input_file,
+            config,
+        );
+        match result {
+            // The tools do not run in a dry run, so the artifacts they write are missing.
+            Err(WhitelistError::ArtifactMissing(artifact)) if dry_run => {
+                let written_by_step = Step::ALL
+                    .iter()
+                    .any(|step| step.outputs(config, &input_file).contains(&artifact));
+                match written_by_step {
+                    true => println!(
+                        "skip {}: {} is written by a tool that does not run in a dry run",
+                        planned_step.step, artifact
+                    ),
+                    false => println!(
+                        "skip {}: the input file {} is missing",
+                        planned_step.step, artifact
+                    ),
+                }
+                continue;
+            }
+            result => result?,
+        }
+        record_step(
+            file_system_ops,
+            config,
+            &input_file,
+            planned_step.step,
+            &mut state,
+        )?;
+    }
     Ok(())
 }
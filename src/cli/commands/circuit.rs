@@ -1,68 +1,51 @@
-use std::env;
-use std::fs::File;
-use std::io::{self, Write};
-use std::path::Path;
+use crate::utils::{config::Config, error::WhitelistError, filesystem_operations::FileSystemOps};
 
-/// Copies a circuit file template to the current directory.
-///
-/// This function is intended to be called when the `circuit` subcommand is used.
-/// It locates the current working directory, constructs a path for the new file,
-/// creates a new file named `circuit.circom` in the current directory,
-/// and writes the contents of a template file into the new file.
-///
-/// # Errors
-/// Returns an `io::Result` wrapping any I/O error that occurs.
-fn copy_circuit_file() -> io::Result<()> {
-    // Obtain the current working directory
-    let current_dir = env::current_dir()?;
-    // Construct a path for the new circuit file
-    let circuit_path = Path::new(&current_dir).join("circuit.circom");
-    // Create a new file at the constructed path
-    let mut file = File::create(circuit_path)?;
-    // Write the contents of the template file into the new file
-    file.write_all(include_bytes!("../../../templates/circuit.circom"))?;
-    Ok(())
-}
+/// The circuit template written by the `circuit` subcommand.
+const CIRCUIT_TEMPLATE: &str = include_str!("../../../templates/circuit.circom");
 
 /// Handles the `circuit` CLI subcommand.
 ///
 /// This function acts as a handler for the `circuit` subcommand.
-/// It calls the `copy_circuit_file` function to perform the actual work.
+/// It writes the circuit template at the circuit path of the configuration
+/// (`circuit.circom` by default), relative to the current directory.
+///
+/// # Parameters
+/// - `file_ops`: The file system operations used to write the circuit.
+/// - `config`: The project configuration, which holds the path of the circuit.
 ///
 /// # Returns
-/// Returns an `io::Result` to indicate success or any I/O error that occurs.
-pub fn handle_circuit_subcommand() -> std::io::Result<()> {
-    copy_circuit_file()
+/// Returns a `Result` to indicate success or any I/O error that occurs.
+pub fn handle_circuit_subcommand<F: FileSystemOps>(
+    file_ops: &F,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    file_ops.write_to_file(&config.circuit.path, CIRCUIT_TEMPLATE)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
-    use std::path::Path;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
 
     /// Tests the functionality of the `handle_circuit_subcommand` function.
     ///
-    /// This test ensures that the `handle_circuit_subcommand` function correctly
-    /// creates a new `circuit.circom` file in the current directory.
-    ///
-    /// # Returns
-    /// Returns an `io::Result` to indicate the success or failure of the test.
+    /// This test ensures that the `handle_circuit_subcommand` function writes
+    /// the template at the circuit path of the configuration.
     #[test]
-    fn test_handle_circuit_subcommand() -> std::io::Result<()> {
-        // Execute the function under test
-        handle_circuit_subcommand()?;
-
-        // Obtain the current working directory
-        let current_dir = std::env::current_dir()?;
-        // Construct the path of the circuit file
-        let circuit_path = Path::new(&current_dir).join("circuit.circom");
-        // Assert that the file has been created
-        assert!(circuit_path.exists());
+    fn test_handle_circuit_subcommand() {
+        // Arrange
+        let file_ops = MockFileSystemOps::new();
+        let mut config = Config::default();
+        config.circuit.path = "circuits/allowlist.circom".to_string();
 
-        // Clean up by removing the created file
-        fs::remove_file(circuit_path)?;
+        // Act
+        let result = handle_circuit_subcommand(&file_ops, &config);
 
-        Ok(())
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(
+            file_ops.get_written_content("circuits/allowlist.circom"),
+            Some(CIRCUIT_TEMPLATE.to_string())
+        );
     }
 }
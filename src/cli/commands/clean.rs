@@ -0,0 +1,230 @@
+use crate::cli::CleanCommand;
+use crate::utils::{
+    config::Config, error::WhitelistError, filesystem_operations::FileSystemOps,
+    pipeline::PIPELINE_STATE_FILE,
+};
+
+/// Returns the generated files `clean` removes, whether they exist or not.
+///
+/// The circuit source and the input file are never removed. Encrypted secrets are removed
+/// together with any plaintext copy.
+///
+/// # Parameters
+/// - `config`: The project configuration locating the files.
+/// - `keep_public`: Keeps the contracts, the verification key and the proofs.
+pub fn files_to_clean(config: &Config, keep_public: bool) -> Vec<String> {
+    let name = config.circuit_name();
+    let mut files = config.scratch_files();
+
+    // The toxic waste of the ceremony comes first, then the secrets still used for proving.
+    for secret in config.setup_secrets() {
+        files.push(format!("{}.age", secret));
+        files.push(secret);
+    }
+    files.push(config.tmp_file(&format!("{}_0001.zkey", name)));
+    files.extend([
+        config.artifact(&format!("{}.r1cs", name)),
+        config.artifact(&format!("{}.sym", name)),
+        config.artifact(&format!("{}.wasm", name)),
+        config.artifact(&format!("{}_js", name)),
+        config.artifact("generate_witness.js"),
+        config.artifact("witness_calculator.js"),
+        config.artifact(PIPELINE_STATE_FILE),
+        config.tmp_file("build"),
+        config.log_dir(),
+    ]);
+
+    if !keep_public {
+        files.extend([
+            config.public_file("verifier.sol"),
+            config.public_file("verifier.rs"),
+            config.public_file("zkToken.sol"),
+            config.public_file("zkWhitelisted.sol"),
+            config.verification_key(),
+            config.proofs_file(&config.input.file),
+        ]);
+    }
+    files.dedup();
+    files
+}
+
+/// Removes a file, first overwriting its content when `secure` is set.
+fn remove_file<F: FileSystemOps>(
+    file_ops: &F,
+    path: &str,
+    secure: bool,
+) -> Result<(), WhitelistError> {
+    if secure {
+        file_ops.overwrite(path)?;
+    }
+    file_ops.remove(path)
+}
+
+/// Securely removes the scratch files left by generating proofs.
+///
+/// # Returns
+/// A warning per scratch file that could not be removed. Failures are not errors, since the
+/// proofs themselves were generated.
+pub fn remove_scratch_files<F: FileSystemOps>(file_ops: &F, config: &Config) -> Vec<String> {
+    config
+        .scratch_files()
+        .into_iter()
+        .filter(|path| file_ops.exists(path))
+        .filter_map(|path| remove_file(file_ops, &path, true).err())
+        .map(|e| format!("failed to remove a scratch file: {}", e))
+        .collect()
+}
+
+/// Handles the `clean` subcommand.
+///
+/// Removes the files generated by the other subcommands: the toxic waste of the ceremony,
+/// the scratch files of the proofs, the compiled circuit and the secrets, and unless
+/// `--keep-public` is given the contracts, verification key and proofs. With `--secure` the
+/// content of every file is overwritten before it is unlinked.
+///
+/// # Parameters
+/// - `file_ops`: The file system operations used to find and remove the files.
+/// - `clean_command`: The parsed options of the `clean` subcommand.
+/// - `config`: The project configuration locating the files.
+///
+/// # Returns
+/// A `Result<(), WhitelistError>` which is `Ok(())` if every existing file was removed, or an
+/// `Err` otherwise.
+pub fn handle_clean_subcommand<F: FileSystemOps>(
+    file_ops: &F,
+    clean_command: &CleanCommand,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    let mut removed = 0;
+    for path in files_to_clean(config, clean_command.keep_public) {
+        if !file_ops.exists(&path) {
+            continue;
+        }
+        remove_file(file_ops, &path, clean_command.secure)?;
+        println!("Removed {}", path);
+        removed += 1;
+    }
+    println!("Removed {} generated files.", removed);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use std::time::SystemTime;
+
+    fn stub_existing(file_ops: &MockFileSystemOps, paths: &[&str]) {
+        for path in paths {
+            file_ops.stub_modified(path, SystemTime::now());
+        }
+    }
+
+    /// Tests that only existing files are removed, and public files only without `--keep-public`.
+    #[test]
+    fn test_handle_clean_subcommand() {
+        let file_ops = MockFileSystemOps::new();
+        stub_existing(
+            &file_ops,
+            &[
+                "pot12_0000.ptau",
+                "witness.wtns",
+                "circuit.circom",
+                "verifier.sol",
+            ],
+        );
+
+        let result = handle_clean_subcommand(
+            &file_ops,
+            &CleanCommand {
+                keep_public: true,
+                secure: false,
+            },
+            &Config::default(),
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(
+            file_ops.operations(),
+            vec![
+                ("remove".to_string(), "witness.wtns".to_string()),
+                ("remove".to_string(), "pot12_0000.ptau".to_string()),
+            ]
+        );
+    }
+
+    /// Tests that `--secure` overwrites every file before removing it.
+    #[test]
+    fn test_handle_clean_subcommand_with_secure() {
+        let file_ops = MockFileSystemOps::new();
+        stub_existing(&file_ops, &["circuit_0000.zkey.age", "verifier.sol"]);
+
+        let result = handle_clean_subcommand(
+            &file_ops,
+            &CleanCommand {
+                keep_public: false,
+                secure: true,
+            },
+            &Config::default(),
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(
+            file_ops.operations(),
+            vec![
+                ("overwrite".to_string(), "circuit_0000.zkey.age".to_string()),
+                ("remove".to_string(), "circuit_0000.zkey.age".to_string()),
+                ("overwrite".to_string(), "verifier.sol".to_string()),
+                ("remove".to_string(), "verifier.sol".to_string()),
+            ]
+        );
+    }
+
+    /// Tests that the scratch files of the proofs are overwritten and removed.
+    #[test]
+    fn test_remove_scratch_files() {
+        let file_ops = MockFileSystemOps::new();
+        let mut config = Config::default();
+        config.artifacts.workdir = Some("whitelist".to_string());
+        stub_existing(
+            &file_ops,
+            &["whitelist/tmp/input.json", "whitelist/private/witness.wtns"],
+        );
+
+        let warnings = remove_scratch_files(&file_ops, &config);
+
+        assert_eq!(
+            file_ops.operations(),
+            vec![
+                (
+                    "overwrite".to_string(),
+                    "whitelist/tmp/input.json".to_string()
+                ),
+                ("remove".to_string(), "whitelist/tmp/input.json".to_string()),
+                (
+                    "overwrite".to_string(),
+                    "whitelist/private/witness.wtns".to_string()
+                ),
+                (
+                    "remove".to_string(),
+                    "whitelist/private/witness.wtns".to_string()
+                ),
+            ]
+        );
+        assert!(warnings.is_empty());
+    }
+
+    /// Tests that the sources are never cleaned.
+    #[test]
+    fn test_files_to_clean() {
+        let config = Config::default();
+
+        let files = files_to_clean(&config, false);
+
+        assert!(!files.contains(&"circuit.circom".to_string()));
+        assert!(!files.contains(&"addresses.txt".to_string()));
+        assert!(files.contains(&"addresses.txt.proofs.json".to_string()));
+        assert!(files.contains(&"circuit_js".to_string()));
+        assert!(!files_to_clean(&config, true).contains(&"zkToken.sol".to_string()));
+    }
+}
@@ -1,55 +1,88 @@
-use crate::utils::command_runner::CommandRunner;
-use std::io;
+use crate::utils::{
+    command_runner::CommandRunner, config::Config, error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+};
+use std::path::Path;
 
 /// Handles the compilation of a circuit file using the `circom` compiler.
 ///
-/// This function is triggered when the `compile` subcommand is used. It prepares the necessary
+/// This function runs the `compile` subcommand through `Project::compile_circuit`. It prepares the necessary
 /// arguments for the `circom` command and executes it using the provided `CommandRunner` implementation.
+/// `circom` writes to `<name>.partial` in the artifact directory, and its outputs are only moved
+/// into place once it succeeded, so an interrupted compilation never leaves a partial `.r1cs`.
 ///
 /// # Parameters
 /// - `runner`: A reference to an implementation of the `CommandRunner` trait which will execute the `circom` command.
+/// - `file_ops`: The file system operations used to create the output directory and move the outputs.
+/// - `config`: The project configuration, which holds the circuit and the artifact directory.
 ///
 /// # Returns
-/// - A `Result` which is `Ok` if the command executes successfully, or an `Err` wrapping an `io::Error` if an error occurs.
-pub fn handle_compile_subcommand<R: CommandRunner>(runner: &R) -> io::Result<()> {
+/// - A `Result` which is `Ok` if the command executes successfully, or an `Err` wrapping a `WhitelistError` if an error occurs.
+pub fn compile_circuit<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    let name = config.circuit_name();
+    // circom needs the output directory to exist.
+    let partial_dir = config.artifact(&format!("{}.partial", name));
+    file_ops.create_dir_all(&partial_dir)?;
+
     // Prepare the arguments for the `circom` command.
-    let args = ["circuit.circom", "--r1cs", "--sym", "--wasm"];
+    let args = [
+        config.circuit.path.as_str(),
+        "--r1cs",
+        "--sym",
+        "--wasm",
+        "-o",
+        &partial_dir,
+    ];
 
     // Execute the `circom` command with the provided arguments using the `CommandRunner` implementation.
-    // Map any error to an `io::Error`.
-    runner
-        .run("circom", &args)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    if let Err(error) = runner.run("circom", &args) {
+        let _ = file_ops.remove(&partial_dir);
+        return Err(error);
+    }
 
-    Ok(())
+    for output in [
+        format!("{}.r1cs", name),
+        format!("{}.sym", name),
+        format!("{}_js", name),
+    ] {
+        let partial_output = Path::new(&partial_dir).join(&output);
+        file_ops.rename(&partial_output.to_string_lossy(), &config.artifact(&output))?;
+    }
+    file_ops.remove(&partial_dir)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::utils::command_runner::MockCommandRunner;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
 
-    /// Tests the `handle_compile_subcommand` function to ensure it executes the `circom` command
+    /// Tests the `compile_circuit` function to ensure it executes the `circom` command
     /// with the correct arguments.
     ///
     /// This test creates a `MockCommandRunner` instance to simulate the behavior of the `CommandRunner` trait.
-    /// It then calls `handle_compile_subcommand` with this mock runner, and checks the returned result to ensure
+    /// It then calls `compile_circuit` with this mock runner, and checks the returned result to ensure
     /// it is `Ok`. Finally, it checks the command and arguments passed to the `run` method of the `MockCommandRunner`
     /// to ensure they match the expected command and arguments for the `circom` compiler.
     #[test]
-    fn test_handle_compile_subcommand() {
+    fn test_compile_circuit() {
         // Create a new `MockCommandRunner` instance for testing.
         let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
 
-        // Call the `handle_compile_subcommand` function with the mock runner.
+        // Call the `compile_circuit` function with the mock runner.
         // This should internally call the `run` method of `MockCommandRunner` with the `circom` command and arguments.
-        let result = handle_compile_subcommand(&runner);
+        let result = compile_circuit(&runner, &file_ops, &Config::default());
 
         // Assert the result is `Ok`, indicating the `circom` command was executed successfully.
         assert!(result.is_ok());
 
         // Check the command and arguments passed to the `run` method of the `MockCommandRunner`.
-        // This ensures `handle_compile_subcommand` is forming the correct command and arguments for the `circom` compiler.
+        // This ensures `compile_circuit` is forming the correct command and arguments for the `circom` compiler.
         assert_eq!(
             runner.calls(),
             vec![(
@@ -59,6 +92,57 @@ mod tests {
                     "--r1cs".to_string(),
                     "--sym".to_string(),
                     "--wasm".to_string(),
+                    "-o".to_string(),
+                    "circuit.partial".to_string(),
+                ]
+            )]
+        );
+
+        // The outputs are moved out of the partial directory, which is removed.
+        let operations = file_ops.operations();
+        assert_eq!(
+            operations[1],
+            (
+                "rename".to_string(),
+                "circuit.partial/circuit.r1cs circuit.r1cs".to_string()
+            )
+        );
+        assert_eq!(
+            operations[4],
+            ("remove".to_string(), "circuit.partial".to_string())
+        );
+    }
+
+    /// Tests that the circuit and artifact directory of the configuration are passed to `circom`.
+    #[test]
+    fn test_compile_circuit_with_config() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        let mut config = Config::default();
+        config.circuit.path = "circuits/allowlist.circom".to_string();
+        config.artifacts.dir = "build".to_string();
+
+        let result = compile_circuit(&runner, &file_ops, &config);
+
+        assert!(result.is_ok());
+        assert_eq!(
+            file_ops.operations()[0],
+            (
+                "create_dir_all".to_string(),
+                "build/allowlist.partial".to_string()
+            )
+        );
+        assert_eq!(
+            runner.calls(),
+            vec![(
+                "circom".to_string(),
+                vec![
+                    "circuits/allowlist.circom".to_string(),
+                    "--r1cs".to_string(),
+                    "--sym".to_string(),
+                    "--wasm".to_string(),
+                    "-o".to_string(),
+                    "build/allowlist.partial".to_string(),
                 ]
             )]
         );
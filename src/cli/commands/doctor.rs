@@ -0,0 +1,657 @@
+use crate::utils::{
+    command_runner::{format_command_line, tool_command, CommandRunner},
+    config::{Config, ToolsConfig},
+    error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+    r1cs::parse_r1cs_header,
+    verification_key::VerificationKey,
+};
+use std::fmt;
+
+/// A version as `(major, minor, patch)`.
+type Version = (u64, u64, u64);
+
+/// An external tool the pipeline runs, with the versions it is known to work with.
+struct Tool {
+    name: &'static str,
+    /// The lowest supported version.
+    min: Version,
+    /// The first unsupported version, if any.
+    max: Option<Version>,
+    install: &'static str,
+}
+
+/// The tools checked by `doctor`. The minimum circom version matches the pragma of the circuit template.
+const TOOLS: &[Tool] = &[
+    Tool {
+        name: "circom",
+        min: (2, 1, 6),
+        max: Some((3, 0, 0)),
+        install: "see https://docs.circom.io/getting-started/installation/",
+    },
+    Tool {
+        name: "snarkjs",
+        min: (0, 7, 0),
+        max: Some((1, 0, 0)),
+        install: "run `npm install -g snarkjs`",
+    },
+    Tool {
+        name: "node",
+        min: (18, 0, 0),
+        max: None,
+        install: "see https://nodejs.org/",
+    },
+];
+
+/// The outcome of a check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    /// Everything is in place.
+    Ok,
+    /// Something has not been generated yet.
+    Todo,
+    /// Something is broken and needs fixing.
+    Fail,
+}
+
+/// A line of the checklist printed by `doctor`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub status: Status,
+    pub message: String,
+}
+
+impl Check {
+    fn new(status: Status, message: String) -> Self {
+        Check { status, message }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.status {
+            Status::Ok => "[ok]  ",
+            Status::Todo => "[todo]",
+            Status::Fail => "[fail]",
+        };
+        write!(f, "  {} {}", label, self.message)
+    }
+}
+
+/// Finds the first `major.minor.patch` version in the output of a tool, e.g. in `v20.10.0`,
+/// `circom compiler 2.1.8` or `snarkjs@0.7.4`.
+pub fn parse_version(output: &str) -> Option<Version> {
+    output
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .find_map(|candidate| {
+            let mut parts = candidate.split('.').map(|part| part.parse::<u64>().ok());
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(Some(major)), Some(Some(minor)), Some(Some(patch))) => {
+                    Some((major, minor, patch))
+                }
+                _ => None,
+            }
+        })
+}
+
+fn format_version((major, minor, patch): Version) -> String {
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+fn supported_range(tool: &Tool) -> String {
+    match tool.max {
+        Some(max) => format!(">={}, <{}", format_version(tool.min), format_version(max)),
+        None => format!(">={}", format_version(tool.min)),
+    }
+}
+
+/// Checks that every tool is installed with a supported version.
+///
+/// The tools are named with the command running them when it is configured, e.g.
+/// `snarkjs (npx --no -- snarkjs)`, followed by the working directory and the environment
+/// variables they run with.
+///
+/// # Parameters
+/// - `runner`: The command runner used to query the versions of the tools.
+/// - `file_ops`: The file system operations used to check the working directory.
+/// - `tools`: The configuration of the tools, as used by the runner.
+pub fn check_tools<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    tools: &ToolsConfig,
+) -> Vec<Check> {
+    let mut checks: Vec<Check> = TOOLS
+        .iter()
+        .map(|tool| {
+            let command = tool_command(tools, tool.name);
+            let args: Vec<&str> = command[1..].iter().map(String::as_str).collect();
+            let command_line = format_command_line(&command[0], &args);
+            let name = match command_line == tool.name {
+                true => tool.name.to_string(),
+                false => format!("{} ({})", tool.name, command_line),
+            };
+            let output = match runner.capture(tool.name, &["--version"]) {
+                Ok(output) => output,
+                Err(_) => {
+                    return Check::new(
+                        Status::Fail,
+                        format!("{} is not installed: {}", name, tool.install),
+                    )
+                }
+            };
+            match parse_version(&output) {
+                Some(version)
+                    if version < tool.min || tool.max.is_some_and(|max| version >= max) =>
+                {
+                    Check::new(
+                        Status::Fail,
+                        format!(
+                            "{} {} is not supported, install a version {}: {}",
+                            name,
+                            format_version(version),
+                            supported_range(tool),
+                            tool.install
+                        ),
+                    )
+                }
+                Some(version) => {
+                    Check::new(Status::Ok, format!("{} {}", name, format_version(version)))
+                }
+                None => Check::new(
+                    Status::Fail,
+                    format!(
+                        "the version of {} could not be read from `{} --version`",
+                        tool.name, command_line
+                    ),
+                ),
+            }
+        })
+        .collect();
+
+    if let Some(working_dir) = &tools.working_dir {
+        checks.push(match file_ops.exists(working_dir) {
+            true => Check::new(Status::Ok, format!("tools run in {}", working_dir)),
+            false => Check::new(
+                Status::Fail,
+                format!(
+                    "the working directory of the tools {} does not exist",
+                    working_dir
+                ),
+            ),
+        });
+    }
+    for (name, value) in &tools.env {
+        checks.push(Check::new(
+            Status::Ok,
+            format!("tools run with {}={}", name, value),
+        ));
+    }
+    checks
+}
+
+/// Returns whether `path` was modified before `than`, when both exist.
+fn older<F: FileSystemOps>(file_ops: &F, path: &str, than: &str) -> bool {
+    match (file_ops.modified(path), file_ops.modified(than)) {
+        (Some(modified), Some(than)) => modified < than,
+        _ => false,
+    }
+}
+
+/// Checks which project artifacts exist and whether they were generated from each other.
+///
+/// The number of public signals of the compiled circuit must match the verification key,
+/// which must match the constants of the verifier and the token, and every artifact must be
+/// newer than the one it was generated from.
+///
+/// # Parameters
+/// - `file_ops`: The file system operations used to inspect the artifacts.
+/// - `config`: The project configuration locating the artifacts.
+pub fn check_artifacts<F: FileSystemOps>(file_ops: &F, config: &Config) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let name = config.circuit_name();
+    let circuit = &config.circuit.path;
+    let r1cs = config.artifact(&format!("{}.r1cs", name));
+    let wasm = config.artifact(&format!("{}.wasm", name));
+    let compiled_wasm = config.artifact(&format!("{}_js/{}.wasm", name, name));
+    let zkey = config.secret_file(&config.zkey("0001"));
+    let verification_key = config.verification_key();
+    let verifier = config.public_file("verifier.sol");
+    let token = config.public_file("zkToken.sol");
+    let proofs = config.proofs_file(&config.input.file);
+
+    checks.push(if file_ops.exists(circuit) {
+        Check::new(Status::Ok, circuit.to_string())
+    } else {
+        Check::new(
+            Status::Todo,
+            format!(
+                "{} is missing: run `zk_whitelist circuit` or write your own",
+                circuit
+            ),
+        )
+    });
+
+    let header = match file_ops.read_bytes(&r1cs) {
+        Err(_) => {
+            checks.push(Check::new(
+                Status::Todo,
+                format!("{} is missing: run `zk_whitelist compile`", r1cs),
+            ));
+            None
+        }
+        Ok(bytes) => match parse_r1cs_header(&bytes) {
+            Err(e) => {
+                checks.push(Check::new(
+                    Status::Fail,
+                    format!("{} is invalid ({}): run `zk_whitelist compile`", r1cs, e),
+                ));
+                None
+            }
+            Ok(_) if older(file_ops, &r1cs, circuit) => {
+                checks.push(Check::new(
+                    Status::Fail,
+                    format!(
+                        "{} is older than {}: run `zk_whitelist compile`",
+                        r1cs, circuit
+                    ),
+                ));
+                None
+            }
+            Ok(header) => {
+                checks.push(Check::new(
+                    Status::Ok,
+                    format!(
+                        "{} ({} constraints, {} public signals)",
+                        r1cs,
+                        header.n_constraints,
+                        header.n_public()
+                    ),
+                ));
+                Some(header)
+            }
+        },
+    };
+
+    checks.push(if file_ops.exists(&wasm) {
+        Check::new(Status::Ok, wasm.clone())
+    } else if file_ops.exists(&compiled_wasm) {
+        Check::new(
+            Status::Todo,
+            format!("{} is missing: run `zk_whitelist movejs`", wasm),
+        )
+    } else {
+        Check::new(
+            Status::Todo,
+            format!("{} is missing: run `zk_whitelist compile`", wasm),
+        )
+    });
+
+    checks.push(if !file_ops.exists(&zkey) {
+        Check::new(
+            Status::Todo,
+            format!("{} is missing: run `zk_whitelist setup`", zkey),
+        )
+    } else if older(file_ops, &zkey, &r1cs) {
+        Check::new(
+            Status::Fail,
+            format!(
+                "{} was generated before {} was last compiled: run `zk_whitelist setup`",
+                zkey, r1cs
+            ),
+        )
+    } else {
+        Check::new(Status::Ok, zkey.clone())
+    });
+
+    if config.secrets.encrypt {
+        for secret in config.setup_secrets() {
+            if file_ops.exists(&secret) {
+                checks.push(Check::new(
+                    Status::Fail,
+                    format!(
+                        "{} is stored in plaintext although secrets are encrypted: delete it or rerun `zk_whitelist setup`",
+                        secret
+                    ),
+                ));
+            }
+        }
+    }
+
+    let key = match file_ops.read_to_string(&verification_key) {
+        Err(_) => {
+            checks.push(Check::new(
+                Status::Todo,
+                format!("{} is missing: run `zk_whitelist setup`", verification_key),
+            ));
+            None
+        }
+        Ok(content) => match VerificationKey::from_json(&content) {
+            Err(e) => {
+                checks.push(Check::new(
+                    Status::Fail,
+                    format!(
+                        "{} is invalid ({}): run `zk_whitelist setup`",
+                        verification_key, e
+                    ),
+                ));
+                None
+            }
+            Ok(key) => {
+                checks.push(match &header {
+                    Some(header) if header.n_public() != key.n_public => Check::new(
+                        Status::Fail,
+                        format!(
+                            "{} has {} public signals but {} has {}: run `zk_whitelist setup`",
+                            verification_key,
+                            key.n_public,
+                            r1cs,
+                            header.n_public()
+                        ),
+                    ),
+                    _ if older(file_ops, &verification_key, &zkey) => Check::new(
+                        Status::Fail,
+                        format!(
+                            "{} is older than {}: run `zk_whitelist setup`",
+                            verification_key, zkey
+                        ),
+                    ),
+                    _ => Check::new(Status::Ok, verification_key.clone()),
+                });
+                Some(key)
+            }
+        },
+    };
+
+    // Both contracts declare the length of `_pubSignals`, and the verifier embeds the key itself.
+    let contracts = [(&verifier, "verifier"), (&token, "token")];
+    for (contract, command) in contracts {
+        let Ok(source) = file_ops.read_to_string(contract) else {
+            checks.push(Check::new(
+                Status::Todo,
+                format!("{} is missing: run `zk_whitelist {}`", contract, command),
+            ));
+            continue;
+        };
+        let matches_key = match &key {
+            Some(key) => {
+                source.contains(&format!("uint[{}] calldata _pubSignals", key.n_public))
+                    && (command != "verifier"
+                        || source.contains(&format!("deltax1 = {};", key.delta.x[1])))
+            }
+            None => true,
+        };
+        checks.push(if matches_key {
+            Check::new(Status::Ok, contract.to_string())
+        } else {
+            Check::new(
+                Status::Fail,
+                format!(
+                    "{} does not match {}: run `zk_whitelist {}`",
+                    contract, verification_key, command
+                ),
+            )
+        });
+    }
+
+    checks.push(if file_ops.exists(&proofs) {
+        Check::new(Status::Ok, proofs.clone())
+    } else {
+        Check::new(
+            Status::Todo,
+            format!("{} is missing: run `zk_whitelist proofs`", proofs),
+        )
+    });
+
+    checks
+}
+
+/// Handles the `doctor` subcommand.
+///
+/// Prints a checklist of the installed tools and the project artifacts, with the command that
+/// fixes every item that is missing or inconsistent.
+///
+/// # Parameters
+/// - `runner`: The command runner used to query the versions of the tools.
+/// - `file_ops`: The file system operations used to inspect the artifacts.
+/// - `config`: The project configuration locating the artifacts and configuring the tools.
+///
+/// # Returns
+/// A `Result<(), WhitelistError>` which is `Ok(())` unless a tool or an artifact needs fixing.
+/// Artifacts that have not been generated yet are not errors.
+pub fn handle_doctor_subcommand<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    let tools = check_tools(runner, file_ops, &config.tools);
+    let artifacts = check_artifacts(file_ops, config);
+
+    println!("Tools");
+    tools.iter().for_each(|check| println!("{}", check));
+    println!("Project");
+    artifacts.iter().for_each(|check| println!("{}", check));
+
+    let failures = tools
+        .iter()
+        .chain(&artifacts)
+        .filter(|check| check.status == Status::Fail)
+        .count();
+    if failures > 0 {
+        return Err(WhitelistError::Other(format!(
+            "doctor found {} problem(s) to fix",
+            failures
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{
+        command_runner::MockCommandRunner, filesystem_operations::MockFileSystemOps,
+        r1cs::tests::sample_r1cs, verification_key::tests::SAMPLE_VERIFICATION_KEY,
+    };
+    use std::time::{Duration, SystemTime};
+
+    /// Stubs a file, modified `seconds` after the epoch.
+    fn stub_file(file_ops: &MockFileSystemOps, path: &str, content: &str, seconds: u64) {
+        file_ops.stub_file_content(path, content.lines().map(str::to_string).collect());
+        file_ops.stub_modified(path, SystemTime::UNIX_EPOCH + Duration::from_secs(seconds));
+    }
+
+    /// Stubs a complete project generated from the sample verification key.
+    fn stub_project(file_ops: &MockFileSystemOps) {
+        let key = VerificationKey::from_json(SAMPLE_VERIFICATION_KEY).unwrap();
+        stub_file(file_ops, "circuit.circom", "pragma circom 2.1.6;", 1);
+        file_ops.stub_bytes("circuit.r1cs", sample_r1cs(1, 1));
+        file_ops.stub_modified(
+            "circuit.r1cs",
+            SystemTime::UNIX_EPOCH + Duration::from_secs(2),
+        );
+        stub_file(file_ops, "circuit.wasm", "", 3);
+        stub_file(file_ops, "circuit_0001.zkey", "", 4);
+        stub_file(
+            file_ops,
+            "verification_key.json",
+            SAMPLE_VERIFICATION_KEY,
+            5,
+        );
+        stub_file(
+            file_ops,
+            "verifier.sol",
+            &format!(
+                "uint256 constant deltax1 = {};\nuint[2] calldata _pubSignals",
+                key.delta.x[1]
+            ),
+            6,
+        );
+        stub_file(file_ops, "zkToken.sol", "uint[2] calldata _pubSignals", 7);
+        stub_file(file_ops, "addresses.txt.proofs.json", "{}", 8);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v20.10.0\n"), Some((20, 10, 0)));
+        assert_eq!(parse_version("circom compiler 2.1.8"), Some((2, 1, 8)));
+        assert_eq!(
+            parse_version("snarkjs@0.7.4\nThis program comes with ABSOLUTELY NO WARRANTY"),
+            Some((0, 7, 4))
+        );
+        assert_eq!(parse_version("command not found"), None);
+    }
+
+    #[test]
+    fn test_check_tools() {
+        let runner = MockCommandRunner::new();
+        runner.stub_output("circom", "circom compiler 2.0.9\n");
+        runner.stub_output("snarkjs", "snarkjs@0.7.4\n");
+
+        let checks = check_tools(&runner, &MockFileSystemOps::new(), &ToolsConfig::default());
+
+        assert_eq!(
+            checks.iter().map(|check| check.status).collect::<Vec<_>>(),
+            vec![Status::Fail, Status::Ok, Status::Fail]
+        );
+        assert_eq!(
+            checks[0].message,
+            "circom 2.0.9 is not supported, install a version >=2.1.6, <3.0.0: \
+             see https://docs.circom.io/getting-started/installation/"
+        );
+        assert_eq!(checks[1].message, "snarkjs 0.7.4");
+        assert_eq!(
+            checks[2].message,
+            "node is not installed: see https://nodejs.org/"
+        );
+    }
+
+    #[test]
+    fn test_check_configured_tools() {
+        let runner = MockCommandRunner::new();
+        runner.stub_output("circom", "circom compiler 2.1.8\n");
+        runner.stub_output("node", "v20.10.0\n");
+        let tools = ToolsConfig {
+            circom: Some("/opt/circom/target/release/circom".to_string()),
+            env: [(
+                "NODE_OPTIONS".to_string(),
+                "--max-old-space-size=8192".to_string(),
+            )]
+            .into(),
+            working_dir: Some("zk_whitelist_missing_tools_dir".to_string()),
+            ..ToolsConfig::default()
+        };
+
+        let checks = check_tools(&runner, &MockFileSystemOps::new(), &tools);
+
+        assert_eq!(
+            checks[0].message,
+            "circom (/opt/circom/target/release/circom) 2.1.8"
+        );
+        assert_eq!(checks[2].message, "node 20.10.0");
+        assert_eq!(
+            checks[3],
+            Check::new(
+                Status::Fail,
+                "the working directory of the tools zk_whitelist_missing_tools_dir does not exist"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            checks[4].message,
+            "tools run with NODE_OPTIONS=--max-old-space-size=8192"
+        );
+    }
+
+    #[test]
+    fn test_check_artifacts_of_a_consistent_project() {
+        let file_ops = MockFileSystemOps::new();
+        stub_project(&file_ops);
+
+        let checks = check_artifacts(&file_ops, &Config::default());
+
+        assert!(checks.iter().all(|check| check.status == Status::Ok));
+        assert_eq!(
+            checks[1].message,
+            "circuit.r1cs (3 constraints, 2 public signals)"
+        );
+    }
+
+    #[test]
+    fn test_check_artifacts_of_a_new_project() {
+        let checks = check_artifacts(&MockFileSystemOps::new(), &Config::default());
+
+        assert!(checks.iter().all(|check| check.status == Status::Todo));
+        assert_eq!(
+            checks[1].message,
+            "circuit.r1cs is missing: run `zk_whitelist compile`"
+        );
+    }
+
+    #[test]
+    fn test_check_artifacts_of_an_inconsistent_project() {
+        let file_ops = MockFileSystemOps::new();
+        stub_project(&file_ops);
+        // The circuit was recompiled with another public signal after the setup.
+        file_ops.stub_bytes("circuit.r1cs", sample_r1cs(1, 2));
+        file_ops.stub_modified(
+            "circuit.r1cs",
+            SystemTime::UNIX_EPOCH + Duration::from_secs(9),
+        );
+        stub_file(&file_ops, "zkToken.sol", "uint[3] calldata _pubSignals", 10);
+
+        let checks = check_artifacts(&file_ops, &Config::default());
+
+        let failures: Vec<&str> = checks
+            .iter()
+            .filter(|check| check.status == Status::Fail)
+            .map(|check| check.message.as_str())
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                "circuit_0001.zkey was generated before circuit.r1cs was last compiled: run `zk_whitelist setup`",
+                "verification_key.json has 2 public signals but circuit.r1cs has 3: run `zk_whitelist setup`",
+                "zkToken.sol does not match verification_key.json: run `zk_whitelist token`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_artifacts_with_encrypted_secrets() {
+        let file_ops = MockFileSystemOps::new();
+        stub_project(&file_ops);
+        stub_file(&file_ops, "circuit_0001.zkey.age", "", 4);
+        let mut config = Config::default();
+        config.secrets.encrypt = true;
+
+        let checks = check_artifacts(&file_ops, &config);
+
+        assert!(checks.contains(&Check::new(Status::Ok, "circuit_0001.zkey.age".to_string())));
+        assert!(checks.contains(&Check::new(
+            Status::Fail,
+            "circuit_0001.zkey is stored in plaintext although secrets are encrypted: \
+             delete it or rerun `zk_whitelist setup`"
+                .to_string()
+        )));
+    }
+
+    #[test]
+    fn test_handle_doctor_subcommand() {
+        let runner = MockCommandRunner::new();
+        runner.stub_output("circom", "circom compiler 2.1.8\n");
+        runner.stub_output("snarkjs", "snarkjs@0.7.4\n");
+        runner.stub_output("node", "v20.10.0\n");
+        let file_ops = MockFileSystemOps::new();
+
+        assert!(handle_doctor_subcommand(&runner, &file_ops, &Config::default()).is_ok());
+
+        stub_file(&file_ops, "verifier.sol", "contract Other {}", 1);
+        stub_file(
+            &file_ops,
+            "verification_key.json",
+            SAMPLE_VERIFICATION_KEY,
+            0,
+        );
+        assert!(handle_doctor_subcommand(&runner, &file_ops, &Config::default()).is_err());
+    }
+}
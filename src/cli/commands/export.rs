@@ -0,0 +1,376 @@
+use crate::cli::ExportCommand;
+use crate::utils::{
+    config::Config, error::WhitelistError, filesystem_operations::FileSystemOps,
+    template_renderer::render_template, verification_key::VerificationKey,
+};
+use flate2::{write::GzEncoder, Compression};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{io, path::Path};
+
+/// The name of the manifest of hashes in the bundle, in the format of `sha256sum`.
+const MANIFEST_FILE_NAME: &str = "MANIFEST.sha256";
+
+/// The extensions of the files that must never be published: the proving keys, the powers of
+/// tau, the witnesses and their encrypted copies.
+const SECRET_EXTENSIONS: &[&str] = &["zkey", "ptau", "wtns", "age"];
+
+/// A file of the bundle.
+#[derive(Serialize, Debug, PartialEq)]
+struct BundleFile {
+    /// The path of the file in the project.
+    #[serde(skip)]
+    path: String,
+    /// The name of the file in the bundle.
+    name: String,
+    description: String,
+}
+
+/// The variables available to the README template.
+#[derive(Serialize)]
+struct ReleaseContext<'a> {
+    circuit_name: &'a str,
+    version: &'a str,
+    files: &'a [BundleFile],
+    n_public: usize,
+    proofs_file: &'a str,
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Checks that a file holds no secret and may be published.
+///
+/// Refuses any path with a secret extension in one of its components, whatever the case and
+/// whatever follows it, so that partial, temporary and encrypted copies of the zkeys, ptau files
+/// and witnesses are refused too. Also refuses the input file listing the whitelisted
+/// addresses and the scratch files of the proofs.
+///
+/// # Returns
+/// `Ok(())` if the file is public, or `WhitelistError::InvalidInput` naming the file otherwise.
+pub fn check_public(path: &str, config: &Config) -> Result<(), WhitelistError> {
+    let name = file_name(path).to_lowercase();
+    let secret_extension = Path::new(path).components().any(|component| {
+        component
+            .as_os_str()
+            .to_string_lossy()
+            .to_lowercase()
+            .split('.')
+            .skip(1)
+            .any(|extension| SECRET_EXTENSIONS.contains(&extension))
+    });
+    let private_file = [config.input.file.as_str(), "input.json", "witness.json"]
+        .into_iter()
+        .map(str::to_string)
+        .chain(config.scratch_files())
+        .any(|private| file_name(&private).to_lowercase() == name);
+    if secret_extension || private_file {
+        return Err(WhitelistError::InvalidInput(format!(
+            "Refusing to export {}, which must stay private",
+            path
+        )));
+    }
+    Ok(())
+}
+
+/// Lists the public artifacts of the project that go into the bundle, followed by `include`.
+///
+/// The verifier, the verification key and the proofs are required, the Rust verifier and the
+/// token contracts are added when they exist.
+fn bundle_files<F: FileSystemOps>(
+    file_ops: &F,
+    config: &Config,
+    include: &[String],
+) -> Result<Vec<BundleFile>, WhitelistError> {
+    let required = [
+        (config.public_file("verifier.sol"), "The Solidity verifier"),
+        (config.verification_key(), "The verification key"),
+        (
+            config.proofs_file(&config.input.file),
+            "The proof of each whitelisted address",
+        ),
+    ];
+    let optional = [
+        (config.public_file("verifier.rs"), "The Rust verifier"),
+        (config.public_file("zkToken.sol"), "The token contract"),
+        (
+            config.public_file("zkWhitelisted.sol"),
+            "The whitelist library",
+        ),
+    ];
+
+    let mut files = Vec::new();
+    for (path, description) in required {
+        if !file_ops.exists(&path) {
+            return Err(WhitelistError::ArtifactMissing(path));
+        }
+        files.push((path, description.to_string()));
+    }
+    for (path, description) in optional {
+        if file_ops.exists(&path) {
+            files.push((path, description.to_string()));
+        }
+    }
+    for path in include {
+        files.push((path.clone(), "Included with `--include`".to_string()));
+    }
+
+    let mut bundle_files: Vec<BundleFile> = Vec::new();
+    for (path, description) in files {
+        check_public(&path, config)?;
+        let name = file_name(&path);
+        if name == "README.md" || name == MANIFEST_FILE_NAME {
+            return Err(WhitelistError::InvalidInput(format!(
+                "{} is generated by the export",
+                name
+            )));
+        }
+        if bundle_files.iter().any(|file| file.name == name) {
+            return Err(WhitelistError::InvalidInput(format!(
+                "Two files of the bundle are named {}",
+                name
+            )));
+        }
+        bundle_files.push(BundleFile {
+            path,
+            name,
+            description,
+        });
+    }
+    Ok(bundle_files)
+}
+
+/// Appends a file to the archive with fixed metadata, so the same files give the same bundle.
+fn append_file<W: io::Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    content: &[u8],
+) -> Result<(), WhitelistError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(0);
+    builder
+        .append_data(&mut header, name, content)
+        .map_err(|e| WhitelistError::io(name, e))
+}
+
+/// Builds the release bundle of the project as a gzipped tarball.
+///
+/// The bundle holds the public artifacts, a `MANIFEST.sha256` with the hash of each of them
+/// and a README describing how to verify the bundle.
+///
+/// # Parameters
+/// - `file_ops`: The file system operations used to read the artifacts.
+/// - `config`: The project configuration locating the artifacts.
+/// - `include`: Additional public files, such as release notes.
+///
+/// # Returns
+/// The content of the bundle and the names of its files, or an error if an artifact is
+/// missing or a file must stay private.
+pub fn build_bundle<F: FileSystemOps>(
+    file_ops: &F,
+    config: &Config,
+    include: &[String],
+) -> Result<(Vec<u8>, Vec<String>), WhitelistError> {
+    let files = bundle_files(file_ops, config, include)?;
+    let verification_key =
+        VerificationKey::from_json(&file_ops.read_to_string(&config.verification_key())?)
+            .map_err(WhitelistError::ArtifactMismatch)?;
+    let readme = render_template(
+        "release_README.md",
+        include_str!("../../../templates/release_README.md.jinja"),
+        ReleaseContext {
+            circuit_name: &config.circuit_name(),
+            version: env!("CARGO_PKG_VERSION"),
+            files: &files,
+            n_public: verification_key.n_public,
+            proofs_file: &file_name(&config.proofs_file(&config.input.file)),
+        },
+    )
+    .map_err(WhitelistError::Other)?;
+
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    let mut manifest = String::new();
+    let mut names = Vec::new();
+    for file in &files {
+        let content = file_ops.read_bytes(&file.path)?;
+        manifest.push_str(&format!(
+            "{}  {}\n",
+            hex::encode(Sha256::digest(&content)),
+            file.name
+        ));
+        append_file(&mut builder, &file.name, &content)?;
+        names.push(file.name.clone());
+    }
+    append_file(&mut builder, MANIFEST_FILE_NAME, manifest.as_bytes())?;
+    append_file(&mut builder, "README.md", readme.as_bytes())?;
+    names.extend([MANIFEST_FILE_NAME.to_string(), "README.md".to_string()]);
+
+    let bundle = builder.into_inner().and_then(GzEncoder::finish)?;
+    Ok((bundle, names))
+}
+
+/// Handles the `export` subcommand.
+///
+/// Packages the public artifacts into the tarball given by `--out`, refusing any secret.
+///
+/// # Parameters
+/// - `file_ops`: The file system operations used to read the artifacts and write the bundle.
+/// - `export_command`: The parsed options of the `export` subcommand.
+/// - `config`: The project configuration locating the artifacts.
+///
+/// # Returns
+/// A `Result<(), WhitelistError>` which is `Ok(())` if the bundle was written, or an `Err`
+/// otherwise.
+pub fn handle_export_subcommand<F: FileSystemOps>(
+    file_ops: &F,
+    export_command: &ExportCommand,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    let (bundle, names) = build_bundle(file_ops, config, &export_command.include)?;
+    file_ops.write_bytes(&export_command.out, &bundle)?;
+    println!("Exported {} with:", export_command.out);
+    for name in names {
+        println!("  {}", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use crate::utils::verification_key::tests::SAMPLE_VERIFICATION_KEY;
+    use flate2::read::GzDecoder;
+    use std::{io::Read, time::SystemTime};
+
+    fn project_with_artifacts() -> MockFileSystemOps {
+        let file_ops = MockFileSystemOps::new();
+        for (path, content) in [
+            ("verifier.sol", "contract Groth16Verifier {}"),
+            ("verification_key.json", SAMPLE_VERIFICATION_KEY),
+            ("addresses.txt.proofs.json", "{}"),
+            ("zkToken.sol", "contract ZKToken {}"),
+        ] {
+            file_ops.stub_bytes(path, content.as_bytes().to_vec());
+            file_ops.stub_file_content(path, vec![content.to_string()]);
+            file_ops.stub_modified(path, SystemTime::now());
+        }
+        file_ops
+    }
+
+    fn unpack(bundle: &[u8]) -> Vec<(String, String)> {
+        let mut archive = tar::Archive::new(GzDecoder::new(bundle));
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                (entry.path().unwrap().to_string_lossy().to_string(), content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_build_bundle() {
+        // Arrange
+        let file_ops = project_with_artifacts();
+
+        // Act
+        let (bundle, names) = build_bundle(&file_ops, &Config::default(), &[]).unwrap();
+
+        // Assert
+        assert_eq!(
+            names,
+            vec![
+                "verifier.sol",
+                "verification_key.json",
+                "addresses.txt.proofs.json",
+                "zkToken.sol",
+                "MANIFEST.sha256",
+                "README.md",
+            ]
+        );
+        let entries = unpack(&bundle);
+        let manifest = &entries[4].1;
+        assert_eq!(manifest.lines().count(), 4);
+        assert!(manifest.contains(&format!(
+            "{}  verifier.sol",
+            hex::encode(Sha256::digest(b"contract Groth16Verifier {}"))
+        )));
+        assert!(entries[5]
+            .1
+            .contains("| `zkToken.sol` | The token contract |"));
+        assert!(entries[5].1.contains("`nPublic` (2)"));
+    }
+
+    #[test]
+    fn test_build_bundle_is_reproducible() {
+        let file_ops = project_with_artifacts();
+
+        let (first, _) = build_bundle(&file_ops, &Config::default(), &[]).unwrap();
+        let (second, _) = build_bundle(&file_ops, &Config::default(), &[]).unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_build_bundle_refuses_secrets() {
+        let file_ops = project_with_artifacts();
+
+        for secret in [
+            "circuit_0001.zkey",
+            "pot12_final.ptau",
+            "build/witness.wtns",
+            "circuit_0001.zkey.age",
+            "addresses.txt",
+        ] {
+            let result = build_bundle(&file_ops, &Config::default(), &[secret.to_string()]);
+
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                format!("Refusing to export {}, which must stay private", secret)
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_public() {
+        let config = Config::default();
+
+        for secret in [
+            "circuit_0001.zkey.partial",
+            "pot12_final.ptau.4242.0.tmp",
+            "KEY.ZKEY",
+            "keys.age/verifier.sol",
+            "witness.json",
+            "Input.JSON",
+            "public.json",
+            "proof.json",
+        ] {
+            assert!(check_public(secret, &config).is_err(), "{}", secret);
+        }
+        for public in ["verifier.sol", "verification_key.json", "zkeys/README.md"] {
+            assert!(check_public(public, &config).is_ok(), "{}", public);
+        }
+    }
+
+    #[test]
+    fn test_build_bundle_without_verifier() {
+        let file_ops = MockFileSystemOps::new();
+
+        let result = build_bundle(&file_ops, &Config::default(), &[]);
+
+        assert!(matches!(
+            result,
+            Err(WhitelistError::ArtifactMissing(path)) if path == "verifier.sol"
+        ));
+    }
+}
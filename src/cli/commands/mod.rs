@@ -1,8 +1,14 @@
 pub mod all;
 pub mod circuit;
+pub mod clean;
 pub mod compile;
+pub mod doctor;
+pub mod export;
 pub mod movejs;
 pub mod proofs;
+pub mod scaffold;
+pub mod serve;
 pub mod setup;
+pub mod simulate;
 pub mod token;
 pub mod verifier;
@@ -1,24 +1,27 @@
 /// movejs.rs
-use crate::utils::filesystem_operations::FileSystemOps;
-use std::io;
+use crate::utils::{config::Config, error::WhitelistError, filesystem_operations::FileSystemOps};
 
 /// Handles the `movejs` subcommand by moving the contents
-/// of the `circuit_js` directory to the current directory.
+/// of the `circuit_js` directory to the artifact directory, the current directory by default.
 ///
 /// This function accepts a generic parameter `F` which implements
 /// the `FileSystemOps` trait, allowing for the move operation to be handled by `fs_ops`.
 ///
 /// # Parameters
 /// - `fs_ops`: The file system operations runner which will perform the move operation.
+/// - `config`: The project configuration, which holds the circuit name and the artifact directory.
 ///
 /// # Returns
-/// - An `io::Result<()>` which will be `Ok(())` if the move operation completes successfully,
-///   or an `Err` wrapping an `io::Error` if an error occurs.
-pub fn handle_movejs_subcommand<F: FileSystemOps>(fs_ops: &F) -> io::Result<()> {
-    // Call the move_item method with fs_ops to move the contents of circuit_js to the current directory.
-    fs_ops
-        .move_item("circuit_js", ".")
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+/// - A `Result<(), WhitelistError>` which will be `Ok(())` if the move operation completes
+///   successfully, or an `Err` with `WhitelistError::ArtifactMissing` if the circuit was not compiled.
+pub fn handle_movejs_subcommand<F: FileSystemOps>(
+    fs_ops: &F,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    // circom writes the witness generator of `<name>.circom` to `<name>_js`.
+    let js_dir = config.artifact(&format!("{}_js", config.circuit_name()));
+    // Call the move_item method with fs_ops to move the contents of circuit_js to the artifact directory.
+    fs_ops.move_item(&js_dir, &config.artifact_dir())
 }
 
 #[cfg(test)]
@@ -38,7 +41,7 @@ mod tests {
         let fs_ops = MockFileSystemOps::new();
 
         // Call the function with the mock fs_ops.
-        let result = handle_movejs_subcommand(&fs_ops); // Now passing a reference
+        let result = handle_movejs_subcommand(&fs_ops, &Config::default()); // Now passing a reference
 
         // Assert the result is Ok(()) indicating success.
         assert!(result.is_ok());
@@ -49,4 +52,40 @@ mod tests {
             vec![("circuit_js".to_string(), ".".to_string())]
         );
     }
+
+    /// Tests that the witness generator is moved within the configured artifact directory.
+    #[test]
+    fn test_handle_movejs_subcommand_with_config() {
+        let fs_ops = MockFileSystemOps::new();
+        let mut config = Config::default();
+        config.circuit.path = "circuits/allowlist.circom".to_string();
+        config.artifacts.dir = "build".to_string();
+
+        let result = handle_movejs_subcommand(&fs_ops, &config);
+
+        assert!(result.is_ok());
+        assert_eq!(
+            fs_ops.operations(),
+            vec![("build/allowlist_js".to_string(), "build".to_string())]
+        );
+    }
+
+    /// Tests that the witness generator stays in the private directory of a working directory.
+    #[test]
+    fn test_handle_movejs_subcommand_with_workdir() {
+        let fs_ops = MockFileSystemOps::new();
+        let mut config = Config::default();
+        config.artifacts.workdir = Some("whitelist".to_string());
+
+        let result = handle_movejs_subcommand(&fs_ops, &config);
+
+        assert!(result.is_ok());
+        assert_eq!(
+            fs_ops.operations(),
+            vec![(
+                "whitelist/private/circuit_js".to_string(),
+                "whitelist/private".to_string()
+            )]
+        );
+    }
 }
@@ -0,0 +1,363 @@
+use super::token::{proof_function, DEFAULT_TOKEN_CONTRACT};
+use crate::cli::{ScaffoldCommand, ScaffoldTarget, TokenKind};
+use crate::utils::{
+    config::Config,
+    error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+    proofs_file::{hex_to_decimal, read_proofs_file, ProofEntry},
+    solidity::validate_identifier,
+    template_renderer::render_template,
+};
+use revm::primitives::Address;
+use serde::Serialize;
+use std::{collections::BTreeMap, path::Path};
+
+/// A proof of the proofs file, with decimal values and a checksummed address.
+#[derive(Serialize)]
+struct TestProof {
+    address: String,
+    p_a: Vec<String>,
+    p_b: Vec<Vec<String>>,
+    p_c: Vec<String>,
+    input: Vec<String>,
+}
+
+/// The variables available to the Foundry test template.
+#[derive(Serialize)]
+struct FoundryTestContext<'a> {
+    contract_name: &'a str,
+    function: &'a str,
+    token_file: &'a str,
+    airdrop: bool,
+    n_public: usize,
+    proofs: Vec<TestProof>,
+}
+
+fn to_decimals(values: &[String]) -> Result<Vec<String>, String> {
+    values.iter().map(|value| hex_to_decimal(value)).collect()
+}
+
+fn to_test_proof(address: &str, entry: &ProofEntry) -> Result<TestProof, String> {
+    // Solidity only accepts address literals with an EIP-55 checksum.
+    let address: Address = address
+        .parse()
+        .map_err(|e| format!("Invalid address {} in proofs file: {}", address, e))?;
+    Ok(TestProof {
+        address: address.to_checksum(None),
+        p_a: to_decimals(&entry.p_a)?,
+        p_b: entry
+            .p_b
+            .iter()
+            .map(|row| to_decimals(row))
+            .collect::<Result<_, _>>()?,
+        p_c: to_decimals(&entry.p_c)?,
+        input: to_decimals(&entry.input)?,
+    })
+}
+
+/// Renders a Foundry test suite for the token from the proofs file.
+///
+/// Every address gets a test minting with its own proof under `vm.prank`. The first proof is
+/// also used to check that a replay, a call from another sender and a tampered proof revert.
+/// An airdrop is deployed with a `TestToken` and funded with its whole supply, and every
+/// claim checks the balance received.
+///
+/// # Parameters
+/// - `proofs`: The proofs to test with, keyed by address.
+/// - `scaffold_command`: The parsed options of the `scaffold` subcommand.
+/// - `contract_name`: The name of the token contract under test.
+/// - `token_file`: The file name of the token in the `src` directory.
+///
+/// # Returns
+/// The Solidity source of the test suite, or an error message if there are no proofs or a
+/// value is invalid.
+pub fn render_foundry_test(
+    proofs: &BTreeMap<String, ProofEntry>,
+    scaffold_command: &ScaffoldCommand,
+    contract_name: &str,
+    token_file: &str,
+) -> Result<String, String> {
+    let function = scaffold_command
+        .function
+        .as_deref()
+        .unwrap_or(proof_function(scaffold_command.kind));
+    validate_identifier("contract name", contract_name)?;
+    validate_identifier("function name", function)?;
+    if scaffold_command.kind == TokenKind::Library {
+        return Err(
+            "The library is abstract and cannot be deployed, scaffold the contract inheriting it"
+                .to_string(),
+        );
+    }
+    let n_public = match proofs.values().next() {
+        Some(entry) => entry.input.len(),
+        None => return Err("The proofs file has no proofs".to_string()),
+    };
+    if proofs.values().any(|entry| entry.input.len() != n_public) {
+        return Err("Every proof must have the same number of public signals".to_string());
+    }
+
+    render_template(
+        "foundry_test.t.sol",
+        include_str!("../../../templates/foundry_test.t.sol.jinja"),
+        FoundryTestContext {
+            contract_name,
+            function,
+            token_file,
+            airdrop: scaffold_command.kind == TokenKind::Airdrop,
+            n_public,
+            proofs: proofs
+                .iter()
+                .map(|(address, entry)| to_test_proof(address, entry))
+                .collect::<Result<_, _>>()?,
+        },
+    )
+}
+
+/// Joins a path below the output directory into a string usable with `FileSystemOps`.
+fn output_path(out_dir: &str, parts: &[&str]) -> String {
+    parts
+        .iter()
+        .fold(Path::new(out_dir).to_path_buf(), |path, part| {
+            path.join(part)
+        })
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Copies a generated contract into the `src` directory of the project.
+///
+/// # Returns
+/// The file name of the contract, or an error if it cannot be read or written.
+fn copy_to_src<F: FileSystemOps>(
+    file_ops: &F,
+    out_dir: &str,
+    file: &str,
+) -> Result<String, WhitelistError> {
+    let file_name = Path::new(file)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| WhitelistError::InvalidInput(format!("{} is not a file", file)))?;
+    let content = file_ops.read_to_string(file)?;
+    file_ops.write_to_file(&output_path(out_dir, &["src", &file_name]), &content)?;
+    Ok(file_name)
+}
+
+/// Generates a Foundry project with `foundry.toml`, the contracts in `src` and tests in `test`.
+fn scaffold_foundry<F: FileSystemOps>(
+    file_ops: &F,
+    scaffold_command: &ScaffoldCommand,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    let proofs_file = scaffold_command
+        .proofs
+        .clone()
+        .unwrap_or_else(|| config.proofs_file(&config.input.file));
+    let contract = scaffold_command
+        .contract
+        .as_deref()
+        .or(config.contracts.token.as_deref())
+        .unwrap_or(DEFAULT_TOKEN_CONTRACT);
+    let proofs = read_proofs_file(file_ops, config, &proofs_file)?;
+    let out_dir = &scaffold_command.out_dir;
+
+    file_ops.create_dir_all(&output_path(out_dir, &["src"]))?;
+    file_ops.create_dir_all(&output_path(out_dir, &["test"]))?;
+    file_ops.write_to_file(
+        &output_path(out_dir, &["foundry.toml"]),
+        include_str!("../../../templates/foundry.toml"),
+    )?;
+    let verifier = scaffold_command
+        .verifier
+        .clone()
+        .unwrap_or_else(|| config.public_file("verifier.sol"));
+    let token = scaffold_command
+        .token
+        .clone()
+        .unwrap_or_else(|| config.public_file("zkToken.sol"));
+    copy_to_src(file_ops, out_dir, &verifier)?;
+    let token_file = copy_to_src(file_ops, out_dir, &token)?;
+
+    let test = render_foundry_test(&proofs, scaffold_command, contract, &token_file)
+        .map_err(WhitelistError::InvalidInput)?;
+    let test_file = format!("{}.t.sol", contract);
+    file_ops.write_to_file(&output_path(out_dir, &["test", &test_file]), &test)?;
+    if scaffold_command.kind == TokenKind::Airdrop {
+        file_ops.write_to_file(
+            &output_path(out_dir, &["test", "TestToken.sol"]),
+            include_str!("../../../templates/TestToken.sol"),
+        )?;
+    }
+
+    println!(
+        "Foundry project written to {} with tests for {} proofs.",
+        out_dir,
+        proofs.len()
+    );
+    println!("Install the dependencies and run the tests with:");
+    println!("  forge install foundry-rs/forge-std OpenZeppelin/openzeppelin-contracts --no-git");
+    println!("  forge test");
+    Ok(())
+}
+
+/// Handles the `scaffold` subcommand.
+///
+/// Generates a project for a smart contract development framework around the verifier and
+/// token, with tests using the real proofs of the proofs file.
+///
+/// # Parameters
+/// - `file_ops`: The file system operations used to read the inputs and write the project.
+/// - `scaffold_command`: The parsed options of the `scaffold` subcommand.
+/// - `config`: The project configuration, which provides the default proofs file, contracts
+///   and token name.
+///
+/// # Returns
+/// A `Result<(), WhitelistError>` which is `Ok(())` if the project was written, or an `Err`
+/// otherwise.
+pub fn handle_scaffold_subcommand<F: FileSystemOps>(
+    file_ops: &F,
+    scaffold_command: &ScaffoldCommand,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    match scaffold_command.target {
+        ScaffoldTarget::Foundry => scaffold_foundry(file_ops, scaffold_command, config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use crate::utils::proofs_file::{parse_proofs_file, tests::SAMPLE_PROOFS_FILE};
+
+    fn stub_files(file_ops: &MockFileSystemOps) {
+        file_ops.stub_file_content(
+            "addresses.txt.proofs.json",
+            SAMPLE_PROOFS_FILE.lines().map(str::to_string).collect(),
+        );
+        file_ops.stub_file_content(
+            "verifier.sol",
+            vec!["contract Groth16Verifier {}".to_string()],
+        );
+        file_ops.stub_file_content("zkToken.sol", vec!["contract ZKToken {}".to_string()]);
+    }
+
+    /// Tests that the project layout is written below the output directory.
+    #[test]
+    fn test_handle_scaffold_subcommand() {
+        let file_ops = MockFileSystemOps::new();
+        stub_files(&file_ops);
+        let scaffold_command = ScaffoldCommand {
+            out_dir: "project".to_string(),
+            ..ScaffoldCommand::default()
+        };
+
+        let result = handle_scaffold_subcommand(&file_ops, &scaffold_command, &Config::default());
+
+        assert!(result.is_ok());
+        assert_eq!(
+            file_ops.operations(),
+            vec![
+                ("create_dir_all".to_string(), "project/src".to_string()),
+                ("create_dir_all".to_string(), "project/test".to_string()),
+            ]
+        );
+        assert!(file_ops
+            .get_written_content("project/foundry.toml")
+            .unwrap()
+            .contains("\"@openzeppelin/=lib/openzeppelin-contracts/\","));
+        assert_eq!(
+            file_ops.get_written_content("project/src/verifier.sol"),
+            Some("contract Groth16Verifier {}".to_string())
+        );
+        assert_eq!(
+            file_ops.get_written_content("project/src/zkToken.sol"),
+            Some("contract ZKToken {}".to_string())
+        );
+        assert!(file_ops
+            .get_written_content("project/test/ZKToken.t.sol")
+            .is_some());
+        assert!(file_ops
+            .get_written_content("project/test/TestToken.sol")
+            .is_none());
+    }
+
+    /// Tests that every proof gets a mint test and the first one the revert tests.
+    #[test]
+    fn test_render_foundry_test() {
+        let proofs = parse_proofs_file(SAMPLE_PROOFS_FILE).unwrap();
+
+        let test = render_foundry_test(
+            &proofs,
+            &ScaffoldCommand::default(),
+            "ZKToken",
+            "zkToken.sol",
+        )
+        .unwrap();
+
+        assert!(test.contains("import \"../src/zkToken.sol\";"));
+        assert!(test.contains("contract ZKTokenTest is Test {"));
+        assert!(test.contains("uint[2] pubSignals;"));
+        assert!(test.contains("account: 0x1234567890AbcdEF1234567890aBcdef12345678,"));
+        assert!(test.contains("pB: [[uint(19), 20], [uint(21), 22]],"));
+        assert!(test
+            .contains("pubSignals: [uint(1), 103929005307927756724354605802047639613112342136]"));
+        assert!(test.contains("function test_mint_proof0() public {"));
+        assert!(test.contains("function test_mint_proof1() public {"));
+        assert!(test.contains("vm.expectRevert(\"Tokens already claimed\");"));
+        assert!(test.contains("vm.expectRevert(\"Not your proof or invalid input\");"));
+        assert!(test.contains("vm.expectRevert(\"Invalid proof\");"));
+        assert!(test.contains("return token.mint(proof.pA, proof.pB, proof.pC, proof.pubSignals);"));
+        assert!(test.contains("token = new ZKToken();"));
+        assert!(!test.contains("TestToken"));
+    }
+
+    /// Tests that an airdrop is deployed with a funded token and claimed with every proof.
+    #[test]
+    fn test_render_foundry_test_for_airdrop() {
+        let proofs = parse_proofs_file(SAMPLE_PROOFS_FILE).unwrap();
+        let scaffold_command = ScaffoldCommand {
+            kind: TokenKind::Airdrop,
+            ..ScaffoldCommand::default()
+        };
+
+        let test =
+            render_foundry_test(&proofs, &scaffold_command, "ZKToken", "zkToken.sol").unwrap();
+
+        assert!(test.contains("import \"./TestToken.sol\";"));
+        assert!(test.contains("token = new ZKToken(airdropped);"));
+        assert!(test.contains("airdropped.transfer(address(token), airdropped.totalSupply());"));
+        assert!(test.contains("function test_claim_proof0() public {"));
+        assert!(test.contains("assertEq(airdropped.balanceOf(proof.account), token.amount());"));
+        assert!(
+            test.contains("return token.claim(proof.pA, proof.pB, proof.pC, proof.pubSignals);")
+        );
+    }
+
+    /// Tests that the abstract library is rejected.
+    #[test]
+    fn test_render_foundry_test_for_library() {
+        let proofs = parse_proofs_file(SAMPLE_PROOFS_FILE).unwrap();
+        let scaffold_command = ScaffoldCommand {
+            kind: TokenKind::Library,
+            ..ScaffoldCommand::default()
+        };
+
+        let result = render_foundry_test(&proofs, &scaffold_command, "ZKWhitelisted", "a.sol");
+
+        assert!(result.unwrap_err().contains("The library is abstract"));
+    }
+
+    /// Tests that an empty proofs file is rejected.
+    #[test]
+    fn test_render_foundry_test_without_proofs() {
+        let result = render_foundry_test(
+            &BTreeMap::new(),
+            &ScaffoldCommand::default(),
+            "ZKToken",
+            "zkToken.sol",
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,534 @@
+use super::proofs::{generate_proofs, is_address, print_warnings, write_proofs_file};
+use super::simulate::encode_mint_call;
+use crate::cli::ServeCommand;
+use crate::utils::{
+    command_runner::CommandRunner,
+    config::Config,
+    error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+    progress::ProofEvent,
+    proofs_file::{read_proofs_file, ProofEntry},
+    secrets::SecretKey,
+};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// The largest request body read, plenty for the address of `POST /prove`.
+const MAX_BODY_SIZE: u64 = 4096;
+
+/// A response of the proof server: an HTTP status and a JSON body, empty when `Null`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl ApiResponse {
+    fn new(status: u16, body: Value) -> Self {
+        ApiResponse { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        ApiResponse::new(status, json!({ "error": message }))
+    }
+}
+
+/// Compares two tokens in a time independent of where they differ.
+fn tokens_match(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len()
+        && expected
+            .bytes()
+            .zip(actual.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// Serves the proofs of a proofs file, and proves new addresses on demand when an admin token
+/// is set.
+///
+/// Requests are handled one at a time, so a `POST /prove` holds the other requests until its
+/// proof is generated.
+pub struct ProofServer<'a, R, F> {
+    runner: &'a R,
+    file_ops: &'a F,
+    serve_command: &'a ServeCommand,
+    config: &'a Config,
+    proofs_file: String,
+    proofs: BTreeMap<String, ProofEntry>,
+    /// The key decrypting an encrypted zkey, loaded once when `POST /prove` is enabled.
+    key: Option<SecretKey>,
+    headers: Vec<Header>,
+}
+
+impl<'a, R: CommandRunner, F: FileSystemOps> ProofServer<'a, R, F> {
+    /// Loads the proofs file of the server, and the key of an encrypted zkey when `POST /prove`
+    /// is enabled, so that requests never prompt for a passphrase.
+    ///
+    /// # Parameters
+    /// - `runner`: The command runner proving new addresses.
+    /// - `file_ops`: The file system operations used to read and update the proofs file.
+    /// - `serve_command`: The parsed options of the `serve` subcommand.
+    /// - `config`: The project configuration locating the proofs file and the zkey.
+    ///
+    /// # Returns
+    /// The server, `WhitelistError::ArtifactMismatch` if the proofs file is invalid, or
+    /// `WhitelistError::InvalidInput` if the CORS origin is not a valid header value or the
+    /// zkey is encrypted without a keyfile or `ZK_WHITELIST_PASSPHRASE`.
+    pub fn new(
+        runner: &'a R,
+        file_ops: &'a F,
+        serve_command: &'a ServeCommand,
+        config: &'a Config,
+    ) -> Result<Self, WhitelistError> {
+        let proofs_file = serve_command
+            .proofs
+            .clone()
+            .unwrap_or_else(|| config.proofs_file(&config.input.file));
+        let proofs = read_proofs_file(file_ops, config, &proofs_file)?;
+        let key = match serve_command.admin_token.is_some() && config.secrets.encrypt {
+            true => Some(SecretKey::load_without_prompt(file_ops, &config.secrets)?),
+            false => None,
+        };
+
+        let mut headers = Vec::new();
+        for (field, value) in [
+            ("Content-Type", "application/json"),
+            (
+                "Access-Control-Allow-Origin",
+                serve_command.cors_origin.as_str(),
+            ),
+            ("Access-Control-Allow-Methods", "GET, POST, OPTIONS"),
+            (
+                "Access-Control-Allow-Headers",
+                "Authorization, Content-Type",
+            ),
+        ] {
+            let header = Header::from_bytes(field, value).map_err(|_| {
+                WhitelistError::InvalidInput(format!("Invalid {} header {:?}", field, value))
+            })?;
+            headers.push(header);
+        }
+
+        Ok(ProofServer {
+            runner,
+            file_ops,
+            serve_command,
+            config,
+            proofs_file,
+            proofs,
+            key,
+            headers,
+        })
+    }
+
+    /// Returns the proof of an address, whatever the case of its hex digits.
+    fn find(&self, address: &str) -> Option<&ProofEntry> {
+        self.proofs
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(address))
+            .map(|(_, entry)| entry)
+    }
+
+    /// Handles a request to the server.
+    ///
+    /// # Parameters
+    /// - `method`: The HTTP method of the request.
+    /// - `url`: The path of the request, with its query string.
+    /// - `authorization`: The value of the `Authorization` header, if any.
+    /// - `body`: The body of the request.
+    ///
+    /// # Returns
+    /// The status and JSON body of the response.
+    pub fn handle(
+        &mut self,
+        method: &Method,
+        url: &str,
+        authorization: Option<&str>,
+        body: &str,
+    ) -> ApiResponse {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        match (method, path) {
+            // The preflight request of browsers, answered by the CORS headers of every response.
+            (Method::Options, _) => ApiResponse::new(204, Value::Null),
+            (Method::Get, "/health") => {
+                ApiResponse::new(200, json!({ "status": "ok", "proofs": self.proofs.len() }))
+            }
+            (Method::Get, path) if path.starts_with("/proof/") => {
+                self.get_proof(&path["/proof/".len()..], query)
+            }
+            (Method::Post, "/prove") => self.prove(authorization, body),
+            (_, "/health" | "/prove") => ApiResponse::error(405, "Method not allowed"),
+            _ => ApiResponse::error(404, "Not found"),
+        }
+    }
+
+    /// Handles `GET /proof/{address}`, returning the proof entry, or the calldata of the
+    /// function with `?format=calldata`.
+    fn get_proof(&self, address: &str, query: &str) -> ApiResponse {
+        let format = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("format="))
+            .unwrap_or("json");
+        if !is_address(address) {
+            return ApiResponse::error(400, &format!("Invalid address {:?}", address));
+        }
+        let Some(entry) = self.find(address) else {
+            return ApiResponse::error(404, &format!("{} is not whitelisted", address));
+        };
+        match format {
+            "json" => ApiResponse::new(200, json!(entry)),
+            "calldata" => match encode_mint_call(&self.serve_command.function, entry) {
+                Ok(calldata) => ApiResponse::new(
+                    200,
+                    json!({ "address": address, "calldata": format!("0x{}", hex::encode(calldata)) }),
+                ),
+                Err(e) => ApiResponse::error(500, &e),
+            },
+            format => ApiResponse::error(
+                400,
+                &format!("Unknown format {:?}, expected json or calldata", format),
+            ),
+        }
+    }
+
+    /// Handles `POST /prove`, proving the address of a `{"address": "0x..."}` body with the
+    /// local zkey and adding its proof to the proofs file.
+    fn prove(&mut self, authorization: Option<&str>, body: &str) -> ApiResponse {
+        let Some(admin_token) = &self.serve_command.admin_token else {
+            return ApiResponse::error(
+                403,
+                "POST /prove is disabled, start the server with --admin-token",
+            );
+        };
+        let token = authorization.and_then(|value| value.strip_prefix("Bearer "));
+        if !token.is_some_and(|token| tokens_match(admin_token, token)) {
+            return ApiResponse::error(401, "Invalid admin token");
+        }
+        let address = serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|body| body["address"].as_str().map(str::to_string));
+        let Some(address) = address.filter(|address| is_address(address)) else {
+            return ApiResponse::error(400, "The body must be {\"address\": \"0x...\"}");
+        };
+        if let Some(entry) = self.find(&address) {
+            return ApiResponse::new(200, json!(entry));
+        }
+
+        let entry = match generate_proofs(
+            self.runner,
+            self.file_ops,
+            self.config,
+            self.key.as_ref(),
+            std::slice::from_ref(&address),
+            &mut |event| {
+                if let ProofEvent::Started(address) = event {
+                    println!("Generating the proof of {}", address)
+                }
+            },
+        ) {
+            Ok(mut generated) => {
+                print_warnings(&generated.warnings);
+                generated.proofs.remove(&address)
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return ApiResponse::error(500, &e.to_string());
+            }
+        };
+        let Some(entry) = entry else {
+            return ApiResponse::error(500, "No proof was generated");
+        };
+        self.proofs.insert(address, entry.clone());
+        if let Err(e) =
+            write_proofs_file(self.file_ops, self.config, &self.proofs_file, &self.proofs)
+        {
+            eprintln!("Warning: failed to update {}: {}", self.proofs_file, e);
+        }
+        ApiResponse::new(201, json!(entry))
+    }
+
+    /// Reads a request, handles it and sends the response with the CORS headers.
+    pub fn respond(&mut self, mut request: Request) {
+        let authorization = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .map(|header| header.value.to_string());
+        let mut body = String::new();
+        let response = match request
+            .as_reader()
+            .take(MAX_BODY_SIZE)
+            .read_to_string(&mut body)
+        {
+            Ok(_) => self.handle(
+                request.method(),
+                request.url(),
+                authorization.as_deref(),
+                &body,
+            ),
+            Err(_) => ApiResponse::error(400, "The body must be UTF-8 text"),
+        };
+
+        let data = match response.body {
+            Value::Null => Vec::new(),
+            body => body.to_string().into_bytes(),
+        };
+        let data_length = data.len();
+        let http_response = Response::new(
+            response.status.into(),
+            self.headers.clone(),
+            Cursor::new(data),
+            Some(data_length),
+            None,
+        );
+        if let Err(e) = request.respond(http_response) {
+            eprintln!("Warning: failed to send a response: {}", e);
+        }
+    }
+}
+
+/// Handles the `serve` subcommand.
+///
+/// Serves `GET /proof/{address}`, `GET /health` and, when an admin token is set,
+/// `POST /prove`, until the process is stopped.
+///
+/// # Parameters
+/// - `runner`: The command runner proving new addresses.
+/// - `file_ops`: The file system operations used to read and update the proofs file.
+/// - `serve_command`: The parsed options of the `serve` subcommand.
+/// - `config`: The project configuration locating the proofs file and the zkey.
+///
+/// # Returns
+/// A `Result<(), WhitelistError>` which is an `Err` if the proofs file cannot be loaded or the
+/// address cannot be listened on.
+pub fn handle_serve_subcommand<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    serve_command: &ServeCommand,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    let mut proof_server = ProofServer::new(runner, file_ops, serve_command, config)?;
+    let server = Server::http(&serve_command.addr).map_err(|e| {
+        WhitelistError::InvalidInput(format!("Cannot listen on {}: {}", serve_command.addr, e))
+    })?;
+    println!(
+        "Serving {} proofs from {} on http://{}",
+        proof_server.proofs.len(),
+        proof_server.proofs_file,
+        server.server_addr()
+    );
+    for request in server.incoming_requests() {
+        proof_server.respond(request);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::command_runner::MockCommandRunner;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use crate::utils::proofs_file::tests::SAMPLE_PROOFS_FILE;
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::thread;
+
+    const ADDRESS: &str = "0xabcdef1234567890abcdef1234567890abcdef12";
+
+    fn stub_proofs(file_ops: &MockFileSystemOps) {
+        file_ops.stub_file_content(
+            "addresses.txt.proofs.json",
+            SAMPLE_PROOFS_FILE.lines().map(str::to_string).collect(),
+        );
+    }
+
+    fn serve_command(admin_token: Option<&str>) -> ServeCommand {
+        ServeCommand {
+            admin_token: admin_token.map(str::to_string),
+            ..ServeCommand::default()
+        }
+    }
+
+    /// Tests `GET /health` and `GET /proof/{address}` in both formats.
+    #[test]
+    fn test_get_proof() {
+        // Arrange
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        stub_proofs(&file_ops);
+        let serve_command = serve_command(None);
+        let config = Config::default();
+        let mut server = ProofServer::new(&runner, &file_ops, &serve_command, &config).unwrap();
+
+        // Act
+        let health = server.handle(&Method::Get, "/health", None, "");
+        let proof = server.handle(&Method::Get, &format!("/proof/{}", ADDRESS), None, "");
+        let upper_case = ADDRESS.to_uppercase().replacen("0X", "0x", 1);
+        let calldata = server.handle(
+            &Method::Get,
+            &format!("/proof/{}?format=calldata", upper_case),
+            None,
+            "",
+        );
+
+        // Assert
+        assert_eq!(
+            health,
+            ApiResponse::new(200, json!({ "status": "ok", "proofs": 2 }))
+        );
+        assert_eq!(proof.status, 200);
+        assert_eq!(proof.body["pA"], json!(["0x01", "0x02"]));
+        assert_eq!(calldata.status, 200);
+        let calldata = calldata.body["calldata"].as_str().unwrap();
+        assert_eq!(calldata.len(), 2 + 2 * (4 + 10 * 32));
+    }
+
+    /// Tests the errors of `GET /proof/{address}`.
+    #[test]
+    fn test_get_proof_errors() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        stub_proofs(&file_ops);
+        let serve_command = serve_command(None);
+        let config = Config::default();
+        let mut server = ProofServer::new(&runner, &file_ops, &serve_command, &config).unwrap();
+
+        let status = |server: &mut ProofServer<_, _>, url: &str| {
+            server.handle(&Method::Get, url, None, "").status
+        };
+
+        assert_eq!(status(&mut server, "/proof/0x1234"), 400);
+        assert_eq!(
+            status(
+                &mut server,
+                "/proof/0x0000000000000000000000000000000000000001"
+            ),
+            404
+        );
+        assert_eq!(
+            status(&mut server, &format!("/proof/{}?format=xml", ADDRESS)),
+            400
+        );
+        assert_eq!(status(&mut server, "/proofs"), 404);
+    }
+
+    /// Tests that a proved address is added to the proofs file in its own format.
+    #[test]
+    fn test_prove_writes_the_proofs_file_format() {
+        let runner = MockCommandRunner::new();
+        runner.stub_output(
+            "snarkjs",
+            r#"["0x1","0x2"],[["0x3","0x4"],["0x5","0x6"]],["0x7","0x8"],["0x9"]"#,
+        );
+        let file_ops = MockFileSystemOps::new();
+        file_ops.stub_file_content(
+            "proofs.csv",
+            vec!["address,pA0,pA1,pB00,pB01,pB10,pB11,pC0,pC1,input0".to_string()],
+        );
+        let serve_command = ServeCommand {
+            proofs: Some("proofs.csv".to_string()),
+            ..serve_command(Some("secret"))
+        };
+        let config = Config::default();
+        let mut server = ProofServer::new(&runner, &file_ops, &serve_command, &config).unwrap();
+        let body = json!({ "address": ADDRESS }).to_string();
+
+        let response = server.handle(&Method::Post, "/prove", Some("Bearer secret"), &body);
+
+        assert_eq!(response.status, 201);
+        let written = file_ops.get_written_content("proofs.csv").unwrap();
+        assert!(written.starts_with("address,pA0,"));
+        assert!(written.contains(&format!("{},0x1,0x2,", ADDRESS)));
+    }
+
+    /// Tests that an encrypted zkey needs a keyfile or a passphrase in the environment before
+    /// `POST /prove` is enabled, instead of prompting on a request.
+    #[test]
+    fn test_new_with_encrypted_zkey_without_key() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        stub_proofs(&file_ops);
+        let mut config = Config::default();
+        config.secrets.encrypt = true;
+        config.secrets.keyfile = Some("missing.key".to_string());
+
+        let (disabled, enabled) = (serve_command(None), serve_command(Some("secret")));
+
+        let disabled = ProofServer::new(&runner, &file_ops, &disabled, &config);
+        let enabled = ProofServer::new(&runner, &file_ops, &enabled, &config);
+
+        assert!(disabled.is_ok());
+        assert!(matches!(enabled, Err(WhitelistError::InvalidInput(_))));
+    }
+
+    /// Tests that `POST /prove` requires the admin token and never runs a tool for a known
+    /// address.
+    #[test]
+    fn test_prove_authorization() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        stub_proofs(&file_ops);
+        let config = Config::default();
+        let body = json!({ "address": ADDRESS }).to_string();
+
+        let disabled = serve_command(None);
+        let mut server = ProofServer::new(&runner, &file_ops, &disabled, &config).unwrap();
+        assert_eq!(
+            server
+                .handle(&Method::Post, "/prove", Some("Bearer secret"), &body)
+                .status,
+            403
+        );
+
+        let enabled = serve_command(Some("secret"));
+        let mut server = ProofServer::new(&runner, &file_ops, &enabled, &config).unwrap();
+        let post = |server: &mut ProofServer<_, _>, authorization: Option<&str>, body: &str| {
+            server
+                .handle(&Method::Post, "/prove", authorization, body)
+                .status
+        };
+        assert_eq!(post(&mut server, None, &body), 401);
+        assert_eq!(post(&mut server, Some("Bearer secreT"), &body), 401);
+        assert_eq!(post(&mut server, Some("Bearer secret"), "{}"), 400);
+        assert_eq!(post(&mut server, Some("Bearer secret"), &body), 200);
+        assert!(runner.calls().is_empty());
+    }
+
+    /// Tests a request sent over a socket on localhost, with its CORS headers.
+    #[test]
+    fn test_respond_on_localhost() {
+        // Arrange
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        stub_proofs(&file_ops);
+        let serve_command = serve_command(None);
+        let config = Config::default();
+        let mut proof_server =
+            ProofServer::new(&runner, &file_ops, &serve_command, &config).unwrap();
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let addr = server.server_addr().to_ip().unwrap();
+
+        // Act
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(
+                stream,
+                "GET /proof/{} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                ADDRESS
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        });
+        proof_server.respond(server.recv().unwrap());
+        let response = client.join().unwrap();
+
+        // Assert
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.contains("Access-Control-Allow-Origin: *"));
+        assert!(response.contains("\"pA\":[\"0x01\",\"0x02\"]"));
+    }
+}
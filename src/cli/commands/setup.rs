@@ -1,172 +1,277 @@
-use crate::utils::command_runner::{run_snarkjs_command, CommandRunner};
-use std::io;
+use crate::project::{Project, SetupOptions};
+use crate::utils::{
+    command_runner::{run_with_outputs, run_with_private_outputs, CommandRunner},
+    config::Config,
+    error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+    interrupt::Deferred,
+    secrets::{encrypt_setup_secrets, SecretKey},
+};
 
 /// Executes the setup procedure to generate necessary files for Zero-Knowledge (ZK) proofs.
 ///
 /// This function orchestrates the ceremony procedure to create the necessary
 /// files for Zero-Knowledge proofs. It performs several steps, each invoking
 /// an external command via the `snarkjs` utility. Basic progress reporting is
-/// provided via print statements.
+/// provided via the `progress` callback. Each step writes its output under a `.partial`
+/// name first, so an interrupted step never leaves a partial ptau file or zkey behind, and the
+/// ptau files and zkeys are only readable by their owner.
 ///
 /// # Parameters
 /// - `runner`: A reference to an implementation of the `CommandRunner` trait which will execute the `snarkjs` commands.
+/// - `file_ops`: The file system operations renaming the outputs of the steps.
 /// - `random_name`: A `String` containing a random name used in the ceremony contributions.
 /// - `random_text`: A `String` containing a random text used in the ceremony contributions.
+/// - `config`: The project configuration, which holds the ptau power, the protocol and the artifact paths.
+/// - `progress`: Called with a message before each step.
 ///
 /// # Errors
 /// Returns an error if any of the external commands fail.
-pub fn execute_setup_command<R: CommandRunner>(
+pub fn execute_setup_command<R: CommandRunner, F: FileSystemOps>(
     runner: &R,
+    file_ops: &F,
     random_name: String,
     random_text: String,
-) -> io::Result<()> {
+    config: &Config,
+    progress: &mut dyn FnMut(&str),
+) -> Result<(), WhitelistError> {
     // Various steps in the setup procedure are detailed below:
 
     // Step 1: Start Ceremony
-    println!("Starting Ceremony...");
-    start_ceremony(runner)?;
+    progress("Starting Ceremony...");
+    start_ceremony(runner, file_ops, config)?;
 
     // Step 2: Contribute to Ceremony
-    println!("Contributing to Ceremony...");
-    contribute_to_ceremony(runner, random_name.clone(), random_text.clone())?;
+    progress("Contributing to Ceremony...");
+    contribute_to_ceremony(
+        runner,
+        file_ops,
+        random_name.clone(),
+        random_text.clone(),
+        config,
+    )?;
 
     // Step3: Prepare Phase 2
-    println!("Preparing Phase 2 (this takes some time)...");
-    prepare_phase_2(runner)?;
+    progress("Preparing Phase 2 (this takes some time)...");
+    prepare_phase_2(runner, file_ops, config)?;
 
     // Step4: Generate zkey
-    println!("Generating zkey...");
-    generate_zkey(runner)?;
+    progress("Generating zkey...");
+    generate_zkey(runner, file_ops, config)?;
 
     // Step5: Contribute to Phase 2
-    println!("Contributing to Phase 2...");
-    contribute_to_phase_2(runner, random_name, random_text)?;
+    progress("Contributing to Phase 2...");
+    contribute_to_phase_2(runner, file_ops, random_name, random_text, config)?;
 
     // Step 6: Export the verification Key
-    println!("Exporting the verification key...");
-    export_verification_key(runner)?;
-    println!("Ceremony completed");
+    progress("Exporting the verification key...");
+    export_verification_key(runner, file_ops, config)?;
+    progress("Ceremony completed");
     Ok(())
 }
 
 // The following helper functions represent individual steps in the setup procedure:
 
 // Step 1: Start Ceremony
-fn start_ceremony<R: CommandRunner>(runner: &R) -> Result<(), io::Error> {
-    run_snarkjs_command(
+fn start_ceremony<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    run_with_private_outputs(
         runner,
-        &["powersoftau", "new", "bn128", "12", "pot12_0000.ptau", "-v"],
+        file_ops,
+        "snarkjs",
+        &[
+            "powersoftau",
+            "new",
+            "bn128",
+            &config.circuit.ptau_power.to_string(),
+            &config.ptau("0000"),
+            "-v",
+        ],
+        &[&config.ptau("0000")],
     )?;
     Ok(())
 }
 
 // Step 2: Contribute to Ceremony
-fn contribute_to_ceremony<R: CommandRunner>(
+fn contribute_to_ceremony<R: CommandRunner, F: FileSystemOps>(
     runner: &R,
+    file_ops: &F,
     random_name: String,
     random_text: String,
-) -> Result<(), io::Error> {
-    run_snarkjs_command(
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    run_with_private_outputs(
         runner,
+        file_ops,
+        "snarkjs",
         &[
             "powersoftau",
             "contribute",
-            "pot12_0000.ptau",
-            "pot12_0001.ptau",
+            &config.ptau("0000"),
+            &config.ptau("0001"),
             &format!("--name=\"{}\"", random_name),
             "-v",
             &format!("-e=\"{}\"", random_text),
         ],
+        &[&config.ptau("0001")],
     )?;
     Ok(())
 }
 
 // Step3: Prepare Phase 2
-fn prepare_phase_2<R: CommandRunner>(runner: &R) -> Result<(), io::Error> {
-    run_snarkjs_command(
+fn prepare_phase_2<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    run_with_private_outputs(
         runner,
+        file_ops,
+        "snarkjs",
         &[
             "powersoftau",
             "prepare",
             "phase2",
-            "pot12_0001.ptau",
-            "pot12_final.ptau",
+            &config.ptau("0001"),
+            &config.ptau("final"),
             "-v",
         ],
+        &[&config.ptau("final")],
     )?;
     Ok(())
 }
 
 // Step4: Generate zkey
-fn generate_zkey<R: CommandRunner>(runner: &R) -> Result<(), io::Error> {
-    run_snarkjs_command(
+fn generate_zkey<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    run_with_private_outputs(
         runner,
+        file_ops,
+        "snarkjs",
         &[
-            "groth16",
+            config.circuit.protocol.as_str(),
             "setup",
-            "circuit.r1cs",
-            "pot12_final.ptau",
-            "circuit_0000.zkey",
+            &config.artifact(&format!("{}.r1cs", config.circuit_name())),
+            &config.ptau("final"),
+            &config.zkey("0000"),
         ],
+        &[&config.zkey("0000")],
     )?;
     Ok(())
 }
 
 // Step5: Contribute to Phase 2
-fn contribute_to_phase_2<R: CommandRunner>(
+fn contribute_to_phase_2<R: CommandRunner, F: FileSystemOps>(
     runner: &R,
+    file_ops: &F,
     random_name: String,
     random_text: String,
-) -> Result<(), io::Error> {
-    run_snarkjs_command(
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    run_with_private_outputs(
         runner,
+        file_ops,
+        "snarkjs",
         &[
             "zkey",
             "contribute",
-            "circuit_0000.zkey",
-            "circuit_0001.zkey",
+            &config.zkey("0000"),
+            &config.zkey("0001"),
             &format!("--name=\"{}\"", random_name),
             "-v",
             &format!("-e=\"{}\"", random_text),
         ],
+        &[&config.zkey("0001")],
     )?;
     Ok(())
 }
 
 // Step 6: Export the verification Key
-fn export_verification_key<R: CommandRunner>(runner: &R) -> Result<(), io::Error> {
-    run_snarkjs_command(
+fn export_verification_key<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    run_with_outputs(
         runner,
+        file_ops,
+        "snarkjs",
         &[
             "zkey",
             "export",
             "verificationkey",
-            "circuit_0001.zkey",
-            "verification_key.json",
+            &config.zkey("0001"),
+            &config.verification_key(),
         ],
+        &[&config.verification_key()],
     )?;
     Ok(())
 }
 
-/// Handles CLI sub command
+/// Runs the setup ceremony, encrypting its secrets when the configuration asks for it.
 ///
-/// This function serves as an entry point to handle the setup subcommand. It calls the
-/// `execute_setup_command` function passing the necessary arguments.
+/// When secrets are encrypted, the key is loaded before the ceremony and the ptau files and
+/// zkeys are encrypted right after it, including when a step failed or was interrupted, so no
+/// secret is left in plaintext. Ctrl-C is deferred meanwhile, stopping the ceremony at the
+/// running step.
 ///
 /// # Parameters
 /// - `runner`: A reference to an implementation of the `CommandRunner` trait which will execute the `snarkjs` commands.
+/// - `file_ops`: The file system operations used to encrypt the secrets.
 /// - `random_name`: A `String` containing a random name used in the ceremony contributions.
 /// - `random_text`: A `String` containing a random text used in the ceremony contributions.
+/// - `config`: The project configuration, which holds the ptau power, the protocol and the artifact paths.
+/// - `progress`: Called with a message before each step and for each encrypted file.
 ///
 /// # Returns
-/// Returns an `io::Result` to indicate success or any I/O error that occurs.
-pub fn handle_setup_subcommand<R: CommandRunner>(
+/// Returns a `Result` to indicate success, or the `WhitelistError` of the first step that failed.
+pub fn run_setup<R: CommandRunner, F: FileSystemOps>(
     runner: &R,
+    file_ops: &F,
     random_name: String,
     random_text: String,
-) -> io::Result<()> {
-    execute_setup_command(runner, random_name, random_text)?;
-    Ok(())
+    config: &Config,
+    progress: &mut dyn FnMut(&str),
+) -> Result<(), WhitelistError> {
+    let key = match config.secrets.encrypt {
+        true => Some(SecretKey::load(file_ops, &config.secrets)?),
+        false => None,
+    };
+    let Some(key) = key else {
+        return execute_setup_command(runner, file_ops, random_name, random_text, config, progress);
+    };
+    let _deferred = Deferred::start();
+    let result =
+        execute_setup_command(runner, file_ops, random_name, random_text, config, progress);
+    let encrypted = encrypt_setup_secrets(file_ops, &key, config)?;
+    for encrypted in encrypted {
+        progress(&format!("Encrypted {}", encrypted));
+    }
+    result
+}
+
+/// Handles CLI sub command
+///
+/// This function serves as an entry point to handle the setup subcommand. It runs the
+/// ceremony through `Project::run_setup`, printing its progress.
+///
+/// # Parameters
+/// - `project`: The project running the ceremony, with the runner of the `setup` step.
+/// - `options`: The names and entropy of the contributions.
+///
+/// # Returns
+/// Returns a `Result` to indicate success, or the `WhitelistError` of the first step that failed.
+pub fn handle_setup_subcommand<R: CommandRunner, F: FileSystemOps>(
+    project: &Project<R, F>,
+    options: SetupOptions,
+) -> Result<(), WhitelistError> {
+    project.run_setup(options, &mut |message| println!("{}", message))
 }
 
 #[cfg(test)]
@@ -175,6 +280,7 @@ mod tests {
 
     use super::*;
     use crate::utils::command_runner::MockCommandRunner;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
 
     /// Tests the `execute_setup_command` function to ensure it orchestrates the setup procedure correctly.
     ///
@@ -188,7 +294,15 @@ mod tests {
         let runner = MockCommandRunner::new();
         let random_name: String = Sentence(2..3).fake();
         let random_text: String = Sentence(3..4).fake();
-        let result = handle_setup_subcommand(&runner, random_name.clone(), random_text.clone());
+        let file_ops = MockFileSystemOps::new();
+        let project = Project::with_ops(Config::default(), &runner, &file_ops);
+        let result = handle_setup_subcommand(
+            &project,
+            SetupOptions {
+                name: Some(random_name.clone()),
+                entropy: Some(random_text.clone()),
+            },
+        );
         assert!(result.is_ok());
 
         let expected_calls = vec![
@@ -199,7 +313,7 @@ mod tests {
                     "new".to_string(),
                     "bn128".to_string(),
                     "12".to_string(),
-                    "pot12_0000.ptau".to_string(),
+                    "pot12_0000.ptau.partial".to_string(),
                     "-v".to_string(),
                 ],
             ),
@@ -209,7 +323,7 @@ mod tests {
                     "powersoftau".to_string(),
                     "contribute".to_string(),
                     "pot12_0000.ptau".to_string(),
-                    "pot12_0001.ptau".to_string(),
+                    "pot12_0001.ptau.partial".to_string(),
                     format!("--name=\"{}\"", random_name),
                     "-v".to_string(),
                     format!("-e=\"{}\"", random_text),
@@ -222,7 +336,7 @@ mod tests {
                     "prepare".to_string(),
                     "phase2".to_string(),
                     "pot12_0001.ptau".to_string(),
-                    "pot12_final.ptau".to_string(),
+                    "pot12_final.ptau.partial".to_string(),
                     "-v".to_string(),
                 ],
             ),
@@ -233,7 +347,7 @@ mod tests {
                     "setup".to_string(),
                     "circuit.r1cs".to_string(),
                     "pot12_final.ptau".to_string(),
-                    "circuit_0000.zkey".to_string(),
+                    "circuit_0000.zkey.partial".to_string(),
                 ],
             ),
             (
@@ -242,7 +356,7 @@ mod tests {
                     "zkey".to_string(),
                     "contribute".to_string(),
                     "circuit_0000.zkey".to_string(),
-                    "circuit_0001.zkey".to_string(),
+                    "circuit_0001.zkey.partial".to_string(),
                     format!("--name=\"{}\"", random_name),
                     "-v".to_string(),
                     format!("-e=\"{}\"", random_text),
@@ -255,11 +369,105 @@ mod tests {
                     "export".to_string(),
                     "verificationkey".to_string(),
                     "circuit_0001.zkey".to_string(),
-                    "verification_key.json".to_string(),
+                    "verification_key.json.partial".to_string(),
                 ],
             ),
         ];
 
         assert_eq!(runner.calls(), expected_calls);
+
+        // Each output is moved into place once its step succeeded.
+        let renames: Vec<String> = file_ops
+            .operations()
+            .into_iter()
+            .filter(|(operation, _)| operation == "rename")
+            .map(|(_, paths)| paths)
+            .collect();
+        assert_eq!(renames.len(), 6);
+        assert_eq!(renames[0], "pot12_0000.ptau.partial pot12_0000.ptau");
+        assert_eq!(
+            renames[5],
+            "verification_key.json.partial verification_key.json"
+        );
+    }
+
+    /// Tests that the ptau power and the artifact directory of the configuration are used.
+    #[test]
+    fn test_execute_setup_command_with_config() {
+        let runner = MockCommandRunner::new();
+        let mut config = Config::default();
+        config.circuit.path = "allowlist.circom".to_string();
+        config.circuit.ptau_power = 14;
+        config.artifacts.dir = "build".to_string();
+
+        let file_ops = MockFileSystemOps::new();
+        let project = Project::with_ops(config, &runner, &file_ops);
+        let result = handle_setup_subcommand(
+            &project,
+            SetupOptions {
+                name: Some("name".to_string()),
+                entropy: Some("text".to_string()),
+            },
+        );
+
+        assert!(result.is_ok());
+        let calls = runner.calls();
+        assert_eq!(calls[0].1[3], "14");
+        assert_eq!(calls[0].1[4], "build/pot14_0000.ptau.partial");
+        assert_eq!(
+            calls[3].1,
+            vec![
+                "groth16".to_string(),
+                "setup".to_string(),
+                "build/allowlist.r1cs".to_string(),
+                "build/pot14_final.ptau".to_string(),
+                "build/allowlist_0000.zkey.partial".to_string(),
+            ]
+        );
+        assert_eq!(calls[5].1[4], "build/verification_key.json.partial");
+    }
+
+    /// Tests that the secrets written before a failed step are encrypted all the same.
+    #[test]
+    fn test_run_setup_encrypts_on_failure() {
+        let runner = MockCommandRunner::new();
+        runner.stub_failure("snarkjs");
+        let file_ops = MockFileSystemOps::new();
+        let identity = age::x25519::Identity::generate();
+        file_ops.stub_file_content(
+            "key.txt",
+            vec![age::secrecy::ExposeSecret::expose_secret(&identity.to_string()).to_string()],
+        );
+        file_ops.stub_bytes("pot12_0000.ptau", b"ptau content".to_vec());
+        let mut config = Config::default();
+        config.secrets.encrypt = true;
+        config.secrets.keyfile = Some("key.txt".to_string());
+
+        let result = run_setup(
+            &runner,
+            &file_ops,
+            "name".to_string(),
+            "text".to_string(),
+            &config,
+            &mut |_| {},
+        );
+
+        assert_eq!(result.unwrap_err().exit_code(), 4);
+        let operations = file_ops.operations();
+        assert_eq!(
+            operations[0],
+            (
+                "create_private".to_string(),
+                "pot12_0000.ptau.partial".to_string()
+            )
+        );
+        assert!(operations.ends_with(&[
+            (
+                "rename".to_string(),
+                "pot12_0000.ptau.age.partial pot12_0000.ptau.age".to_string()
+            ),
+            ("overwrite".to_string(), "pot12_0000.ptau".to_string()),
+            ("remove".to_string(), "pot12_0000.ptau".to_string()),
+        ]));
     }
 }
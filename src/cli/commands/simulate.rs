@@ -0,0 +1,460 @@
+use super::token::{proof_function, DEFAULT_TOKEN_CONTRACT};
+use crate::cli::{SimulateCommand, TokenKind};
+use crate::utils::{
+    command_runner::CommandRunner,
+    config::Config,
+    error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+    local_evm::{CallOutcome, LocalEvm},
+    proofs_file::{read_proofs_file, ProofEntry},
+};
+use revm::primitives::{address, hex, keccak256, Address, U256};
+use std::collections::BTreeMap;
+
+/// The account deploying the token on the local EVM.
+const DEPLOYER: Address = address!("00000000000000000000000000000000000d3910");
+
+/// The result of minting with a single proof on the local EVM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MintResult {
+    pub address: String,
+    pub passed: bool,
+    pub gas_used: u64,
+    pub revert_reason: Option<String>,
+}
+
+/// Encodes a call to `<function>(uint[2],uint[2][2],uint[2],uint[N])` with the arguments of a proof entry.
+///
+/// The arguments are static arrays, so the calldata is the selector followed by
+/// every value of the proof as a 32-byte word, in declaration order.
+pub fn encode_mint_call(function: &str, entry: &ProofEntry) -> Result<Vec<u8>, String> {
+    let signature = format!(
+        "{}(uint256[2],uint256[2][2],uint256[2],uint256[{}])",
+        function,
+        entry.input.len()
+    );
+    let mut calldata = keccak256(signature.as_bytes())[..4].to_vec();
+    let words = entry
+        .p_a
+        .iter()
+        .chain(entry.p_b.iter().flatten())
+        .chain(entry.p_c.iter())
+        .chain(entry.input.iter());
+    for word in words {
+        let value = U256::from_str_radix(word.trim_start_matches("0x"), 16)
+            .map_err(|e| format!("Invalid value {} in proofs file: {}", word, e))?;
+        calldata.extend_from_slice(&value.to_be_bytes::<32>());
+    }
+    Ok(calldata)
+}
+
+/// Deploys an airdrop funded with the whole supply of a test token.
+///
+/// The test token is deployed first, minting its supply to the deployer. Its address is the
+/// constructor argument of the airdrop, which then receives the supply to transfer on claims.
+///
+/// # Returns
+/// The address of the airdrop together with the outcome of its deployment, or an error
+/// message if a deployment or the funding failed.
+fn deploy_airdrop(
+    evm: &mut LocalEvm,
+    creation_code: Vec<u8>,
+    test_token_code: Vec<u8>,
+) -> Result<(Address, CallOutcome), String> {
+    let (test_token, _) = evm.deploy(DEPLOYER, test_token_code)?;
+    let mut creation_code = creation_code;
+    creation_code.extend_from_slice(test_token.into_word().as_slice());
+    let (airdrop, deployment) = evm.deploy(DEPLOYER, creation_code)?;
+
+    // The test token mints `type(uint128).max` to the deployer.
+    let mut calldata = keccak256("transfer(address,uint256)".as_bytes())[..4].to_vec();
+    calldata.extend_from_slice(airdrop.into_word().as_slice());
+    calldata.extend_from_slice(&U256::from(u128::MAX).to_be_bytes::<32>());
+    let funding = evm.call(DEPLOYER, test_token, calldata)?;
+    if !funding.success {
+        return Err(format!(
+            "Failed to fund the airdrop: {}",
+            funding
+                .revert_reason
+                .unwrap_or_else(|| "transfer failed".to_string())
+        ));
+    }
+    Ok((airdrop, deployment))
+}
+
+/// Deploys the token on a local EVM and calls `function` with every proof, from the proven address.
+///
+/// # Parameters
+/// - `creation_code`: The creation bytecode of the token, which deploys its own verifier.
+/// - `test_token_code`: For an airdrop, the creation bytecode of the `TestToken` it is deployed
+///   with and funded from.
+/// - `function`: The name of the function that takes a proof, e.g. `mint`.
+/// - `proofs`: The proofs to mint with, keyed by address.
+///
+/// # Returns
+/// The gas used by the deployment and the result of every mint,
+/// `WhitelistError::InvalidInput` if an address of the proofs file is invalid,
+/// `WhitelistError::ArtifactMismatch` if the token cannot be deployed or a proof cannot be
+/// encoded, or `WhitelistError::Other` if the EVM fails.
+pub fn simulate_mints(
+    creation_code: Vec<u8>,
+    test_token_code: Option<Vec<u8>>,
+    function: &str,
+    proofs: &BTreeMap<String, ProofEntry>,
+) -> Result<(u64, Vec<MintResult>), WhitelistError> {
+    let mut evm = LocalEvm::new();
+    let (token, deployment) = match test_token_code {
+        Some(test_token_code) => deploy_airdrop(&mut evm, creation_code, test_token_code),
+        None => evm.deploy(DEPLOYER, creation_code),
+    }
+    .map_err(WhitelistError::ArtifactMismatch)?;
+
+    let mut results = Vec::new();
+    for (address, entry) in proofs {
+        let sender: Address = address.parse().map_err(|e| {
+            WhitelistError::InvalidInput(format!(
+                "Invalid address {} in proofs file: {}",
+                address, e
+            ))
+        })?;
+        let calldata =
+            encode_mint_call(function, entry).map_err(WhitelistError::ArtifactMismatch)?;
+        let outcome = evm
+            .call(sender, token, calldata)
+            .map_err(WhitelistError::Other)?;
+        // The function returns a bool, so a successful call must not return `false`.
+        let returned_true = outcome.output.is_empty() || outcome.output.iter().any(|b| *b != 0);
+        results.push(MintResult {
+            address: address.clone(),
+            passed: outcome.success && returned_true,
+            gas_used: outcome.gas_used,
+            revert_reason: match outcome.revert_reason {
+                None if !returned_true => Some(format!("{} returned false", function)),
+                reason => reason,
+            },
+        });
+    }
+    Ok((deployment.gas_used, results))
+}
+
+/// Reads the creation bytecode written by `solc`.
+fn read_bytecode<F: FileSystemOps>(file_ops: &F, file: &str) -> Result<Vec<u8>, WhitelistError> {
+    let bytecode = file_ops.read_to_string(file)?;
+    hex::decode(bytecode.trim())
+        .map_err(|e| WhitelistError::ArtifactMismatch(format!("{}: {}", file, e)))
+}
+
+/// Handles the `simulate` subcommand.
+///
+/// Compiles the token and the verifier it imports with a locally installed `solc`, deploys the
+/// token into an embedded EVM and calls `mint` (or the function given with `--function`) with
+/// each entry of the proofs file from the matching sender, reporting pass/fail and the gas
+/// used per call. An airdrop is compiled together with a `TestToken`, deployed with it and
+/// funded with its whole supply before `claim` is called.
+///
+/// # Parameters
+/// - `runner`: The command runner used to execute `solc`.
+/// - `file_ops`: The file system operations used to read the proofs and the compiled bytecode.
+/// - `simulate_command`: The parsed options of the `simulate` subcommand.
+/// - `config`: The project configuration, which provides the default proofs file, token file,
+///   token name and build directory.
+///
+/// # Returns
+/// A `Result<(), WhitelistError>` which is `Ok(())` if every proof was accepted,
+/// `WhitelistError::InvalidInput` for the abstract library, or
+/// `WhitelistError::ArtifactMismatch` if a proof was rejected.
+pub fn handle_simulate_subcommand<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    simulate_command: &SimulateCommand,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    let proofs_file = simulate_command
+        .proofs
+        .clone()
+        .unwrap_or_else(|| config.proofs_file(&config.input.file));
+    let contract = simulate_command
+        .contract
+        .as_deref()
+        .or(config.contracts.token.as_deref())
+        .unwrap_or(DEFAULT_TOKEN_CONTRACT);
+    let token_file = simulate_command
+        .token
+        .clone()
+        .unwrap_or_else(|| config.public_file("zkToken.sol"));
+    let build_dir = simulate_command
+        .build_dir
+        .clone()
+        .unwrap_or_else(|| config.tmp_file("build"));
+    let function = simulate_command
+        .function
+        .as_deref()
+        .unwrap_or(proof_function(simulate_command.kind));
+    if simulate_command.kind == TokenKind::Library {
+        return Err(WhitelistError::InvalidInput(
+            "The library is abstract and cannot be deployed, simulate the contract inheriting it"
+                .to_string(),
+        ));
+    }
+    let airdrop = simulate_command.kind == TokenKind::Airdrop;
+    let test_token_file = format!("{}/TestToken.sol", build_dir);
+    let mut args = vec![
+        "--bin",
+        "--optimize",
+        "--overwrite",
+        "--base-path",
+        ".",
+        "--allow-paths",
+        ".",
+        "-o",
+        &build_dir,
+    ];
+    args.extend(simulate_command.remappings.iter().map(String::as_str));
+    args.push(&token_file);
+    if airdrop {
+        file_ops.create_dir_all(&build_dir)?;
+        file_ops.write_to_file(
+            &test_token_file,
+            include_str!("../../../templates/TestToken.sol"),
+        )?;
+        args.push(&test_token_file);
+    }
+    println!("Compiling {}...", token_file);
+    runner.run("solc", &args)?;
+
+    let creation_code = read_bytecode(file_ops, &format!("{}/{}.bin", build_dir, contract))?;
+    let test_token_code = match airdrop {
+        true => Some(read_bytecode(
+            file_ops,
+            &format!("{}/TestToken.bin", build_dir),
+        )?),
+        false => None,
+    };
+    let proofs = read_proofs_file(file_ops, config, &proofs_file)?;
+
+    let (deployment_gas, results) =
+        simulate_mints(creation_code, test_token_code, function, &proofs)?;
+    println!("Deployed {} (gas used: {})", contract, deployment_gas);
+    for result in &results {
+        match &result.revert_reason {
+            None => println!("PASS {} (gas used: {})", result.address, result.gas_used),
+            Some(reason) => println!(
+                "FAIL {} (gas used: {}): {}",
+                result.address, result.gas_used, reason
+            ),
+        }
+    }
+
+    let failed = results.iter().filter(|result| !result.passed).count();
+    println!("{} passed, {} failed", results.len() - failed, failed);
+    if failed > 0 {
+        // The verifier rejects proofs of another circuit or setup.
+        return Err(WhitelistError::ArtifactMismatch(format!(
+            "{} of {} proofs were rejected",
+            failed,
+            results.len()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::command_runner::MockCommandRunner;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use crate::utils::local_evm::tests::{RETURN_ONE_CONTRACT, REVERTING_CONTRACT};
+    use crate::utils::proofs_file::{parse_proofs_file, tests::SAMPLE_PROOFS_FILE};
+
+    fn stub_files(file_ops: &MockFileSystemOps, bytecode: &str) {
+        file_ops.stub_file_content("build/ZKToken.bin", vec![bytecode.to_string()]);
+        file_ops.stub_file_content(
+            "addresses.txt.proofs.json",
+            SAMPLE_PROOFS_FILE.lines().map(str::to_string).collect(),
+        );
+    }
+
+    /// Tests that `mint` calldata is the selector followed by every proof value as a word.
+    #[test]
+    fn test_encode_mint_call() {
+        let proofs = parse_proofs_file(SAMPLE_PROOFS_FILE).unwrap();
+        let entry = &proofs["0x1234567890abcdef1234567890abcdef12345678"];
+
+        let calldata = encode_mint_call("mint", entry).unwrap();
+
+        assert_eq!(calldata.len(), 4 + 10 * 32);
+        assert_eq!(
+            calldata[..4],
+            keccak256("mint(uint256[2],uint256[2][2],uint256[2],uint256[2])")[..4]
+        );
+        assert_eq!(calldata[4 + 31], 0x11);
+        assert_eq!(calldata[4 + 2 * 32 + 31], 0x13);
+        assert_eq!(calldata[4 + 5 * 32 + 31], 0x16);
+        assert_eq!(calldata[4 + 9 * 32 + 31], 0x78);
+    }
+
+    /// Tests that `solc` is invoked and every proof is minted against the deployed bytecode.
+    #[test]
+    fn test_handle_simulate_subcommand() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        stub_files(&file_ops, RETURN_ONE_CONTRACT);
+
+        let result = handle_simulate_subcommand(
+            &runner,
+            &file_ops,
+            &SimulateCommand::default(),
+            &Config::default(),
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(
+            runner.calls(),
+            vec![(
+                "solc".to_string(),
+                vec![
+                    "--bin".to_string(),
+                    "--optimize".to_string(),
+                    "--overwrite".to_string(),
+                    "--base-path".to_string(),
+                    ".".to_string(),
+                    "--allow-paths".to_string(),
+                    ".".to_string(),
+                    "-o".to_string(),
+                    "build".to_string(),
+                    "@openzeppelin/=node_modules/@openzeppelin/".to_string(),
+                    "zkToken.sol".to_string(),
+                ]
+            )]
+        );
+    }
+
+    /// Tests that an airdrop is deployed with the test token and funded before the claims.
+    #[test]
+    fn test_simulate_mints_for_airdrop() {
+        let proofs = parse_proofs_file(SAMPLE_PROOFS_FILE).unwrap();
+        let creation_code = hex::decode(RETURN_ONE_CONTRACT).unwrap();
+
+        let (_, results) = simulate_mints(
+            creation_code.clone(),
+            Some(creation_code.clone()),
+            "claim",
+            &proofs,
+        )
+        .unwrap();
+        let unfunded = simulate_mints(
+            creation_code,
+            Some(hex::decode(REVERTING_CONTRACT).unwrap()),
+            "claim",
+            &proofs,
+        );
+
+        assert!(results.iter().all(|result| result.passed));
+        assert!(matches!(
+            unfunded,
+            Err(WhitelistError::ArtifactMismatch(message)) if message.starts_with("Failed to fund the airdrop")
+        ));
+    }
+
+    /// Tests that an airdrop is compiled together with the test token.
+    #[test]
+    fn test_handle_simulate_subcommand_for_airdrop() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        stub_files(&file_ops, RETURN_ONE_CONTRACT);
+        file_ops.stub_file_content("build/TestToken.bin", vec![RETURN_ONE_CONTRACT.to_string()]);
+        let simulate_command = SimulateCommand {
+            kind: TokenKind::Airdrop,
+            ..SimulateCommand::default()
+        };
+
+        let result =
+            handle_simulate_subcommand(&runner, &file_ops, &simulate_command, &Config::default());
+
+        assert!(result.is_ok());
+        assert!(file_ops
+            .get_written_content("build/TestToken.sol")
+            .unwrap()
+            .contains("contract TestToken is ERC20 {"));
+        let (_, args) = &runner.calls()[0];
+        assert_eq!(
+            args[args.len() - 2..],
+            ["zkToken.sol", "build/TestToken.sol"]
+        );
+    }
+
+    /// Tests that the abstract library is refused before compiling.
+    #[test]
+    fn test_handle_simulate_subcommand_for_library() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        let simulate_command = SimulateCommand {
+            kind: TokenKind::Library,
+            ..SimulateCommand::default()
+        };
+
+        let result =
+            handle_simulate_subcommand(&runner, &file_ops, &simulate_command, &Config::default());
+
+        assert!(matches!(result, Err(WhitelistError::InvalidInput(_))));
+        assert!(runner.calls().is_empty());
+    }
+
+    /// Tests that rejected proofs are reported as failures.
+    #[test]
+    fn test_simulate_mints_with_rejected_proofs() {
+        let proofs = parse_proofs_file(SAMPLE_PROOFS_FILE).unwrap();
+        let creation_code = hex::decode(REVERTING_CONTRACT).unwrap();
+
+        let (_, results) = simulate_mints(creation_code, None, "claim", &proofs).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| !result.passed));
+        assert_eq!(
+            results[0].revert_reason,
+            Some("reverted without a reason".to_string())
+        );
+    }
+
+    /// Tests that invalid addresses and proofs are reported as invalid input and mismatched
+    /// artifacts.
+    #[test]
+    fn test_simulate_mints_with_invalid_proofs_file() {
+        let proofs = parse_proofs_file(SAMPLE_PROOFS_FILE).unwrap();
+        let (address, entry) = proofs.iter().next().unwrap();
+        let invalid_address = BTreeMap::from([("0x1234".to_string(), entry.clone())]);
+        let mut invalid_entry = entry.clone();
+        invalid_entry.p_a[0] = "0xzz".to_string();
+        let invalid_proof = BTreeMap::from([(address.clone(), invalid_entry)]);
+        let creation_code = hex::decode(RETURN_ONE_CONTRACT).unwrap();
+
+        let invalid_address = simulate_mints(creation_code.clone(), None, "mint", &invalid_address);
+        let invalid_proof = simulate_mints(creation_code, None, "mint", &invalid_proof);
+
+        assert!(matches!(
+            invalid_address,
+            Err(WhitelistError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            invalid_proof,
+            Err(WhitelistError::ArtifactMismatch(_))
+        ));
+    }
+
+    /// Tests that the subcommand fails when a proof is rejected.
+    #[test]
+    fn test_handle_simulate_subcommand_with_rejected_proofs() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+        stub_files(&file_ops, REVERTING_CONTRACT);
+
+        let result = handle_simulate_subcommand(
+            &runner,
+            &file_ops,
+            &SimulateCommand::default(),
+            &Config::default(),
+        );
+
+        assert!(result.is_err());
+    }
+}
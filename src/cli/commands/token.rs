@@ -1,66 +1,581 @@
-use std::env;
-use std::fs::File;
-use std::io::{self, Write};
-use std::path::Path;
+use super::verifier::DEFAULT_VERIFIER_CONTRACT;
+use crate::cli::{TokenCommand, TokenKind};
+use crate::utils::{
+    config::Config,
+    error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+    public_signals::{default_public_signals, parse_public_signals, PublicSignal},
+    solidity::{validate_identifier, validate_pragma, validate_string_literal},
+    template_renderer::render_template,
+    verification_key::VerificationKey,
+};
+use serde::Serialize;
 
-/// Copies a token file template to the current directory.
+/// The name of the token contract when neither the command nor the configuration names it.
+pub const DEFAULT_TOKEN_CONTRACT: &str = "ZKToken";
+
+/// The largest number of decimals of an erc20 token, which keeps every `u64` amount of whole
+/// tokens within a `uint256`.
+pub const MAX_DECIMALS: u8 = 36;
+
+/// The variables available to the token template.
+#[derive(Serialize)]
+struct TokenContext<'a> {
+    contract_name: &'a str,
+    verifier_contract: &'a str,
+    pragma: &'a str,
+    name: &'a str,
+    symbol: &'a str,
+    amount: u64,
+    decimals: u8,
+    owner_mintable: bool,
+    cap: Option<u64>,
+    uri: &'a str,
+    token_id: u64,
+    public_signals: &'a [PublicSignal],
+    address_signal: &'a str,
+}
+
+/// Renders the token contract from the options of the `token` subcommand.
+///
+/// Every kind of contract shares the verifier wiring and the `claimed` bookkeeping of the
+/// ERC20 token. Every value that ends up in the contract is validated first, so the rendered
+/// contract always compiles.
 ///
-/// This function is intended to be called when a verifier is available.
-/// It provides a sample token solidity contract to be used with the verifier
+/// # Parameters
+/// - `token_command`: The parsed options of the `token` subcommand.
+/// - `public_signals`: The public signals of the circuit, which size `_pubSignals` and name
+///   its indices.
 ///
-/// # Errors
-/// Returns an `io::Result` wrapping any I/O error that occurs.
-fn copy_token_file() -> io::Result<()> {
-    // Obtain the current working directory
-    let current_dir = env::current_dir()?;
-    // Construct a path for the new solidity contract
-    let circuit_path = Path::new(&current_dir).join("zkToken.sol");
-    // Create a new file at the constructed path
-    let mut file = File::create(circuit_path)?;
-    // Write the contents of the template file into the new file
-    file.write_all(include_bytes!("../../../templates/zkToken.sol"))?;
-    Ok(())
+/// # Returns
+/// The Solidity source of the token, or an error message if an option is invalid.
+pub fn render_token(
+    token_command: &TokenCommand,
+    public_signals: &[PublicSignal],
+) -> Result<String, String> {
+    let contract_name = contract_name(token_command);
+    validate_identifier("contract name", contract_name)?;
+    let verifier_contract = token_command
+        .verifier_contract
+        .as_deref()
+        .unwrap_or(DEFAULT_VERIFIER_CONTRACT);
+    validate_identifier("verifier contract name", verifier_contract)?;
+    validate_pragma(&token_command.pragma)?;
+    validate_string_literal("token name", &token_command.name)?;
+    validate_string_literal("token symbol", &token_command.symbol)?;
+    if let Some(uri) = token_command.uri.as_deref().filter(|uri| !uri.is_empty()) {
+        validate_string_literal("token uri", uri)?;
+    }
+
+    let address_signal = public_signals
+        .iter()
+        .find(|signal| signal.name == token_command.address_signal)
+        .ok_or_else(|| {
+            format!(
+                "The circuit has no public signal named {}",
+                token_command.address_signal
+            )
+        })?;
+
+    let kind = token_command.kind;
+    let (template_name, template_source) = match kind {
+        TokenKind::Erc20 => (
+            "zkToken.sol",
+            include_str!("../../../templates/zkToken.sol.jinja"),
+        ),
+        TokenKind::Erc721 => (
+            "zkNft.sol",
+            include_str!("../../../templates/zkNft.sol.jinja"),
+        ),
+        TokenKind::Erc1155 => (
+            "zkMultiToken.sol",
+            include_str!("../../../templates/zkMultiToken.sol.jinja"),
+        ),
+        TokenKind::Airdrop => (
+            "zkAirdrop.sol",
+            include_str!("../../../templates/zkAirdrop.sol.jinja"),
+        ),
+        TokenKind::Library => (
+            "zkWhitelisted.sol",
+            include_str!("../../../templates/zkWhitelisted.sol.jinja"),
+        ),
+    };
+    if token_command.decimals.is_some() && kind != TokenKind::Erc20 {
+        return Err("--decimals is only available for erc20 tokens".to_string());
+    }
+    if (token_command.uri.is_some() || token_command.token_id.is_some())
+        && kind != TokenKind::Erc1155
+    {
+        return Err("--uri and --token-id are only available for erc1155 tokens".to_string());
+    }
+    if kind == TokenKind::Airdrop && (token_command.owner_mintable || token_command.cap.is_some()) {
+        return Err(
+            "--owner-mintable and --cap are not available for airdrops, which transfer from a treasury"
+                .to_string(),
+        );
+    }
+    if kind == TokenKind::Library
+        && (token_command.amount.is_some()
+            || token_command.owner_mintable
+            || token_command.cap.is_some())
+    {
+        return Err(
+            "--amount, --owner-mintable and --cap are not available for the library, which mints nothing"
+                .to_string(),
+        );
+    }
+
+    let amount = token_command.amount.unwrap_or(match kind {
+        TokenKind::Erc20 | TokenKind::Airdrop => 10,
+        TokenKind::Erc721 | TokenKind::Erc1155 | TokenKind::Library => 1,
+    });
+    if amount == 0 {
+        return Err("The mint amount must be greater than zero".to_string());
+    }
+    let decimals = token_command.decimals.unwrap_or(18);
+    if decimals > MAX_DECIMALS {
+        return Err(format!(
+            "The number of decimals ({}) must not be greater than {}",
+            decimals, MAX_DECIMALS
+        ));
+    }
+    match token_command.cap {
+        Some(0) => return Err("The cap must be greater than zero".to_string()),
+        Some(cap) if cap < amount => {
+            return Err(format!(
+                "The cap ({}) must not be lower than the mint amount ({})",
+                cap, amount
+            ))
+        }
+        _ => {}
+    }
+
+    render_template(
+        template_name,
+        template_source,
+        TokenContext {
+            contract_name,
+            verifier_contract,
+            pragma: &token_command.pragma,
+            name: &token_command.name,
+            symbol: &token_command.symbol,
+            amount,
+            decimals,
+            owner_mintable: token_command.owner_mintable,
+            cap: token_command.cap,
+            uri: token_command.uri.as_deref().unwrap_or_default(),
+            token_id: token_command.token_id.unwrap_or_default(),
+            public_signals,
+            address_signal: &address_signal.constant,
+        },
+    )
 }
 
-/// Handles the `circuit` CLI subcommand.
+/// Reads the public signals of the compiled circuit from its symbols file and
+/// `verification_key.json`, falling back to those of the default circuit when neither exists
+/// yet.
 ///
-/// This function acts as a handler for the `circuit` subcommand.
-/// It calls the `copy_circuit_file` function to perform the actual work.
+/// # Returns
+/// The public signals in verifier order, `WhitelistError::ArtifactMissing` if only one of the
+/// files exists, `WhitelistError::ArtifactMismatch` if they cannot be parsed, or any I/O error.
+pub fn read_public_signals<F: FileSystemOps>(
+    file_ops: &F,
+    config: &Config,
+) -> Result<Vec<PublicSignal>, WhitelistError> {
+    let sym_file = config.artifact(&format!("{}.sym", config.circuit_name()));
+    let verification_key_file = config.verification_key();
+    match (
+        file_ops.read_to_string(&sym_file),
+        file_ops.read_to_string(&verification_key_file),
+    ) {
+        (Ok(sym), Ok(verification_key)) => {
+            let verification_key = VerificationKey::from_json(&verification_key)
+                .map_err(WhitelistError::ArtifactMismatch)?;
+            parse_public_signals(&sym, verification_key.n_public)
+                .map_err(WhitelistError::ArtifactMismatch)
+        }
+        (Err(WhitelistError::ArtifactMissing(_)), Err(WhitelistError::ArtifactMissing(_))) => {
+            eprintln!(
+                "{} and {} not found, using the public signals of the default circuit",
+                sym_file, verification_key_file
+            );
+            Ok(default_public_signals())
+        }
+        (Err(error), _) | (_, Err(error)) => Err(error),
+    }
+}
+
+/// Fills the options not given on the command line from the project configuration.
+///
+/// The library mints nothing, so it takes neither the token name nor the amount.
+fn apply_config(token_command: &TokenCommand, config: &Config) -> TokenCommand {
+    let mints = token_command.kind != TokenKind::Library;
+    TokenCommand {
+        contract_name: token_command
+            .contract_name
+            .clone()
+            .or_else(|| config.contracts.token.clone().filter(|_| mints)),
+        verifier_contract: token_command
+            .verifier_contract
+            .clone()
+            .or_else(|| config.contracts.verifier.clone()),
+        amount: token_command
+            .amount
+            .or(config.contracts.amount.filter(|_| mints)),
+        ..token_command.clone()
+    }
+}
+
+/// Returns the name of the generated contract, defaulting to one that fits its kind.
+fn contract_name(token_command: &TokenCommand) -> &str {
+    match (&token_command.contract_name, token_command.kind) {
+        (Some(contract_name), _) => contract_name,
+        (None, TokenKind::Library) => "ZKWhitelisted",
+        (None, _) => DEFAULT_TOKEN_CONTRACT,
+    }
+}
+
+/// Returns the file the contract of the given kind is written to.
+pub fn token_file_name(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Library => "zkWhitelisted.sol",
+        _ => "zkToken.sol",
+    }
+}
+
+/// Returns the function of the contract of the given kind that takes a proof.
+pub fn proof_function(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Airdrop => "claim",
+        _ => "mint",
+    }
+}
+
+/// Handles the `token` CLI subcommand.
+///
+/// This function acts as a handler for the `token` subcommand.
+/// It renders the token contract from the given options and writes it to `zkToken.sol`,
+/// or to `zkWhitelisted.sol` for the library, in the public directory. The public signals are taken from the
+/// compiled circuit when `circuit.sym` and `verification_key.json` exist.
+///
+/// # Parameters
+/// - `file_ops`: The file system operations used to read the circuit symbols and verification key,
+///   and to write the contract.
+/// - `token_command`: The parsed options of the `token` subcommand.
+/// - `config`: The project configuration, which provides the contract names and mint amount
+///   not given as options.
 ///
 /// # Returns
-/// Returns an `io::Result` to indicate success or any I/O error that occurs.
-pub fn handle_token_subcommand() -> std::io::Result<()> {
-    copy_token_file()
+/// Returns a `Result` with `WhitelistError::InvalidInput` if an option is invalid,
+/// `WhitelistError::ArtifactMissing` if only part of the compiled circuit exists,
+/// `WhitelistError::ArtifactMismatch` if it cannot be parsed, or any I/O error.
+pub fn handle_token_subcommand<F: FileSystemOps>(
+    file_ops: &F,
+    token_command: &TokenCommand,
+    config: &Config,
+) -> Result<(), WhitelistError> {
+    let token_command = &apply_config(token_command, config);
+    let public_signals = read_public_signals(file_ops, config)?;
+    let content =
+        render_token(token_command, &public_signals).map_err(WhitelistError::InvalidInput)?;
+    let token_file = config.public_file(token_file_name(token_command.kind));
+    file_ops.write_to_file(&token_file, &content)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
-    use std::path::Path;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use crate::utils::verification_key::tests::SAMPLE_VERIFICATION_KEY;
 
-    /// Tests the functionality of the `handle_circuit_subcommand` function.
+    /// Tests the functionality of the `handle_token_subcommand` function.
     ///
-    /// This test ensures that the `handle_circuit_subcommand` function correctly
-    /// creates a new `circuit.circom` file in the current directory.
-    ///
-    /// # Returns
-    /// Returns an `io::Result` to indicate the success or failure of the test.
+    /// This test ensures that the `handle_token_subcommand` function correctly
+    /// writes the `zkToken.sol` contract.
     #[test]
-    fn test_handle_token_subcommand() -> std::io::Result<()> {
-        // Execute the function under test
-        handle_token_subcommand()?;
+    fn test_handle_token_subcommand() {
+        // Arrange
+        let file_ops = MockFileSystemOps::new();
+
+        // Act
+        let result =
+            handle_token_subcommand(&file_ops, &TokenCommand::default(), &Config::default());
+
+        // Assert
+        assert!(result.is_ok());
+        let token = file_ops.get_written_content("zkToken.sol").unwrap();
+        assert!(token.contains("pragma solidity ^0.8.20;"));
+    }
+
+    /// Tests that the default options render the original sample token.
+    #[test]
+    fn test_render_token_with_default_options() {
+        let token = render_token(&TokenCommand::default(), &default_public_signals()).unwrap();
+
+        assert!(token.contains("pragma solidity ^0.8.20;"));
+        assert!(token.contains("contract ZKToken is ERC20 {"));
+        assert!(token.contains("Groth16Verifier public verifier;"));
+        assert!(token.contains("constructor() ERC20(\"YourToken\", \"YTK\") {"));
+        assert!(token.contains("_mint(msg.sender, 10 * 10 ** decimals());"));
+        assert!(!token.contains("function decimals()"));
+        assert!(!token.contains("Ownable"));
+        assert!(!token.contains("ERC20Capped"));
+    }
 
-        // Obtain the current working directory
-        let current_dir = std::env::current_dir()?;
-        // Construct the path of the circuit file
-        let circuit_path = Path::new(&current_dir).join("zkToken.sol");
-        // Assert that the file has been created
-        assert!(circuit_path.exists());
+    /// Tests that every option of the `token` subcommand ends up in the contract.
+    #[test]
+    fn test_render_token_with_custom_options() {
+        let token_command = TokenCommand {
+            contract_name: Some("AirdropToken".to_string()),
+            verifier_contract: Some("AirdropVerifier".to_string()),
+            pragma: "0.8.24".to_string(),
+            name: "Airdrop Token".to_string(),
+            symbol: "ADT".to_string(),
+            amount: Some(250),
+            decimals: Some(6),
+            owner_mintable: true,
+            cap: Some(1_000_000),
+            ..TokenCommand::default()
+        };
+
+        let token = render_token(&token_command, &default_public_signals()).unwrap();
+
+        assert!(token.contains("pragma solidity 0.8.24;"));
+        assert!(token.contains("contract AirdropToken is ERC20Capped, Ownable {"));
+        assert!(token.contains("AirdropVerifier public verifier;"));
+        assert!(token.contains("verifier = new AirdropVerifier();"));
+        assert!(token.contains(
+            "constructor() ERC20(\"Airdrop Token\", \"ADT\") ERC20Capped(1000000 * 10 ** 6) Ownable(msg.sender) {"
+        ));
+        assert!(token.contains("        return 6;"));
+        assert!(token.contains("_mint(msg.sender, 250 * 10 ** decimals());"));
+        assert!(token.contains("function ownerMint(address to, uint256 amount) public onlyOwner {"));
+    }
+
+    /// Tests that invalid options are rejected.
+    #[test]
+    fn test_render_token_with_invalid_options() {
+        let invalid_commands = [
+            TokenCommand {
+                contract_name: Some("ZK Token".to_string()),
+                ..TokenCommand::default()
+            },
+            TokenCommand {
+                name: "Your\"Token".to_string(),
+                ..TokenCommand::default()
+            },
+            TokenCommand {
+                amount: Some(0),
+                ..TokenCommand::default()
+            },
+            TokenCommand {
+                kind: TokenKind::Erc721,
+                decimals: Some(6),
+                ..TokenCommand::default()
+            },
+            TokenCommand {
+                kind: TokenKind::Erc20,
+                uri: Some("ipfs://drop/{id}.json".to_string()),
+                ..TokenCommand::default()
+            },
+            TokenCommand {
+                kind: TokenKind::Airdrop,
+                owner_mintable: true,
+                ..TokenCommand::default()
+            },
+            TokenCommand {
+                kind: TokenKind::Library,
+                amount: Some(5),
+                ..TokenCommand::default()
+            },
+            TokenCommand {
+                cap: Some(5),
+                ..TokenCommand::default()
+            },
+            TokenCommand {
+                decimals: Some(MAX_DECIMALS + 1),
+                ..TokenCommand::default()
+            },
+        ];
+
+        for token_command in &invalid_commands {
+            assert!(render_token(token_command, &default_public_signals()).is_err());
+        }
+    }
+
+    /// Tests the ERC721 kind, which mints one token per proof by default.
+    #[test]
+    fn test_render_token_with_erc721_kind() {
+        let token_command = TokenCommand {
+            kind: TokenKind::Erc721,
+            cap: Some(500),
+            ..TokenCommand::default()
+        };
+
+        let token = render_token(&token_command, &default_public_signals()).unwrap();
+
+        assert!(token.contains("import \"@openzeppelin/contracts/token/ERC721/ERC721.sol\";"));
+        assert!(token.contains("contract ZKToken is ERC721 {"));
+        assert!(token.contains("uint256 public constant MAX_SUPPLY = 500;"));
+        assert!(token.contains("_mintTokens(msg.sender, 1);"));
+        assert!(token.contains("require(!claimed[msg.sender], \"Tokens already claimed\");"));
+        assert!(token.contains(
+            "require(verifier.verifyProof(_pA, _pB, _pC, _pubSignals), \"Invalid proof\");"
+        ));
+    }
+
+    /// Tests the ERC1155 kind with a custom uri and token id.
+    #[test]
+    fn test_render_token_with_erc1155_kind() {
+        let token_command = TokenCommand {
+            kind: TokenKind::Erc1155,
+            amount: Some(3),
+            owner_mintable: true,
+            uri: Some("ipfs://drop/{id}.json".to_string()),
+            token_id: Some(7),
+            ..TokenCommand::default()
+        };
+
+        let token = render_token(&token_command, &default_public_signals()).unwrap();
+
+        assert!(token.contains("contract ZKToken is ERC1155, Ownable {"));
+        assert!(token
+            .contains("constructor() ERC1155(\"ipfs://drop/{id}.json\") Ownable(msg.sender) {"));
+        assert!(token.contains("uint256 public constant TOKEN_ID = 7;"));
+        assert!(token.contains("_mintTokens(msg.sender, 3);"));
+        assert!(token.contains("function ownerMint(address to, uint256 amount) public onlyOwner {"));
+        assert!(!token.contains("MAX_SUPPLY"));
+    }
+
+    /// Tests the airdrop kind, which transfers from the contract treasury on `claim`.
+    #[test]
+    fn test_render_token_with_airdrop_kind() {
+        let token_command = TokenCommand {
+            kind: TokenKind::Airdrop,
+            amount: Some(25),
+            ..TokenCommand::default()
+        };
+
+        let token = render_token(&token_command, &default_public_signals()).unwrap();
+
+        assert!(token.contains("contract ZKToken is Ownable {"));
+        assert!(token.contains("amount = 25 * 10 ** IERC20Metadata(address(_token)).decimals();"));
+        assert!(token.contains("function claim(uint[2] calldata _pA"));
+        assert!(token.contains("token.safeTransfer(msg.sender, amount);"));
+        assert!(token.contains("claimed[msg.sender] = true;"));
+    }
+
+    /// Tests the library kind, an abstract contract exposing the whitelist check as a modifier.
+    #[test]
+    fn test_render_token_with_library_kind() {
+        let token_command = TokenCommand {
+            kind: TokenKind::Library,
+            ..TokenCommand::default()
+        };
+
+        let token = render_token(&token_command, &default_public_signals()).unwrap();
+
+        assert!(token.contains("abstract contract ZKWhitelisted {"));
+        assert!(token.contains("verifier = Groth16Verifier(_verifier);"));
+        assert!(token.contains("modifier onlyWhitelisted(uint[2] memory _pA, uint[2][2] memory _pB, uint[2] memory _pC, uint[2] memory _pubSignals) {"));
+        assert!(token.contains(
+            "require(verifier.verifyProof(_pA, _pB, _pC, _pubSignals), \"Invalid proof\");"
+        ));
+        assert!(token.contains("        claimed[msg.sender] = true;\n\n        _;\n    }"));
+        assert!(!token.contains("_mint"));
+        assert_eq!(token_file_name(TokenKind::Library), "zkWhitelisted.sol");
+    }
+
+    /// Tests that the public signals of the compiled circuit size and index `_pubSignals`.
+    #[test]
+    fn test_render_token_with_public_signals_of_the_circuit() {
+        let file_ops = MockFileSystemOps::new();
+        file_ops.stub_file_content(
+            "circuit.sym",
+            vec![
+                "1,1,0,main.account".to_string(),
+                "2,2,0,main.nonce".to_string(),
+                "3,3,0,main.secret".to_string(),
+            ],
+        );
+        file_ops.stub_file_content(
+            "verification_key.json",
+            SAMPLE_VERIFICATION_KEY
+                .lines()
+                .map(str::to_string)
+                .collect(),
+        );
+        let token_command = TokenCommand {
+            address_signal: "account".to_string(),
+            ..TokenCommand::default()
+        };
+
+        let public_signals = read_public_signals(&file_ops, &Config::default()).unwrap();
+        let token = render_token(&token_command, &public_signals).unwrap();
+
+        assert!(token.contains("uint256 public constant ACCOUNT_INDEX = 0;"));
+        assert!(token.contains("uint256 public constant NONCE_INDEX = 1;"));
+        assert!(!token.contains("SECRET_INDEX"));
+        assert!(token.contains("uint[2] calldata _pubSignals"));
+        assert!(token.contains("senderDecimalAddress == _pubSignals[ACCOUNT_INDEX]"));
+        assert!(render_token(&TokenCommand::default(), &public_signals).is_err());
+    }
+
+    /// Tests that the default circuit is assumed when it has not been compiled yet.
+    #[test]
+    fn test_read_public_signals_without_compiled_circuit() {
+        let public_signals =
+            read_public_signals(&MockFileSystemOps::new(), &Config::default()).unwrap();
+
+        assert_eq!(public_signals, default_public_signals());
+    }
+
+    /// Tests that a compiled circuit without its verification key is reported as missing
+    /// instead of silently assuming the default circuit.
+    #[test]
+    fn test_read_public_signals_with_partial_artifacts() {
+        let file_ops = MockFileSystemOps::new();
+        file_ops.stub_file_content("circuit.sym", vec!["1,1,0,main.account".to_string()]);
+
+        let result = read_public_signals(&file_ops, &Config::default());
+
+        assert!(
+            matches!(result, Err(WhitelistError::ArtifactMissing(ref path)) if path == "verification_key.json")
+        );
+    }
+
+    /// Tests that the configuration fills the options not given on the command line.
+    #[test]
+    fn test_apply_config() {
+        let mut config = Config::default();
+        config.contracts.verifier = Some("AllowlistVerifier".to_string());
+        config.contracts.token = Some("AllowlistToken".to_string());
+        config.contracts.amount = Some(50);
+        let token_command = TokenCommand {
+            amount: Some(5),
+            ..TokenCommand::default()
+        };
 
-        // Clean up by removing the created file
-        fs::remove_file(circuit_path)?;
+        let token_command = apply_config(&token_command, &config);
+        let library_command = apply_config(
+            &TokenCommand {
+                kind: TokenKind::Library,
+                ..TokenCommand::default()
+            },
+            &config,
+        );
 
-        Ok(())
+        assert_eq!(
+            token_command.contract_name,
+            Some("AllowlistToken".to_string())
+        );
+        assert_eq!(
+            token_command.verifier_contract,
+            Some("AllowlistVerifier".to_string())
+        );
+        assert_eq!(token_command.amount, Some(5));
+        assert_eq!(library_command.contract_name, None);
+        assert_eq!(library_command.amount, None);
+        assert!(render_token(&library_command, &default_public_signals()).is_ok());
     }
 }
@@ -1,68 +1,544 @@
-use crate::utils::command_runner::{run_snarkjs_command, CommandRunner};
-use std::io;
+use super::proofs::print_warnings;
+use crate::cli::{VerifierCommand, VerifierTarget};
+use crate::project::{Project, Verifier};
+use crate::utils::{
+    command_runner::CommandRunner,
+    config::Config,
+    error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+    proofs_file::{hex_to_decimal, read_proofs_file, ProofEntry},
+    solidity::{validate_identifier, validate_license, validate_pragma, validate_verifier_license},
+    template_renderer::render_template,
+    verification_key::VerificationKey,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
 
-/// Handles the `verifier` subcommand by executing the `snarkjs generateverifier` shell command.
+/// The name of the verifier contract when neither the options nor the configuration set one.
+pub const DEFAULT_VERIFIER_CONTRACT: &str = "Groth16Verifier";
+
+/// The Solidity version constraint of the verifier when the options do not set one.
+pub const DEFAULT_VERIFIER_PRAGMA: &str = ">=0.7.0 <0.9.0";
+
+/// The SPDX license identifier of the verifier when the options do not set one.
+pub const DEFAULT_VERIFIER_LICENSE: &str = "GPL-3.0";
+
+/// The variables available to the Solidity verifier template.
+#[derive(Serialize)]
+struct SolidityVerifierContext<'a> {
+    contract_name: &'a str,
+    pragma: &'a str,
+    license: &'a str,
+    #[serde(flatten)]
+    verification_key: VerificationKey,
+}
+
+/// The variables available to the Rust verifier template.
+#[derive(Serialize)]
+struct RustVerifierContext {
+    #[serde(flatten)]
+    verification_key: VerificationKey,
+    test_proof: Option<TestProof>,
+}
+
+/// A proof embedded in the unit test of the Rust verifier, with decimal coordinates.
+///
+/// Unlike the proofs file, G2 coordinates are stored as `[c0, c1]`.
+#[derive(Serialize)]
+struct TestProof {
+    address: String,
+    a: [String; 2],
+    b: [[String; 2]; 2],
+    c: [String; 2],
+    inputs: Vec<String>,
+}
+
+impl TestProof {
+    fn from_entry(address: &str, entry: &ProofEntry) -> Result<Self, String> {
+        Ok(TestProof {
+            address: address.to_string(),
+            a: [
+                hex_to_decimal(&entry.p_a[0])?,
+                hex_to_decimal(&entry.p_a[1])?,
+            ],
+            b: [
+                [
+                    hex_to_decimal(&entry.p_b[0][1])?,
+                    hex_to_decimal(&entry.p_b[0][0])?,
+                ],
+                [
+                    hex_to_decimal(&entry.p_b[1][1])?,
+                    hex_to_decimal(&entry.p_b[1][0])?,
+                ],
+            ],
+            c: [
+                hex_to_decimal(&entry.p_c[0])?,
+                hex_to_decimal(&entry.p_c[1])?,
+            ],
+            inputs: entry
+                .input
+                .iter()
+                .map(|input| hex_to_decimal(input))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Renders a Solidity Groth16 verifier from the contents of `verification_key.json`.
+///
+/// The contract name, pragma and license header are taken from `verifier_command`,
+/// so several whitelists can live side by side in the same Solidity project.
+/// Without a contract name, the verifier is named `Groth16Verifier`.
+///
+/// # Returns
+/// The Solidity source of the verifier, `WhitelistError::InvalidInput` if an option is invalid,
+/// or `WhitelistError::ArtifactMismatch` if the key is.
+pub fn render_solidity_verifier(
+    verification_key_json: &str,
+    verifier_command: &VerifierCommand,
+) -> Result<String, WhitelistError> {
+    let contract_name = verifier_command
+        .contract_name
+        .as_deref()
+        .unwrap_or(DEFAULT_VERIFIER_CONTRACT);
+    let pragma = verifier_command
+        .pragma
+        .as_deref()
+        .unwrap_or(DEFAULT_VERIFIER_PRAGMA);
+    let license = verifier_command
+        .license
+        .as_deref()
+        .unwrap_or(DEFAULT_VERIFIER_LICENSE);
+    validate_identifier("contract name", contract_name)
+        .and_then(|_| validate_pragma(pragma))
+        .and_then(|_| validate_license(license))
+        .and_then(|_| validate_verifier_license(license))
+        .map_err(WhitelistError::InvalidInput)?;
+    let verification_key = VerificationKey::from_json(verification_key_json)
+        .map_err(WhitelistError::ArtifactMismatch)?;
+
+    render_template(
+        "verifier_groth16.sol",
+        include_str!("../../../templates/verifier_groth16.sol.jinja"),
+        SolidityVerifierContext {
+            contract_name,
+            pragma,
+            license,
+            verification_key,
+        },
+    )
+    .map_err(WhitelistError::Other)
+}
+
+/// Renders a `no_std` Rust Groth16 verifier module from the contents of `verification_key.json`.
+///
+/// When the proofs of a proofs file are given, the first proof is embedded in a unit test of
+/// the module, so the generated verifier can be checked against a real proof with `cargo test`.
+///
+/// # Returns
+/// The Rust source of the verifier, or `WhitelistError::ArtifactMismatch` if the key or the
+/// proofs are invalid or do not match.
+pub fn render_rust_verifier(
+    verification_key_json: &str,
+    proofs: Option<&BTreeMap<String, ProofEntry>>,
+) -> Result<String, WhitelistError> {
+    let verification_key = VerificationKey::from_json(verification_key_json)
+        .map_err(WhitelistError::ArtifactMismatch)?;
+    let test_proof = match proofs {
+        Some(proofs) => {
+            let (address, entry) = proofs.iter().next().ok_or_else(|| {
+                WhitelistError::ArtifactMismatch(
+                    "The proofs file does not contain any proof".to_string(),
+                )
+            })?;
+            if entry.input.len() != verification_key.n_public {
+                return Err(WhitelistError::ArtifactMismatch(format!(
+                    "The proof for {} has {} public inputs but the verification key expects {}",
+                    address,
+                    entry.input.len(),
+                    verification_key.n_public
+                )));
+            }
+            Some(TestProof::from_entry(address, entry).map_err(WhitelistError::ArtifactMismatch)?)
+        }
+        None => None,
+    };
+
+    render_template(
+        "verifier_groth16.rs",
+        include_str!("../../../templates/verifier_groth16.rs.jinja"),
+        RustVerifierContext {
+            verification_key,
+            test_proof,
+        },
+    )
+    .map_err(WhitelistError::Other)
+}
+
+/// Generates a verifier from `verification_key.json`, as the `verifier` subcommand does.
+///
+/// The verification key is read from the artifact directory, and the contract name defaults
+/// to the verifier name of the project configuration. The Rust verifier embeds a test of the
+/// proofs file of the input file when no proofs file is given and it exists, and rejects the
+/// options that only apply to Solidity.
 ///
-/// This function accepts a generic parameter `R` which implements the `CommandRunner` trait,
-/// allowing for the execution of the shell command to be handled by `runner`.
+/// The verifier is rendered from a template bundled with the tool instead of being exported by
+/// `snarkjs`, which allows the contract name, pragma and license to be configured. With
+/// `--target rust` a Rust module is generated instead of a Solidity contract.
 ///
 /// # Parameters
-/// - `runner`: The command runner which will execute the shell command.
+/// - `file_ops`: The file system operations used to read the key and write the verifier.
+/// - `verifier_command`: The parsed options of the `verifier` subcommand.
+/// - `config`: The project configuration.
 ///
 /// # Returns
-/// - An `io::Result<()>` which will be `Ok(())` if the command executes successfully, or an `Err`
-///   wrapping an `io::Error` if an error occurs.
-pub fn handle_verifier_subcommand<R: CommandRunner>(runner: &R) -> io::Result<()> {
-    // Call the run_snarkjs_command function with the runner and the arguments for the shell command.
-    run_snarkjs_command(
-        runner,
-        &[
-            "zkey",
-            "export",
-            "solidityverifier",
-            "circuit_0001.zkey",
+/// - A `Result<Verifier, WhitelistError>` with the path and the source of the verifier, and a
+///   warning when the Rust verifier has no test, `WhitelistError::InvalidInput` if an option
+///   is invalid for the target, or another `Err` if an error occurs.
+pub fn write_verifier<F: FileSystemOps>(
+    file_ops: &F,
+    verifier_command: &VerifierCommand,
+    config: &Config,
+) -> Result<Verifier, WhitelistError> {
+    if verifier_command.target == VerifierTarget::Rust {
+        let solidity_options = [
+            ("--contract-name", verifier_command.contract_name.is_some()),
+            ("--pragma", verifier_command.pragma.is_some()),
+            ("--license", verifier_command.license.is_some()),
+        ];
+        if let Some((option, _)) = solidity_options.iter().find(|(_, given)| *given) {
+            return Err(WhitelistError::InvalidInput(format!(
+                "{} only applies to the Solidity verifier, not to --target rust",
+                option
+            )));
+        }
+    }
+    let verification_key_json = file_ops.read_to_string(&config.verification_key())?;
+    let verifier_command = &VerifierCommand {
+        contract_name: verifier_command
+            .contract_name
+            .clone()
+            .or_else(|| config.contracts.verifier.clone()),
+        ..verifier_command.clone()
+    };
+    let mut warnings = Vec::new();
+    let (verifier, default_output) = match verifier_command.target {
+        VerifierTarget::Solidity => (
+            render_solidity_verifier(&verification_key_json, verifier_command),
             "verifier.sol",
-        ],
-    )?;
+        ),
+        VerifierTarget::Rust => {
+            let default_proofs = config.proofs_file(&config.input.file);
+            let proofs_file = match &verifier_command.proofs {
+                Some(proofs_file) => Some(proofs_file.as_str()),
+                None if file_ops.exists(&default_proofs) => Some(default_proofs.as_str()),
+                None => None,
+            };
+            let proofs = match proofs_file {
+                Some(proofs_file) => Some(read_proofs_file(file_ops, config, proofs_file)?),
+                None => {
+                    warnings.push(
+                        "No proofs file found, the Rust verifier is generated without a test"
+                            .to_string(),
+                    );
+                    None
+                }
+            };
+            (
+                render_rust_verifier(&verification_key_json, proofs.as_ref()),
+                "verifier.rs",
+            )
+        }
+    };
+    let source = verifier?;
+    let path = verifier_command
+        .output
+        .clone()
+        .unwrap_or_else(|| config.public_file(default_output));
+    file_ops.write_to_file(&path, &source)?;
+    Ok(Verifier {
+        path,
+        source,
+        warnings,
+    })
+}
+
+/// Handles the `verifier` subcommand, writing the verifier through `Project::write_verifier`
+/// and printing its warnings.
+///
+/// # Parameters
+/// - `project`: The project holding the verification key.
+/// - `verifier_command`: The parsed options of the `verifier` subcommand.
+///
+/// # Returns
+/// - A `Result<(), WhitelistError>` which will be `Ok(())` if the verifier is written
+///   successfully, or the error of `write_verifier`.
+pub fn handle_verifier_subcommand<R: CommandRunner, F: FileSystemOps>(
+    project: &Project<R, F>,
+    verifier_command: &VerifierCommand,
+) -> Result<(), WhitelistError> {
+    let verifier = project.write_verifier(verifier_command)?;
+    print_warnings(&verifier.warnings);
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::command_runner::MockCommandRunner;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use crate::utils::proofs_file::tests::SAMPLE_PROOFS_FILE;
+    use crate::utils::verification_key::tests::SAMPLE_VERIFICATION_KEY;
+
+    fn stub_verification_key(file_ops: &MockFileSystemOps) {
+        file_ops.stub_file_content(
+            "verification_key.json",
+            SAMPLE_VERIFICATION_KEY
+                .lines()
+                .map(str::to_string)
+                .collect(),
+        );
+    }
 
-    /// Tests the `handle_verifier_subcommand` function to ensure it can execute the
-    /// `snarkjs generateverifier` shell command.
+    /// Tests the `write_verifier` function with the default options.
     ///
-    /// This test creates a `MockCommandRunner` instance to simulate the behavior of the `CommandRunner` trait.
-    /// It then calls `handle_verifier_subcommand` with this mock runner, checks the returned result to ensure
-    /// it is `Ok`, and finally, it checks the command and arguments passed to the `run` method of the `MockCommandRunner`
-    /// to ensure they match the expected command and arguments for the `snarkjs` utility.
+    /// This test stubs `verification_key.json` on a `MockFileSystemOps` instance and checks that
+    /// the rendered verifier is written to `verifier.sol` with the snarkjs defaults and the key data.
     #[test]
-    fn test_handle_verifier_subcommand() {
-        // Create a new MockCommandRunner instance for testing.
-        let runner = MockCommandRunner::new();
+    fn test_write_verifier() {
+        let file_ops = MockFileSystemOps::new();
+        stub_verification_key(&file_ops);
 
-        // Call the function with the mock runner.
-        let result = handle_verifier_subcommand(&runner); // Now passing a reference
+        let result = write_verifier(&file_ops, &VerifierCommand::default(), &Config::default());
+        assert!(result.is_ok());
+
+        let verifier = file_ops.get_written_content("verifier.sol").unwrap();
+        assert!(verifier.starts_with("// SPDX-License-Identifier: GPL-3.0\n"));
+        assert!(verifier.contains("pragma solidity >=0.7.0 <0.9.0;"));
+        assert!(verifier.contains("contract Groth16Verifier {"));
+        assert!(verifier.contains("uint256 constant alphax  = 11;"));
+        assert!(verifier.contains("uint256 constant betax1  = 22;"));
+        assert!(verifier.contains("uint256 constant betax2  = 21;"));
+        assert!(verifier.contains("uint256 constant IC2y = 56;"));
+        assert!(verifier.contains("uint[2] calldata _pubSignals"));
+        assert!(
+            verifier.contains("g1_mulAccC(_pVk, IC2x, IC2y, calldataload(add(pubSignals, 32)))")
+        );
+        assert!(verifier.contains("checkField(calldataload(add(_pubSignals, 32)))"));
+        assert!(!verifier.contains("checkField(calldataload(add(_pubSignals, 64)))"));
+    }
+
+    /// Tests that the key is read from the artifact directory and the configured name is used.
+    #[test]
+    fn test_write_verifier_with_config() {
+        let file_ops = MockFileSystemOps::new();
+        file_ops.stub_file_content(
+            "build/verification_key.json",
+            SAMPLE_VERIFICATION_KEY
+                .lines()
+                .map(str::to_string)
+                .collect(),
+        );
+        let mut config = Config::default();
+        config.artifacts.dir = "build".to_string();
+        config.contracts.verifier = Some("AllowlistVerifier".to_string());
 
-        // Assert the result is Ok(()) indicating success.
+        let result = write_verifier(&file_ops, &VerifierCommand::default(), &config);
         assert!(result.is_ok());
 
-        // Assert that the `run` method of `MockCommandRunner` has been called with correct arguments
-        assert_eq!(
-            runner.calls(),
-            vec![(
-                "snarkjs".to_string(),
-                vec![
-                    "zkey".to_string(),
-                    "export".to_string(),
-                    "solidityverifier".to_string(),
-                    "circuit_0001.zkey".to_string(),
-                    "verifier.sol".to_string(),
-                ]
-            )]
+        let verifier = file_ops.get_written_content("verifier.sol").unwrap();
+        assert!(verifier.contains("contract AllowlistVerifier {"));
+    }
+
+    /// Tests that the verifier is read from and written to the public directory of a working directory.
+    #[test]
+    fn test_write_verifier_with_workdir() {
+        let file_ops = MockFileSystemOps::new();
+        file_ops.stub_file_content(
+            "whitelist/public/verification_key.json",
+            SAMPLE_VERIFICATION_KEY
+                .lines()
+                .map(str::to_string)
+                .collect(),
         );
+        let mut config = Config::default();
+        config.artifacts.workdir = Some("whitelist".to_string());
+
+        let result = write_verifier(&file_ops, &VerifierCommand::default(), &config);
+        assert!(result.is_ok());
+
+        assert!(file_ops
+            .get_written_content("whitelist/public/verifier.sol")
+            .is_some());
+    }
+
+    /// Tests that the contract name, pragma, license and output path can be customized.
+    #[test]
+    fn test_write_verifier_with_custom_options() {
+        let file_ops = MockFileSystemOps::new();
+        stub_verification_key(&file_ops);
+        let verifier_command = VerifierCommand {
+            contract_name: Some("AirdropVerifier".to_string()),
+            pragma: Some("^0.8.20".to_string()),
+            license: Some("GPL-3.0-or-later".to_string()),
+            output: Some("src/AirdropVerifier.sol".to_string()),
+            ..VerifierCommand::default()
+        };
+
+        let result = write_verifier(&file_ops, &verifier_command, &Config::default());
+        assert!(result.is_ok());
+
+        let verifier = file_ops
+            .get_written_content("src/AirdropVerifier.sol")
+            .unwrap();
+        assert!(verifier.starts_with("// SPDX-License-Identifier: GPL-3.0-or-later\n"));
+        assert!(verifier.contains("pragma solidity ^0.8.20;"));
+        assert!(verifier.contains("contract AirdropVerifier {"));
+    }
+
+    /// Tests that an invalid contract name is rejected before anything is written.
+    #[test]
+    fn test_write_verifier_with_invalid_contract_name() {
+        let file_ops = MockFileSystemOps::new();
+        stub_verification_key(&file_ops);
+        let verifier_command = VerifierCommand {
+            contract_name: Some("My Verifier".to_string()),
+            ..VerifierCommand::default()
+        };
+
+        let result = write_verifier(&file_ops, &verifier_command, &Config::default());
+
+        assert!(result.is_err());
+        assert_eq!(file_ops.get_written_content("verifier.sol"), None);
+    }
+
+    /// Tests that a license disagreeing with the GPL-3.0 notice of the template is rejected.
+    #[test]
+    fn test_write_verifier_with_incompatible_license() {
+        let file_ops = MockFileSystemOps::new();
+        stub_verification_key(&file_ops);
+        let verifier_command = VerifierCommand {
+            license: Some("MIT".to_string()),
+            ..VerifierCommand::default()
+        };
+
+        let result = write_verifier(&file_ops, &verifier_command, &Config::default());
+
+        assert!(matches!(result, Err(WhitelistError::InvalidInput(_))));
+        assert_eq!(file_ops.get_written_content("verifier.sol"), None);
+    }
+
+    /// Tests that a missing verification key is reported as an error.
+    #[test]
+    fn test_write_verifier_without_verification_key() {
+        let file_ops = MockFileSystemOps::new();
+
+        let result = write_verifier(&file_ops, &VerifierCommand::default(), &Config::default());
+
+        assert!(result.is_err());
+    }
+
+    /// Tests that `--target rust` writes a Rust module with the key data and a test for the first proof.
+    #[test]
+    fn test_write_verifier_with_rust_target() {
+        let file_ops = MockFileSystemOps::new();
+        stub_verification_key(&file_ops);
+        file_ops.stub_file_content(
+            "addresses.txt.proofs.json",
+            SAMPLE_PROOFS_FILE.lines().map(str::to_string).collect(),
+        );
+        let verifier_command = VerifierCommand {
+            target: VerifierTarget::Rust,
+            proofs: Some("addresses.txt.proofs.json".to_string()),
+            ..VerifierCommand::default()
+        };
+
+        let result = write_verifier(&file_ops, &verifier_command, &Config::default());
+        assert!(result.is_ok());
+
+        let verifier = file_ops.get_written_content("verifier.rs").unwrap();
+        assert!(verifier.contains("pub const N_PUBLIC: usize = 2;"));
+        assert!(
+            verifier.contains("const ALPHA_G1: G1Affine = g1(MontFp!(\"11\"), MontFp!(\"12\"));")
+        );
+        assert!(verifier.contains("[MontFp!(\"21\"), MontFp!(\"22\")],"));
+        assert!(verifier.contains("    g1(MontFp!(\"55\"), MontFp!(\"56\")),"));
+        assert!(verifier.contains(
+            "/// Verifies the proof generated for `0x1234567890abcdef1234567890abcdef12345678`."
+        ));
+        // The G2 coordinates of the calldata are swapped back to `[c0, c1]`.
+        assert!(verifier.contains("[MontFp!(\"20\"), MontFp!(\"19\")],"));
+        assert!(verifier.contains("            MontFp!(\"1\"),"));
+    }
+
+    /// Tests that the Rust verifier embeds a test of the proofs file of the input file by default.
+    #[test]
+    fn test_write_verifier_with_rust_target_and_default_proofs() {
+        let file_ops = MockFileSystemOps::new();
+        stub_verification_key(&file_ops);
+        file_ops.stub_file_content(
+            "addresses.txt.proofs.json",
+            SAMPLE_PROOFS_FILE.lines().map(str::to_string).collect(),
+        );
+        let verifier_command = VerifierCommand {
+            target: VerifierTarget::Rust,
+            ..VerifierCommand::default()
+        };
+
+        let result = write_verifier(&file_ops, &verifier_command, &Config::default());
+        assert!(result.is_ok());
+
+        let verifier = file_ops.get_written_content("verifier.rs").unwrap();
+        assert!(verifier.contains("#[cfg(test)]"));
+    }
+
+    /// Tests that the options of the Solidity verifier are rejected with `--target rust`.
+    #[test]
+    fn test_write_verifier_with_rust_target_and_solidity_options() {
+        let file_ops = MockFileSystemOps::new();
+        stub_verification_key(&file_ops);
+        let verifier_commands = [
+            VerifierCommand {
+                contract_name: Some("AirdropVerifier".to_string()),
+                ..VerifierCommand::default()
+            },
+            VerifierCommand {
+                pragma: Some("^0.8.20".to_string()),
+                ..VerifierCommand::default()
+            },
+            VerifierCommand {
+                license: Some("GPL-3.0".to_string()),
+                ..VerifierCommand::default()
+            },
+        ];
+
+        for verifier_command in verifier_commands {
+            let verifier_command = VerifierCommand {
+                target: VerifierTarget::Rust,
+                ..verifier_command
+            };
+            let result = write_verifier(&file_ops, &verifier_command, &Config::default());
+            assert!(matches!(result, Err(WhitelistError::InvalidInput(_))));
+        }
+        assert_eq!(file_ops.get_written_content("verifier.rs"), None);
+    }
+
+    /// Tests that the Rust verifier is generated without a test when no proofs file is given.
+    #[test]
+    fn test_write_verifier_with_rust_target_without_proofs() {
+        let file_ops = MockFileSystemOps::new();
+        stub_verification_key(&file_ops);
+        let verifier_command = VerifierCommand {
+            target: VerifierTarget::Rust,
+            output: Some("src/verifier.rs".to_string()),
+            ..VerifierCommand::default()
+        };
+
+        let result = write_verifier(&file_ops, &verifier_command, &Config::default());
+        assert!(result.is_ok());
+
+        let verifier = file_ops.get_written_content("src/verifier.rs").unwrap();
+        assert!(verifier
+            .contains("pub fn verify(proof: &Proof, public_inputs: &[Fr; N_PUBLIC]) -> bool"));
+        assert!(!verifier.contains("#[cfg(test)]"));
     }
 }
@@ -1,4 +1,10 @@
 mod cli;
 
 pub use cli::run_cli;
+pub mod project;
 pub mod utils;
+
+pub use project::{
+    CompiledCircuit, Project, Proof, ProveOutput, SetupOptions, SetupOutput, Verifier,
+};
+pub use utils::error::WhitelistError;
@@ -2,6 +2,14 @@
 
 extern crate zk_whitelist;
 
-fn main() -> std::io::Result<()> {
-    zk_whitelist::run_cli()
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match zk_whitelist::run_cli() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            ExitCode::from(error.exit_code())
+        }
+    }
 }
@@ -0,0 +1,444 @@
+use fake::{faker::lorem::en::Sentence, Fake};
+use serde::Serialize;
+use std::{collections::BTreeMap, env, path::Path};
+
+use crate::cli::commands::proofs::{self, GeneratedProofs};
+use crate::cli::commands::{compile, movejs, setup, verifier};
+use crate::cli::VerifierCommand;
+use crate::utils::{
+    command_runner::{CommandRunner, RealCommandRunner},
+    config::{Config, CONFIG_FILE_NAME},
+    error::WhitelistError,
+    filesystem_operations::{FileSystemOps, RealFileSystemOps},
+    progress::ProofEvent,
+    proofs_file::ProofEntry,
+    r1cs::{parse_r1cs_header, R1csHeader},
+    verification_key::VerificationKey,
+};
+
+/// The artifacts of a compiled circuit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledCircuit {
+    /// The path of the constraint system, `<name>.r1cs`.
+    pub r1cs: String,
+    /// The path of the witness generator, `<name>.wasm`.
+    pub wasm: String,
+    /// The header of the constraint system.
+    pub header: R1csHeader,
+}
+
+/// The options of the setup ceremony.
+///
+/// The contributions use random names and entropy unless they are given.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SetupOptions {
+    /// The name of the contributions.
+    pub name: Option<String>,
+    /// The entropy of the contributions.
+    pub entropy: Option<String>,
+}
+
+/// The artifacts of the setup ceremony.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupOutput {
+    /// The path of the final zkey, used to generate the proofs.
+    pub zkey: String,
+    /// The path of `verification_key.json`.
+    pub verification_key: String,
+    /// The number of public signals of the circuit.
+    pub n_public: usize,
+}
+
+/// A generated verifier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verifier {
+    /// The path the verifier was written to.
+    pub path: String,
+    /// The source of the verifier, a Solidity contract or a Rust module.
+    pub source: String,
+    /// The warnings about the verifier, e.g. a Rust verifier generated without a test.
+    pub warnings: Vec<String>,
+}
+
+/// The proof of a whitelisted address.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Proof {
+    pub address: String,
+    /// The `pA`, `pB`, `pC` and `input` arguments of the verifier.
+    #[serde(flatten)]
+    pub entry: ProofEntry,
+}
+
+/// The proofs generated by `Project::prove`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ProveOutput {
+    /// The proofs ordered by address.
+    pub proofs: Vec<Proof>,
+    /// The scratch files of the witness generator that could not be removed afterwards.
+    pub warnings: Vec<String>,
+}
+
+/// A whitelist project, running each step of the CLI and returning its results instead of
+/// printing them.
+///
+/// The external tools are run by `runner` and the files are accessed through `file_ops`, so
+/// the project can be driven with the mock implementations in tests. The CLI subcommands are
+/// thin wrappers over a project borrowing their runner and file system operations, printing
+/// its results.
+pub struct Project<R = RealCommandRunner, F = RealFileSystemOps> {
+    config: Config,
+    runner: R,
+    file_ops: F,
+}
+
+impl Project {
+    /// Creates a project running the tools of the configuration on the real file system.
+    ///
+    /// # Parameters
+    /// - `config`: The project configuration, whose relative paths are resolved against the
+    ///   current directory. They are made absolute when the tools run in another directory.
+    pub fn new(config: Config) -> Self {
+        let config = match (&config.tools.working_dir, env::current_dir()) {
+            (Some(_), Ok(current_dir)) => config.resolve_paths(&current_dir),
+            _ => config,
+        };
+        let runner = RealCommandRunner::default().with_tools(config.tools.clone());
+        Project::with_ops(config, runner, RealFileSystemOps)
+    }
+
+    /// Opens the project in a directory, reading its `zk_whitelist.toml` when there is one.
+    ///
+    /// The paths of the configuration are resolved against `dir`, whatever the current
+    /// directory is.
+    ///
+    /// # Returns
+    /// The project, or `WhitelistError::InvalidInput` if the configuration is invalid.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, WhitelistError> {
+        let dir = dir.as_ref();
+        let config_file = dir.join(CONFIG_FILE_NAME);
+        let config = match RealFileSystemOps.exists(&config_file.to_string_lossy()) {
+            true => Config::from_file(&RealFileSystemOps, &config_file),
+            false => Config::from_toml("", dir),
+        }
+        .map_err(WhitelistError::InvalidInput)?;
+        Ok(Project::new(config))
+    }
+}
+
+impl<R: CommandRunner, F: FileSystemOps> Project<R, F> {
+    /// Creates a project with the given command runner and file system operations.
+    pub fn with_ops(config: Config, runner: R, file_ops: F) -> Self {
+        Project {
+            config,
+            runner,
+            file_ops,
+        }
+    }
+
+    /// Returns the project configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns the file system operations of the project.
+    pub fn file_ops(&self) -> &F {
+        &self.file_ops
+    }
+
+    /// Creates the directories of the workdir layout, as the CLI does before every command.
+    fn create_layout(&self) -> Result<(), WhitelistError> {
+        self.config
+            .layout_dirs()
+            .iter()
+            .try_for_each(|dir| self.file_ops.create_dir_all(dir))
+    }
+
+    /// Compiles the circuit with `circom`, as the `compile` subcommand does, leaving its witness
+    /// generator in `<name>_js`.
+    ///
+    /// # Returns
+    /// `Ok(())` once the `.r1cs`, `.sym` and `<name>_js` are in the artifact directory, or the
+    /// error of `circom`.
+    pub fn compile_circuit(&self) -> Result<(), WhitelistError> {
+        self.create_layout()?;
+        compile::compile_circuit(&self.runner, &self.file_ops, &self.config)
+    }
+
+    /// Compiles the circuit with `circom` and moves its witness generator to the artifact
+    /// directory, as the `compile` and `movejs` subcommands do.
+    ///
+    /// # Returns
+    /// The paths of the artifacts and the header of the constraint system.
+    pub fn compile(&self) -> Result<CompiledCircuit, WhitelistError> {
+        self.compile_circuit()?;
+        movejs::handle_movejs_subcommand(&self.file_ops, &self.config)?;
+
+        let name = self.config.circuit_name();
+        let r1cs = self.config.artifact(&format!("{}.r1cs", name));
+        let header = parse_r1cs_header(&self.file_ops.read_bytes(&r1cs)?)
+            .map_err(WhitelistError::ArtifactMismatch)?;
+        Ok(CompiledCircuit {
+            r1cs,
+            wasm: self.config.artifact(&format!("{}.wasm", name)),
+            header,
+        })
+    }
+
+    /// Runs the setup ceremony of the compiled circuit as the `setup` subcommand does,
+    /// encrypting its secrets when the configuration asks for it.
+    ///
+    /// # Parameters
+    /// - `options`: The names and entropy of the contributions.
+    /// - `progress`: Called with a message before each step and for each encrypted file.
+    pub fn run_setup(
+        &self,
+        options: SetupOptions,
+        progress: &mut dyn FnMut(&str),
+    ) -> Result<(), WhitelistError> {
+        self.create_layout()?;
+        let name = options.name.unwrap_or_else(|| Sentence(2..3).fake());
+        let entropy = options.entropy.unwrap_or_else(|| Sentence(3..4).fake());
+        setup::run_setup(
+            &self.runner,
+            &self.file_ops,
+            name,
+            entropy,
+            &self.config,
+            progress,
+        )
+    }
+
+    /// Runs the setup ceremony of the compiled circuit, encrypting its secrets when the
+    /// configuration asks for it.
+    ///
+    /// # Returns
+    /// The paths of the zkey and the verification key, and the number of public signals.
+    pub fn setup(&self, options: SetupOptions) -> Result<SetupOutput, WhitelistError> {
+        self.run_setup(options, &mut |_| {})?;
+
+        let verification_key = self.config.verification_key();
+        let n_public =
+            VerificationKey::from_json(&self.file_ops.read_to_string(&verification_key)?)
+                .map_err(WhitelistError::ArtifactMismatch)?
+                .n_public;
+        Ok(SetupOutput {
+            zkey: self.config.secret_file(&self.config.zkey("0001")),
+            verification_key,
+            n_public,
+        })
+    }
+
+    /// Writes the verifier described by the options of the `verifier` subcommand.
+    ///
+    /// # Returns
+    /// The path, the source and the warnings of the verifier.
+    pub(crate) fn write_verifier(
+        &self,
+        verifier_command: &VerifierCommand,
+    ) -> Result<Verifier, WhitelistError> {
+        self.create_layout()?;
+        verifier::write_verifier(&self.file_ops, verifier_command, &self.config)
+    }
+
+    /// Writes the Solidity verifier of the verification key, named after the verifier of the
+    /// configuration.
+    ///
+    /// # Returns
+    /// The path and the source of the verifier.
+    pub fn export_verifier(&self) -> Result<Verifier, WhitelistError> {
+        self.write_verifier(&VerifierCommand::default())
+    }
+
+    /// Reads the addresses of an input file, in the input format of the configuration.
+    ///
+    /// # Returns
+    /// The addresses, or `WhitelistError::InvalidInput` naming the file and the line of an
+    /// invalid address.
+    pub fn read_addresses(&self, input_file: &str) -> Result<Vec<String>, WhitelistError> {
+        proofs::parse_addresses(
+            &self.file_ops.read_lines(input_file)?,
+            self.config.input.format,
+        )
+        .map_err(|e| WhitelistError::InvalidInput(format!("{}: {}", input_file, e)))
+    }
+
+    /// Generates the proofs of the given addresses, reporting the progress of each one.
+    ///
+    /// # Returns
+    /// The proofs by address with the warnings of the scratch files, or the error of the first
+    /// proof that failed.
+    pub(crate) fn generate_proofs(
+        &self,
+        addresses: &[String],
+        progress: &mut dyn FnMut(ProofEvent),
+    ) -> Result<GeneratedProofs, WhitelistError> {
+        self.create_layout()?;
+        proofs::generate_proofs(
+            &self.runner,
+            &self.file_ops,
+            &self.config,
+            None,
+            addresses,
+            progress,
+        )
+    }
+
+    /// Writes the proofs file of an input file, in the output format of the configuration.
+    ///
+    /// # Returns
+    /// The path of the proofs file.
+    pub(crate) fn write_proofs_file(
+        &self,
+        input_file: &str,
+        proofs_map: &BTreeMap<String, ProofEntry>,
+    ) -> Result<String, WhitelistError> {
+        let path = self.config.proofs_file(input_file);
+        proofs::write_proofs_file(&self.file_ops, &self.config, &path, proofs_map)?;
+        Ok(path)
+    }
+
+    /// Generates the proofs of the given addresses, without writing a proofs file.
+    ///
+    /// # Returns
+    /// The proofs ordered by address with the warnings of the scratch files,
+    /// `WhitelistError::InvalidInput` if an address is invalid, or the error of the tool that
+    /// failed.
+    pub fn prove(&self, addresses: &[String]) -> Result<ProveOutput, WhitelistError> {
+        let addresses = proofs::parse_addresses(addresses, self.config.input.format)?;
+        let generated = self.generate_proofs(&addresses, &mut |_| {})?;
+        Ok(ProveOutput {
+            proofs: generated
+                .proofs
+                .into_iter()
+                .map(|(address, entry)| Proof { address, entry })
+                .collect(),
+            warnings: generated.warnings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::command_runner::MockCommandRunner;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use crate::utils::verification_key::tests::SAMPLE_VERIFICATION_KEY;
+
+    fn mock_project() -> Project<MockCommandRunner, MockFileSystemOps> {
+        Project::with_ops(
+            Config::default(),
+            MockCommandRunner::new(),
+            MockFileSystemOps::new(),
+        )
+    }
+
+    fn stub_verification_key(file_ops: &MockFileSystemOps) {
+        file_ops.stub_file_content(
+            "verification_key.json",
+            SAMPLE_VERIFICATION_KEY
+                .lines()
+                .map(str::to_string)
+                .collect(),
+        );
+    }
+
+    /// Tests that the setup runs the ceremony with the given contributions and reads the key.
+    #[test]
+    fn test_setup() {
+        // Arrange
+        let project = mock_project();
+        stub_verification_key(&project.file_ops);
+
+        // Act
+        let output = project
+            .setup(SetupOptions {
+                name: Some("name".to_string()),
+                entropy: Some("entropy".to_string()),
+            })
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            output,
+            SetupOutput {
+                zkey: "circuit_0001.zkey".to_string(),
+                verification_key: "verification_key.json".to_string(),
+                n_public: 2,
+            }
+        );
+        let calls = project.runner.calls();
+        assert_eq!(calls.len(), 6);
+        assert_eq!(calls[1].1[4], "--name=\"name\"");
+    }
+
+    /// Tests that the verifier is written and returned.
+    #[test]
+    fn test_export_verifier() {
+        // Arrange
+        let mut project = mock_project();
+        project.config.contracts.verifier = Some("AllowlistVerifier".to_string());
+        stub_verification_key(&project.file_ops);
+
+        // Act
+        let verifier = project.export_verifier().unwrap();
+
+        // Assert
+        assert_eq!(verifier.path, "verifier.sol");
+        assert!(verifier.source.contains("contract AllowlistVerifier"));
+        assert_eq!(
+            project.file_ops.get_written_content("verifier.sol"),
+            Some(verifier.source)
+        );
+    }
+
+    /// Tests that the proofs are returned without a proofs file, along with no warnings when
+    /// the scratch files were removed.
+    #[test]
+    fn test_prove() {
+        // Arrange
+        let project = mock_project();
+        let address = "0x1234567890abcdef1234567890abcdef12345678".to_string();
+        project.runner.stub_output(
+            "snarkjs",
+            r#"["0x1","0x2"],[["0x3","0x4"],["0x5","0x6"]],["0x7","0x8"],["0x9"]"#,
+        );
+
+        // Act
+        let output = project.prove(std::slice::from_ref(&address)).unwrap();
+
+        // Assert
+        assert_eq!(output.proofs.len(), 1);
+        assert_eq!(output.proofs[0].address, address);
+        assert!(output.warnings.is_empty());
+    }
+
+    /// Tests that no tool runs when an address is invalid.
+    #[test]
+    fn test_prove_with_invalid_address() {
+        // Arrange
+        let project = mock_project();
+
+        // Act
+        let error = project.prove(&["0x1234".to_string()]).unwrap_err();
+
+        // Assert
+        assert_eq!(error.exit_code(), 2);
+        assert!(project.runner.calls().is_empty());
+    }
+
+    /// Tests that a project opened in a directory without configuration resolves its paths
+    /// against it.
+    #[test]
+    fn test_open() {
+        let dir = std::env::temp_dir().join("zk_whitelist_project_open_test");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let project = Project::open(&dir).unwrap();
+
+        assert_eq!(
+            project.config().circuit.path,
+            dir.join("circuit.circom").to_string_lossy()
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,18 @@
-use std::io;
+use crate::utils::{
+    config::ToolsConfig,
+    error::WhitelistError,
+    filesystem_operations::{FileSystemOps, RealFileSystemOps},
+    interrupt::{self, Deferred},
+};
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::iter;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// A trait defining a generic command runner interface.
 ///
@@ -13,36 +26,500 @@ pub trait CommandRunner {
     /// - `args`: A slice of arguments to pass to the command.
     ///
     /// # Returns
-    /// A `Result` indicating the success or failure of the command execution.
-    fn run(&self, command: &str, args: &[&str]) -> Result<(), String>;
+    /// A `Result` indicating the success or failure of the command execution, with
+    /// `WhitelistError::ToolMissing` if the command is not installed and
+    /// `WhitelistError::SubprocessFailed` if it fails.
+    fn run(&self, command: &str, args: &[&str]) -> Result<(), WhitelistError>;
+
+    /// Executes a command and captures what it prints, whatever its exit status.
+    ///
+    /// # Returns
+    /// The standard output followed by the standard error, or an error if the command cannot
+    /// be started, e.g. `WhitelistError::ToolMissing` if it is not installed.
+    fn capture(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError>;
+
+    /// Executes a command as `run` does, and returns what it prints on the standard output.
+    ///
+    /// # Returns
+    /// The standard output of the command, or the same errors as `run`.
+    fn output(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError>;
+}
+
+/// Runs the commands with the runner it borrows, so that a `Project` can be built over a runner
+/// owned elsewhere.
+impl<T: CommandRunner + ?Sized> CommandRunner for &T {
+    fn run(&self, command: &str, args: &[&str]) -> Result<(), WhitelistError> {
+        (**self).run(command, args)
+    }
+
+    fn capture(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError> {
+        (**self).capture(command, args)
+    }
+
+    fn output(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError> {
+        (**self).output(command, args)
+    }
 }
 
+/// The number of trailing lines of output quoted in the error of a failed command.
+const ERROR_TAIL_LINES: usize = 20;
+
+/// The interval at which a running command is checked for its exit, its timeout and Ctrl-C.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// A real implementation of the `CommandRunner` trait that executes shell commands.
-pub struct RealCommandRunner;
+///
+/// The output of the commands is streamed live to the terminal when `verbose` is set, and
+/// appended to the log file of the current step when there is one. The tools are run as
+/// configured by `tools`, see `tool_command`, and killed once they run longer than the timeout
+/// of the step or when Ctrl-C is pressed.
+#[derive(Debug, Clone, Default)]
+pub struct RealCommandRunner {
+    verbose: bool,
+    log_dir: Option<String>,
+    log_file: Option<String>,
+    tools: ToolsConfig,
+    timeout: Option<u64>,
+}
 
-impl CommandRunner for RealCommandRunner {
-    fn run(&self, command: &str, args: &[&str]) -> Result<(), String> {
-        // Execute the command with the specified arguments
-        let output = std::process::Command::new(command)
-            .args(args)
-            .output()
-            .map_err(|e| e.to_string())?;
+impl RealCommandRunner {
+    /// Creates a runner.
+    ///
+    /// # Parameters
+    /// - `verbose`: Streams the output of the commands to the terminal as they run.
+    /// - `log_dir`: The directory of the per-step log files, see `for_step`.
+    pub fn new(verbose: bool, log_dir: Option<String>) -> Self {
+        RealCommandRunner {
+            verbose,
+            log_dir,
+            log_file: None,
+            tools: ToolsConfig::default(),
+            timeout: None,
+        }
+    }
 
-        // Check the exit status of the command
-        if !output.status.success() {
-            return Err(format!("Command failed: {:?}", output));
+    /// Returns a runner running the tools with the given binaries, environment, working
+    /// directory and timeout.
+    pub fn with_tools(self, tools: ToolsConfig) -> Self {
+        RealCommandRunner {
+            timeout: tools.timeout,
+            tools,
+            ..self
         }
+    }
 
-        Ok(())
+    /// Prepares the process of a command, as configured by `tools`.
+    ///
+    /// # Returns
+    /// The process and its command line, or `WhitelistError::InvalidInput` if the working
+    /// directory of the tools does not exist.
+    fn prepare(&self, command: &str, args: &[&str]) -> Result<(Command, String), WhitelistError> {
+        let (program, args) = tool_invocation(&self.tools, command, args);
+        let mut process = Command::new(&program);
+        process.args(&args).envs(&self.tools.env);
+        // The tool leads its own process group, so that killing the group also kills the
+        // processes it started, see `kill`.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut process, 0);
+        if let Some(working_dir) = &self.tools.working_dir {
+            if !Path::new(working_dir).is_dir() {
+                return Err(WhitelistError::InvalidInput(format!(
+                    "The working directory of the tools {} does not exist",
+                    working_dir
+                )));
+            }
+            process.current_dir(working_dir);
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        Ok((process, format_command_line(&program, &args)))
+    }
+
+    /// Returns a runner logging the output of the commands to `<log_dir>/<step>.log`, with
+    /// the timeout of the step.
+    ///
+    /// The log file is truncated, so it only holds the output of the latest run of the step.
+    pub fn for_step(&self, step: &str) -> Result<Self, WhitelistError> {
+        let timeout = self
+            .tools
+            .timeouts
+            .get(step)
+            .copied()
+            .or(self.tools.timeout);
+        let Some(log_dir) = &self.log_dir else {
+            return Ok(RealCommandRunner {
+                timeout,
+                ..self.clone()
+            });
+        };
+        RealFileSystemOps.create_dir_all(log_dir)?;
+        let log_file = Path::new(log_dir)
+            .join(format!("{}.log", step))
+            .to_string_lossy()
+            .to_string();
+        RealFileSystemOps.write_to_file(&log_file, "")?;
+        Ok(RealCommandRunner {
+            log_file: Some(log_file),
+            timeout,
+            ..self.clone()
+        })
+    }
+
+    fn open_log(&self) -> Result<Option<Box<dyn Write + Send>>, WhitelistError> {
+        self.log_file
+            .as_ref()
+            .map(|path| RealFileSystemOps.append(path))
+            .transpose()
+    }
+
+    /// Runs a command, streaming its output to the log file and, when verbose, the terminal.
+    ///
+    /// # Returns
+    /// The standard output of the command when `keep_stdout` is set, an empty string otherwise.
+    fn execute(
+        &self,
+        command: &str,
+        args: &[&str],
+        keep_stdout: bool,
+    ) -> Result<String, WhitelistError> {
+        let (mut process, command_line) = self.prepare(command, args)?;
+        if interrupt::is_interrupted() {
+            return Err(WhitelistError::Interrupted(command_line));
+        }
+        if self.verbose {
+            println!("$ {}", command_line);
+        }
+        let mut log = self.open_log()?;
+        if let Some(log) = log.as_mut() {
+            let _ = writeln!(log, "$ {}", command_line);
+        }
+
+        let _running = Deferred::start();
+        let mut child = process
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| spawn_error(command, &command_line, e))?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        // Both streams are drained at once, so a child filling one pipe never blocks, while
+        // this thread watches for the timeout and Ctrl-C.
+        let log = Mutex::new(log);
+        let timeout = self.timeout.map(Duration::from_secs);
+        let (waited, stdout_tail, stderr_tail) = thread::scope(|scope| {
+            let stdout_thread =
+                scope.spawn(|| forward(stdout, &log, self.verbose, false, keep_stdout));
+            let stderr_thread = scope.spawn(|| forward(stderr, &log, self.verbose, true, false));
+            let waited = wait_child(&mut child, timeout);
+            (
+                waited,
+                stdout_thread.join().unwrap_or_default(),
+                stderr_thread.join().unwrap_or_default(),
+            )
+        });
+        let waited = waited.map_err(|e| spawn_error(command, &command_line, e))?;
+        if let Some(log) = log.lock().unwrap().as_mut() {
+            let _ = writeln!(log, "{}", waited);
+        }
+        let failure = match waited {
+            Waited::Exited(status) if status.success() => {
+                return Ok(match keep_stdout {
+                    true => Vec::from(stdout_tail).join("\n"),
+                    false => String::new(),
+                });
+            }
+            Waited::Interrupted => return Err(WhitelistError::Interrupted(command_line)),
+            Waited::Exited(status) => match status.code() {
+                Some(code) => format!("failed with exit code {}", code),
+                None => "failed with no exit code, terminated by a signal".to_string(),
+            },
+            Waited::TimedOut => format!(
+                "timed out after {} seconds and was killed",
+                self.timeout.unwrap_or_default()
+            ),
+        };
+
+        let mut message = format!("Command `{}` {}", command_line, failure);
+        // Some tools, snarkjs among them, report their errors on the standard output.
+        let tail = if stderr_tail.is_empty() {
+            stdout_tail
+        } else {
+            stderr_tail
+        };
+        for line in tail
+            .iter()
+            .skip(tail.len().saturating_sub(ERROR_TAIL_LINES))
+        {
+            message.push_str("\n  ");
+            message.push_str(line);
+        }
+        if let Some(log_file) = &self.log_file {
+            message.push_str(&format!("\nThe full output is in {}", log_file));
+        }
+        Err(WhitelistError::SubprocessFailed(message))
+    }
+}
+
+/// How a command ended.
+enum Waited {
+    Exited(ExitStatus),
+    TimedOut,
+    Interrupted,
+}
+
+impl std::fmt::Display for Waited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Waited::Exited(status) => write!(f, "{}", status),
+            Waited::TimedOut => write!(f, "timed out"),
+            Waited::Interrupted => write!(f, "interrupted"),
+        }
+    }
+}
+
+/// Waits for a child to exit, killing it once it outlives `timeout` or Ctrl-C is pressed.
+fn wait_child(child: &mut Child, timeout: Option<Duration>) -> io::Result<Waited> {
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Waited::Exited(status));
+        }
+        let waited = if interrupt::is_interrupted() {
+            Waited::Interrupted
+        } else if timeout.is_some_and(|timeout| started.elapsed() >= timeout) {
+            Waited::TimedOut
+        } else {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        };
+        kill(child);
+        child.wait()?;
+        return Ok(waited);
+    }
+}
+
+/// Kills a child along with the processes it started, e.g. the node process of `npx`.
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = i32::try_from(child.id()) {
+        // SAFETY: `kill` has no memory safety requirements, and the negative pid targets the
+        // process group the child leads.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+/// Runs a command writing the given output files as `run` does, so that a failed, timed out
+/// or interrupted command never leaves a partial output behind.
+///
+/// The command writes each output to `<output>.partial`, which is renamed to the output once
+/// the command succeeded and removed otherwise.
+///
+/// # Parameters
+/// - `runner`: The command runner executing the command.
+/// - `file_ops`: The file system operations renaming and removing the outputs.
+/// - `command`, `args`: The command, whose arguments name the outputs.
+/// - `outputs`: The paths of the files the command writes.
+pub fn run_with_outputs<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    command: &str,
+    args: &[&str],
+    outputs: &[&str],
+) -> Result<(), WhitelistError> {
+    run_with_partial_outputs(runner, file_ops, command, args, outputs, false)
+}
+
+/// Runs a command writing secret output files as `run_with_outputs` does.
+///
+/// Each `<output>.partial` is created with 0600 permissions before the command runs, so the
+/// secret is only readable by its owner from the first byte the command writes.
+pub fn run_with_private_outputs<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    command: &str,
+    args: &[&str],
+    outputs: &[&str],
+) -> Result<(), WhitelistError> {
+    run_with_partial_outputs(runner, file_ops, command, args, outputs, true)
+}
+
+fn run_with_partial_outputs<R: CommandRunner, F: FileSystemOps>(
+    runner: &R,
+    file_ops: &F,
+    command: &str,
+    args: &[&str],
+    outputs: &[&str],
+    private: bool,
+) -> Result<(), WhitelistError> {
+    let partial = |path: &str| format!("{}.partial", path);
+    if private {
+        for output in outputs {
+            file_ops.create_private(&partial(output))?;
+        }
+    }
+    let partial_args: Vec<String> = args
+        .iter()
+        .map(|arg| match outputs.contains(arg) {
+            true => partial(arg),
+            false => arg.to_string(),
+        })
+        .collect();
+    let partial_args: Vec<&str> = partial_args.iter().map(String::as_str).collect();
+    if let Err(error) = runner.run(command, &partial_args) {
+        for output in outputs {
+            if file_ops.exists(&partial(output)) {
+                let _ = file_ops.remove(&partial(output));
+            }
+        }
+        return Err(error);
+    }
+    outputs
+        .iter()
+        .try_for_each(|output| file_ops.rename(&partial(output), output))
+}
+
+/// Returns the command line prefix running a tool: the binary set for it, `npx --no -- snarkjs`
+/// when `npx_fallback` is set and `snarkjs` is not on the PATH, or the tool itself.
+pub fn tool_command(tools: &ToolsConfig, tool: &str) -> Vec<String> {
+    if let Some(binary) = tools.binary(tool) {
+        return vec![binary.to_string()];
+    }
+    if tool == "snarkjs" && tools.npx_fallback && !on_path(tool) {
+        return vec![
+            "npx".to_string(),
+            "--no".to_string(),
+            "--".to_string(),
+            tool.to_string(),
+        ];
     }
+    vec![tool.to_string()]
+}
+
+/// Returns the program running a tool and its arguments, see `tool_command`.
+fn tool_invocation(tools: &ToolsConfig, tool: &str, args: &[&str]) -> (String, Vec<String>) {
+    let mut invocation = tool_command(tools, tool);
+    let program = invocation.remove(0);
+    invocation.extend(args.iter().map(|arg| arg.to_string()));
+    (program, invocation)
 }
 
+/// Returns whether a program is found in a directory of the PATH.
+fn on_path(program: &str) -> bool {
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|dir| {
+            let candidate = dir.join(program);
+            candidate.is_file()
+                || (cfg!(windows)
+                    && ["exe", "cmd"]
+                        .iter()
+                        .any(|extension| candidate.with_extension(extension).is_file()))
+        })
+    })
+}
+
+/// Converts the error of starting a command, reporting a command not found as a missing tool.
+fn spawn_error(command: &str, command_line: &str, error: io::Error) -> WhitelistError {
+    match error.kind() {
+        io::ErrorKind::NotFound => WhitelistError::ToolMissing {
+            tool: command.to_string(),
+        },
+        kind => WhitelistError::Io(io::Error::new(
+            kind,
+            format!("Failed to run `{}`: {}", command_line, error),
+        )),
+    }
+}
+
+/// Formats a command line as it would be typed in a shell, quoting the arguments that need it.
+pub fn format_command_line(command: &str, args: &[&str]) -> String {
+    iter::once(command)
+        .chain(args.iter().copied())
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+            if plain {
+                arg.to_string()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Forwards the lines of a child stream to the log file, and to the terminal when `verbose`.
+///
+/// # Returns
+/// Every line of the stream when `keep_all` is set, otherwise its last `ERROR_TAIL_LINES` lines.
+fn forward<R: Read>(
+    stream: R,
+    log: &Mutex<Option<Box<dyn Write + Send>>>,
+    verbose: bool,
+    is_stderr: bool,
+    keep_all: bool,
+) -> VecDeque<String> {
+    let mut reader = BufReader::new(stream);
+    let mut tail = VecDeque::with_capacity(ERROR_TAIL_LINES);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).unwrap_or(0) > 0 {
+        if let Some(log) = log.lock().unwrap().as_mut() {
+            let _ = log.write_all(&line);
+        }
+        if verbose {
+            let _ = if is_stderr {
+                io::stderr().write_all(&line)
+            } else {
+                io::stdout().write_all(&line)
+            };
+        }
+        if !keep_all && tail.len() == ERROR_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(String::from_utf8_lossy(&line).trim_end().to_string());
+        line.clear();
+    }
+    tail
+}
+
+impl CommandRunner for RealCommandRunner {
+    fn run(&self, command: &str, args: &[&str]) -> Result<(), WhitelistError> {
+        self.execute(command, args, false).map(|_| ())
+    }
+
+    fn capture(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError> {
+        let (mut process, command_line) = self.prepare(command, args)?;
+        let output = process
+            .output()
+            .map_err(|e| spawn_error(command, &command_line, e))?;
+        Ok(format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
+
+    fn output(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError> {
+        self.execute(command, args, true)
+    }
+}
+
+/// A recorded command invocation: the command name and its arguments.
+type RecordedCall = (String, Vec<String>);
+
 /// A mock implementation of the `CommandRunner` trait for testing purposes.
 ///
 /// This implementation records the commands and arguments it was called with,
 /// allowing tests to verify correct behavior without executing real shell commands.
 pub struct MockCommandRunner {
-    calls: Arc<Mutex<Vec<(String, Vec<String>)>>>,
+    calls: Arc<Mutex<Vec<RecordedCall>>>,
+    stubbed_outputs: Arc<Mutex<HashMap<String, String>>>,
+    failing: Arc<Mutex<Vec<String>>>,
 }
 
 impl MockCommandRunner {
@@ -50,26 +527,122 @@ impl MockCommandRunner {
     pub fn new() -> Self {
         MockCommandRunner {
             calls: Arc::new(Mutex::new(Vec::new())),
+            stubbed_outputs: Arc::new(Mutex::new(HashMap::new())),
+            failing: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    /// Makes `run` fail for a command, as if it exited with an error.
+    pub fn stub_failure(&self, command: &str) {
+        self.failing.lock().unwrap().push(command.to_string());
+    }
+
+    /// Makes `capture` return the given output for a command.
+    ///
+    /// Commands without a stubbed output behave as if they were not installed.
+    pub fn stub_output(&self, command: &str, output: &str) {
+        self.stubbed_outputs
+            .lock()
+            .unwrap()
+            .insert(command.to_string(), output.to_string());
+    }
+
     /// Returns a list of command and argument tuples that this runner was called with.
     pub fn calls(&self) -> Vec<(String, Vec<String>)> {
         self.calls.lock().unwrap().clone()
     }
 }
 
+impl Default for MockCommandRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CommandRunner for MockCommandRunner {
-    fn run(&self, command: &str, args: &[&str]) -> Result<(), String> {
+    fn run(&self, command: &str, args: &[&str]) -> Result<(), WhitelistError> {
         // Record the command and arguments
         self.calls.lock().unwrap().push((
             command.to_string(),
             args.iter().map(|&s| s.to_string()).collect(),
         ));
+        if self.failing.lock().unwrap().iter().any(|c| c == command) {
+            return Err(WhitelistError::SubprocessFailed(format!(
+                "Command `{}` failed with exit code 1",
+                command
+            )));
+        }
+        Ok(())
+    }
+
+    fn capture(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError> {
+        self.calls.lock().unwrap().push((
+            command.to_string(),
+            args.iter().map(|&s| s.to_string()).collect(),
+        ));
+        self.stubbed_outputs
+            .lock()
+            .unwrap()
+            .get(command)
+            .cloned()
+            .ok_or_else(|| WhitelistError::ToolMissing {
+                tool: command.to_string(),
+            })
+    }
+
+    fn output(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError> {
+        self.capture(command, args)
+    }
+}
+
+/// A `CommandRunner` for `--dry-run`, printing the commands that would run instead of running
+/// them.
+///
+/// Captured commands only query the tools, e.g. for their version, so they still run.
+#[derive(Debug, Clone, Default)]
+pub struct DryRunCommandRunner {
+    tools: ToolsConfig,
+}
+
+impl DryRunCommandRunner {
+    /// Creates a runner printing the commands as they would run with the given tools.
+    pub fn new(tools: ToolsConfig) -> Self {
+        DryRunCommandRunner { tools }
+    }
+
+    fn print(&self, command: &str, args: &[&str]) -> String {
+        let (program, args) = tool_invocation(&self.tools, command, args);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let command_line = format_command_line(&program, &args);
+        println!("$ {}", command_line);
+        command_line
+    }
+}
+
+impl CommandRunner for DryRunCommandRunner {
+    fn run(&self, command: &str, args: &[&str]) -> Result<(), WhitelistError> {
+        self.print(command, args);
         Ok(())
     }
+
+    fn capture(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError> {
+        RealCommandRunner::default()
+            .with_tools(self.tools.clone())
+            .capture(command, args)
+    }
+
+    /// Prints the command and returns placeholder calldata in the format of `snarkjs zkesc`,
+    /// the only command whose output is read, so a dry run goes through every address.
+    fn output(&self, command: &str, args: &[&str]) -> Result<String, WhitelistError> {
+        self.print(command, args);
+        Ok(DRY_RUN_CALLDATA.to_string())
+    }
 }
 
+/// The calldata returned by `DryRunCommandRunner::output`, with every value zero.
+pub const DRY_RUN_CALLDATA: &str =
+    r#"["0x0","0x0"],[["0x0","0x0"],["0x0","0x0"]],["0x0","0x0"],["0x0","0x0"]"#;
+
 /// Executes a `snarkjs` command with the specified arguments using the provided `CommandRunner`.
 ///
 /// # Parameters
@@ -78,15 +651,18 @@ impl CommandRunner for MockCommandRunner {
 ///
 /// # Returns
 /// A `Result` indicating the success or failure of the command execution.
-pub fn run_snarkjs_command<R: CommandRunner>(runner: &R, args: &[&str]) -> io::Result<()> {
-    runner
-        .run("snarkjs", args)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+pub fn run_snarkjs_command<R: CommandRunner>(
+    runner: &R,
+    args: &[&str],
+) -> Result<(), WhitelistError> {
+    runner.run("snarkjs", args)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use std::fs;
 
     #[test]
     /// Test to verify the execution of `snarkjs` command using `MockCommandRunner`.
@@ -105,4 +681,220 @@ mod tests {
             )]
         );
     }
+
+    #[test]
+    fn test_format_command_line() {
+        assert_eq!(
+            format_command_line("snarkjs", &["zkey", "contribute", "--name=a b", "it's"]),
+            "snarkjs zkey contribute '--name=a b' 'it'\\''s'"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_real_command_runner_failure() {
+        let log_dir = std::env::temp_dir()
+            .join("zk_whitelist_test_runner_logs")
+            .to_string_lossy()
+            .to_string();
+        let runner = RealCommandRunner::new(false, Some(log_dir.clone()))
+            .for_step("setup")
+            .unwrap();
+
+        let result = runner.run("sh", &["-c", "echo progress; echo bad input >&2; exit 3"]);
+
+        let log_file = Path::new(&log_dir).join("setup.log");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            format!(
+                "Command `sh -c 'echo progress; echo bad input >&2; exit 3'` failed with exit code 3\n  bad input\nThe full output is in {}",
+                log_file.display()
+            )
+        );
+        let log = fs::read_to_string(&log_file).unwrap();
+        assert!(log.starts_with("$ sh -c"));
+        assert!(log.contains("progress\n"));
+        assert!(log.contains("bad input\n"));
+        fs::remove_dir_all(log_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_real_command_runner_output() {
+        let runner = RealCommandRunner::default();
+
+        let output = runner.output("sh", &["-c", "echo '[\"0x01\"]'; echo warning >&2"]);
+
+        assert_eq!(output.unwrap(), "[\"0x01\"]");
+    }
+
+    #[test]
+    fn test_dry_run_command_runner() {
+        let runner = DryRunCommandRunner::default();
+
+        assert!(runner.run("circom", &["circuit.circom", "--r1cs"]).is_ok());
+        assert_eq!(
+            runner
+                .output("snarkjs", &["zkesc", "public.json", "proof.json"])
+                .unwrap(),
+            DRY_RUN_CALLDATA
+        );
+    }
+
+    #[test]
+    fn test_tool_command() {
+        let tools = ToolsConfig {
+            circom: Some("/opt/circom/circom".to_string()),
+            npx_fallback: true,
+            ..ToolsConfig::default()
+        };
+        let snarkjs = match on_path("snarkjs") {
+            true => vec!["snarkjs"],
+            false => vec!["npx", "--no", "--", "snarkjs"],
+        };
+
+        assert_eq!(tool_command(&tools, "circom"), vec!["/opt/circom/circom"]);
+        assert_eq!(tool_command(&tools, "snarkjs"), snarkjs);
+        assert_eq!(tool_command(&tools, "node"), vec!["node"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_real_command_runner_with_tools() {
+        let working_dir = std::env::temp_dir().canonicalize().unwrap();
+        let runner = RealCommandRunner::default().with_tools(ToolsConfig {
+            env: [("ZK_WHITELIST_TEST".to_string(), "8192".to_string())].into(),
+            working_dir: Some(working_dir.to_string_lossy().to_string()),
+            ..ToolsConfig::default()
+        });
+
+        let output = runner.output("sh", &["-c", "echo $ZK_WHITELIST_TEST; pwd -P"]);
+
+        assert_eq!(
+            output.unwrap(),
+            format!("8192\n{}", working_dir.to_string_lossy())
+        );
+        let missing_dir = RealCommandRunner::default().with_tools(ToolsConfig {
+            working_dir: Some("zk_whitelist_missing_tools_dir".to_string()),
+            ..ToolsConfig::default()
+        });
+        assert_eq!(missing_dir.run("sh", &[]).unwrap_err().exit_code(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_real_command_runner_not_installed() {
+        let result = RealCommandRunner::default().run("zk_whitelist_missing_tool", &["--version"]);
+
+        assert!(matches!(
+            result,
+            Err(WhitelistError::ToolMissing { tool }) if tool == "zk_whitelist_missing_tool"
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_real_command_runner_timeout() {
+        let runner = RealCommandRunner::default().with_tools(ToolsConfig {
+            timeout: Some(1),
+            ..ToolsConfig::default()
+        });
+        let started = Instant::now();
+
+        let result = runner.run("sh", &["-c", "sleep 10"]);
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Command `sh -c 'sleep 10'` timed out after 1 seconds and was killed"
+        );
+    }
+
+    #[test]
+    fn test_for_step_timeout() {
+        let tools = ToolsConfig {
+            timeout: Some(600),
+            timeouts: [("setup".to_string(), 7200)].into(),
+            ..ToolsConfig::default()
+        };
+        let runner = RealCommandRunner::default().with_tools(tools);
+
+        assert_eq!(runner.for_step("setup").unwrap().timeout, Some(7200));
+        assert_eq!(runner.for_step("compile").unwrap().timeout, Some(600));
+    }
+
+    /// Tests that the outputs are written to `.partial` files, moved into place on success.
+    #[test]
+    fn test_run_with_outputs() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+
+        let result = run_with_outputs(
+            &runner,
+            &file_ops,
+            "snarkjs",
+            &["new", "a.ptau"],
+            &["a.ptau"],
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(
+            runner.calls(),
+            vec![(
+                "snarkjs".to_string(),
+                vec!["new".to_string(), "a.ptau.partial".to_string()]
+            )]
+        );
+        assert_eq!(
+            file_ops.operations(),
+            vec![("rename".to_string(), "a.ptau.partial a.ptau".to_string())]
+        );
+    }
+
+    /// Tests that the partial outputs of secrets are created private before the command runs.
+    #[test]
+    fn test_run_with_private_outputs() {
+        let runner = MockCommandRunner::new();
+        let file_ops = MockFileSystemOps::new();
+
+        let result = run_with_private_outputs(
+            &runner,
+            &file_ops,
+            "snarkjs",
+            &["new", "a.ptau"],
+            &["a.ptau"],
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(
+            file_ops.operations(),
+            vec![
+                ("create_private".to_string(), "a.ptau.partial".to_string()),
+                ("rename".to_string(), "a.ptau.partial a.ptau".to_string())
+            ]
+        );
+    }
+
+    /// Tests that the partial outputs of a failed command are removed.
+    #[test]
+    fn test_run_with_outputs_failure() {
+        let runner = MockCommandRunner::new();
+        runner.stub_failure("snarkjs");
+        let file_ops = MockFileSystemOps::new();
+        file_ops.stub_modified("a.ptau.partial", std::time::SystemTime::now());
+
+        let result = run_with_outputs(
+            &runner,
+            &file_ops,
+            "snarkjs",
+            &["new", "a.ptau", "b.ptau"],
+            &["a.ptau", "b.ptau"],
+        );
+
+        assert!(result.is_err());
+        assert_eq!(
+            file_ops.operations(),
+            vec![("remove".to_string(), "a.ptau.partial".to_string())]
+        );
+    }
 }
@@ -0,0 +1,684 @@
+use crate::utils::{error::WhitelistError, filesystem_operations::FileSystemOps};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+};
+
+/// The name of the project configuration file.
+pub const CONFIG_FILE_NAME: &str = "zk_whitelist.toml";
+
+/// The proving system used for the setup and the proofs.
+#[derive(ValueEnum, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    /// Groth16, with a circuit specific trusted setup
+    #[default]
+    Groth16,
+}
+
+impl Protocol {
+    /// Returns the name of the protocol as used by `snarkjs` commands.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Protocol::Groth16 => "groth16",
+        }
+    }
+}
+
+/// The format of the file listing the whitelisted addresses.
+#[derive(ValueEnum, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    /// One address per line
+    #[default]
+    Lines,
+    /// Comma separated values with the address in the first column and an optional header
+    Csv,
+}
+
+/// The format of the generated proofs file.
+#[derive(ValueEnum, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// A JSON object mapping every address to its proof
+    #[default]
+    Json,
+    /// Comma separated values with one proof per row
+    Csv,
+}
+
+impl OutputFormat {
+    /// Returns the file extension of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        }
+    }
+}
+
+/// The `[circuit]` table of the configuration.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CircuitConfig {
+    /// The circom file of the circuit.
+    pub path: String,
+    /// The power of two of the maximum number of constraints supported by the ptau files.
+    pub ptau_power: u8,
+    /// The proving system.
+    pub protocol: Protocol,
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        CircuitConfig {
+            path: "circuit.circom".to_string(),
+            ptau_power: 12,
+            protocol: Protocol::Groth16,
+        }
+    }
+}
+
+/// The `[artifacts]` table of the configuration.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArtifactsConfig {
+    /// The directory of the compiled circuit, the ceremony files and the scratch files of the proofs.
+    pub dir: String,
+    /// A working directory separating the generated files into `private/`, `public/` and `tmp/`.
+    ///
+    /// When set, it takes the place of `dir`.
+    pub workdir: Option<String>,
+}
+
+impl Default for ArtifactsConfig {
+    fn default() -> Self {
+        ArtifactsConfig {
+            dir: ".".to_string(),
+            workdir: None,
+        }
+    }
+}
+
+/// The `[contracts]` table of the configuration.
+///
+/// Unset values fall back to the defaults of the `verifier` and `token` subcommands.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContractsConfig {
+    /// The name of the verifier contract.
+    pub verifier: Option<String>,
+    /// The name of the token contract.
+    pub token: Option<String>,
+    /// The number of whole tokens minted for every proof.
+    pub amount: Option<u64>,
+}
+
+/// The `[input]` table of the configuration.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputConfig {
+    /// The file listing the whitelisted addresses.
+    pub file: String,
+    /// The format of the file.
+    pub format: InputFormat,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        InputConfig {
+            file: "addresses.txt".to_string(),
+            format: InputFormat::Lines,
+        }
+    }
+}
+
+/// Reads a configuration file, reporting a missing one as such rather than as a missing
+/// artifact.
+fn read_config_file<F: FileSystemOps>(file_ops: &F, path: &Path) -> Result<String, String> {
+    file_ops
+        .read_to_string(&path.to_string_lossy())
+        .map_err(|e| match e {
+            WhitelistError::ArtifactMissing(_) => {
+                format!("{}: the configuration file does not exist", path.display())
+            }
+            e => e.to_string(),
+        })
+}
+
+/// How the progress of the proofs is reported.
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressMode {
+    /// A progress bar with the proofs per second and the time left
+    #[default]
+    Bar,
+    /// No progress at all
+    Quiet,
+    /// One JSON event per line, for log aggregation
+    Json,
+}
+
+/// The `[output]` table of the configuration.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// The format of the proofs file.
+    pub format: OutputFormat,
+    /// How the progress of the proofs is reported.
+    pub progress: ProgressMode,
+}
+
+/// The `[secrets]` table of the configuration.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecretsConfig {
+    /// Encrypts the zkeys and ptau files once the setup has written them.
+    pub encrypt: bool,
+    /// An age identity file encrypting the secrets instead of a passphrase.
+    pub keyfile: Option<String>,
+}
+
+/// The `[tools]` table of the configuration, telling how the external tools are run.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolsConfig {
+    /// The `circom` binary, looked up on the PATH when unset.
+    pub circom: Option<String>,
+    /// The `snarkjs` binary, looked up on the PATH when unset.
+    pub snarkjs: Option<String>,
+    /// The `node` binary, looked up on the PATH when unset.
+    pub node: Option<String>,
+    /// Runs `snarkjs` with `npx` when it is not set and not on the PATH, e.g. from the
+    /// `node_modules` of the working directory.
+    pub npx_fallback: bool,
+    /// Environment variables set for every tool, e.g. `NODE_OPTIONS`.
+    pub env: BTreeMap<String, String>,
+    /// The directory the tools run in, the current directory when unset.
+    pub working_dir: Option<String>,
+    /// The number of seconds after which a tool is killed, none by default.
+    pub timeout: Option<u64>,
+    /// The timeout of the tools of a step, e.g. `setup`, overriding `timeout`.
+    pub timeouts: BTreeMap<String, u64>,
+}
+
+impl ToolsConfig {
+    /// Returns the binary set for a tool, if any.
+    pub fn binary(&self, tool: &str) -> Option<&str> {
+        match tool {
+            "circom" => self.circom.as_deref(),
+            "snarkjs" => self.snarkjs.as_deref(),
+            "node" => self.node.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Resolves a binary against `root` when it is a path, leaving a bare name to the PATH lookup.
+fn join_binary(root: &Path, binary: String) -> String {
+    match binary.contains(['/', '\\']) {
+        true => join(root, &binary),
+        false => binary,
+    }
+}
+
+/// The project configuration, read from `zk_whitelist.toml`.
+///
+/// Every table and value is optional, the defaults match the files the tool generates
+/// when no configuration exists.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub circuit: CircuitConfig,
+    pub artifacts: ArtifactsConfig,
+    pub contracts: ContractsConfig,
+    pub input: InputConfig,
+    pub output: OutputConfig,
+    pub secrets: SecretsConfig,
+    pub tools: ToolsConfig,
+}
+
+/// Joins two paths, leaving out `.` components so that paths in the current directory stay bare.
+fn join(base: &Path, path: &str) -> String {
+    base.join(path)
+        .components()
+        .filter(|component| *component != Component::CurDir)
+        .collect::<PathBuf>()
+        .to_string_lossy()
+        .to_string()
+}
+
+impl Config {
+    /// Parses the content of a configuration file.
+    ///
+    /// Relative paths are resolved against `root`, the directory of the configuration file.
+    pub fn from_toml(content: &str, root: &Path) -> Result<Self, String> {
+        let config: Config =
+            toml::from_str(content).map_err(|e| format!("Invalid {}: {}", CONFIG_FILE_NAME, e))?;
+        if !(1..=28).contains(&config.circuit.ptau_power) {
+            return Err(format!(
+                "Invalid {}: ptau_power must be between 1 and 28",
+                CONFIG_FILE_NAME
+            ));
+        }
+        Ok(config.resolve_paths(root))
+    }
+
+    /// Returns the configuration with its relative paths resolved against `base`.
+    ///
+    /// Resolving them against the current directory makes them absolute, as needed when the
+    /// tools run in another directory and get the paths of the artifacts as arguments.
+    pub fn resolve_paths(&self, base: &Path) -> Self {
+        let mut config = self.clone();
+        config.circuit.path = join(base, &config.circuit.path);
+        config.artifacts.dir = join(base, &config.artifacts.dir);
+        config.artifacts.workdir = config.artifacts.workdir.map(|workdir| join(base, &workdir));
+        config.input.file = join(base, &config.input.file);
+        config.secrets.keyfile = config.secrets.keyfile.map(|keyfile| join(base, &keyfile));
+        let tools = &mut config.tools;
+        for binary in [&mut tools.circom, &mut tools.snarkjs, &mut tools.node] {
+            *binary = binary.take().map(|binary| join_binary(base, binary));
+        }
+        tools.working_dir = tools.working_dir.take().map(|dir| join(base, &dir));
+        config
+    }
+
+    /// Reads the configuration file at the given path.
+    pub fn from_file<F: FileSystemOps>(file_ops: &F, path: &Path) -> Result<Self, String> {
+        let content = read_config_file(file_ops, path)?;
+        Config::from_toml(&content, path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Looks for `zk_whitelist.toml` in `start` and its parent directories.
+    ///
+    /// # Returns
+    /// The first configuration found, the default configuration if there is none, or an error
+    /// message if the configuration found is invalid.
+    pub fn discover<F: FileSystemOps>(file_ops: &F, start: &Path) -> Result<Self, String> {
+        for directory in start.ancestors() {
+            let path = directory.join(CONFIG_FILE_NAME);
+            if file_ops.exists(&path.to_string_lossy()) {
+                let content = read_config_file(file_ops, &path)?;
+                // Paths of a configuration in the start directory itself stay relative.
+                let root = directory.strip_prefix(start).unwrap_or(directory);
+                return Config::from_toml(&content, root);
+            }
+        }
+        Ok(Config::default())
+    }
+
+    /// Returns the name of the circuit, which prefixes the files `circom` generates.
+    pub fn circuit_name(&self) -> String {
+        Path::new(&self.circuit.path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "circuit".to_string())
+    }
+
+    /// Returns a directory of the layout, `.` for the current directory.
+    ///
+    /// With a working directory this is its `subdir`, otherwise `fallback`.
+    fn layout_dir(&self, subdir: &str, fallback: &str) -> String {
+        let dir = match &self.artifacts.workdir {
+            Some(workdir) => join(Path::new(workdir), subdir),
+            None => join(Path::new(""), fallback),
+        };
+        if dir.is_empty() {
+            ".".to_string()
+        } else {
+            dir
+        }
+    }
+
+    /// Returns the directory of the private artifacts: the compiled circuit, the ceremony
+    /// files and the witnesses, which let anyone holding them generate proofs.
+    pub fn artifact_dir(&self) -> String {
+        self.layout_dir("private", &self.artifacts.dir)
+    }
+
+    /// Returns the path of a file in the private artifact directory.
+    pub fn artifact(&self, file_name: &str) -> String {
+        join(Path::new(&self.artifact_dir()), file_name)
+    }
+
+    /// Returns the directory of the files meant to be shared: the contracts, the verification
+    /// key and the proofs. Without a working directory this is the current directory.
+    pub fn public_dir(&self) -> String {
+        self.layout_dir("public", ".")
+    }
+
+    /// Returns the path of a file in the public directory.
+    pub fn public_file(&self, file_name: &str) -> String {
+        join(Path::new(&self.public_dir()), file_name)
+    }
+
+    /// Returns the directory of the scratch files written while generating proofs.
+    pub fn tmp_dir(&self) -> String {
+        self.layout_dir("tmp", &self.artifacts.dir)
+    }
+
+    /// Returns the path of a file in the scratch directory.
+    pub fn tmp_file(&self, file_name: &str) -> String {
+        join(Path::new(&self.tmp_dir()), file_name)
+    }
+
+    /// Returns the directory of the log files holding the output of each step.
+    ///
+    /// The logs are hidden in `.zk_whitelist/logs` when the scratch files share the project
+    /// directory, and go to `logs` in the scratch directory of a working directory.
+    pub fn log_dir(&self) -> String {
+        match self.artifacts.workdir {
+            Some(_) => self.tmp_file("logs"),
+            None => self.tmp_file(".zk_whitelist/logs"),
+        }
+    }
+
+    /// Returns the directories of the working directory layout, none without a working directory.
+    pub fn layout_dirs(&self) -> Vec<String> {
+        match self.artifacts.workdir {
+            Some(_) => vec![self.artifact_dir(), self.public_dir(), self.tmp_dir()],
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the path of a ptau file of the powers of tau ceremony, e.g. `pot12_final.ptau`.
+    pub fn ptau(&self, stage: &str) -> String {
+        self.artifact(&format!("pot{}_{}.ptau", self.circuit.ptau_power, stage))
+    }
+
+    /// Returns the path of a zkey of the circuit, e.g. `circuit_0001.zkey`.
+    pub fn zkey(&self, stage: &str) -> String {
+        self.artifact(&format!("{}_{}.zkey", self.circuit_name(), stage))
+    }
+
+    /// Returns the path a secret file is stored at, with the `.age` extension when secrets are encrypted.
+    pub fn secret_file(&self, path: &str) -> String {
+        if self.secrets.encrypt {
+            format!("{}.age", path)
+        } else {
+            path.to_string()
+        }
+    }
+
+    /// Returns the paths of the secret files written by the setup: the ptau files and the zkeys.
+    pub fn setup_secrets(&self) -> Vec<String> {
+        vec![
+            self.ptau("0000"),
+            self.ptau("0001"),
+            self.ptau("final"),
+            self.zkey("0000"),
+            self.zkey("0001"),
+        ]
+    }
+
+    /// Returns the paths of the scratch files written while generating each proof.
+    ///
+    /// The witness and the input hold the proved address, the others the proof itself.
+    pub fn scratch_files(&self) -> Vec<String> {
+        vec![
+            self.tmp_file("input.json"),
+            self.artifact("witness.wtns"),
+            self.tmp_file("proof.json"),
+            self.tmp_file("public.json"),
+        ]
+    }
+
+    /// Returns the path of the verification key exported by the setup.
+    ///
+    /// The key is public in a working directory, and stays with the artifacts otherwise.
+    pub fn verification_key(&self) -> String {
+        match self.artifacts.workdir {
+            Some(_) => self.public_file("verification_key.json"),
+            None => self.artifact("verification_key.json"),
+        }
+    }
+
+    /// Returns the path of the proofs file generated for an input file.
+    ///
+    /// The proofs file is written next to the input file, or to the public directory of a
+    /// working directory.
+    pub fn proofs_file(&self, input_file: &str) -> String {
+        let proofs_file = format!("{}.proofs.{}", input_file, self.output.format.extension());
+        match (&self.artifacts.workdir, Path::new(&proofs_file).file_name()) {
+            (Some(_), Some(file_name)) => self.public_file(&file_name.to_string_lossy()),
+            _ => proofs_file,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::filesystem_operations::{MockFileSystemOps, RealFileSystemOps};
+    use std::fs;
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+
+        assert_eq!(config.circuit_name(), "circuit");
+        assert_eq!(config.artifact_dir(), ".");
+        assert_eq!(config.artifact("circuit.r1cs"), "circuit.r1cs");
+        assert_eq!(config.ptau("0000"), "pot12_0000.ptau");
+        assert_eq!(config.zkey("0001"), "circuit_0001.zkey");
+        assert_eq!(config.verification_key(), "verification_key.json");
+        assert_eq!(config.public_file("verifier.sol"), "verifier.sol");
+        assert_eq!(config.tmp_file("input.json"), "input.json");
+        assert_eq!(config.log_dir(), ".zk_whitelist/logs");
+        assert!(config.layout_dirs().is_empty());
+        assert_eq!(
+            config.proofs_file(&config.input.file),
+            "addresses.txt.proofs.json"
+        );
+    }
+
+    #[test]
+    fn test_config_from_toml() {
+        let content = r#"
+            [circuit]
+            path = "circuits/allowlist.circom"
+            ptau_power = 14
+
+            [artifacts]
+            dir = "build"
+
+            [contracts]
+            verifier = "AllowlistVerifier"
+            amount = 50
+
+            [input]
+            file = "allowlist.csv"
+            format = "csv"
+
+            [output]
+            format = "csv"
+            progress = "json"
+        "#;
+
+        let config = Config::from_toml(content, Path::new("project")).unwrap();
+
+        assert_eq!(config.circuit.path, "project/circuits/allowlist.circom");
+        assert_eq!(config.circuit_name(), "allowlist");
+        assert_eq!(config.artifact_dir(), "project/build");
+        assert_eq!(config.ptau("final"), "project/build/pot14_final.ptau");
+        assert_eq!(config.zkey("0000"), "project/build/allowlist_0000.zkey");
+        assert_eq!(
+            config.contracts.verifier,
+            Some("AllowlistVerifier".to_string())
+        );
+        assert_eq!(config.contracts.token, None);
+        assert_eq!(config.contracts.amount, Some(50));
+        assert_eq!(config.input.format, InputFormat::Csv);
+        assert_eq!(config.output.progress, ProgressMode::Json);
+        assert_eq!(
+            config.proofs_file(&config.input.file),
+            "project/allowlist.csv.proofs.csv"
+        );
+    }
+
+    #[test]
+    fn test_config_with_workdir() {
+        let config = Config::from_toml(
+            "[artifacts]\nworkdir = \"whitelist\"\n",
+            Path::new("project"),
+        )
+        .unwrap();
+
+        assert_eq!(config.artifact_dir(), "project/whitelist/private");
+        assert_eq!(
+            config.ptau("final"),
+            "project/whitelist/private/pot12_final.ptau"
+        );
+        assert_eq!(
+            config.zkey("0001"),
+            "project/whitelist/private/circuit_0001.zkey"
+        );
+        assert_eq!(
+            config.artifact("witness.wtns"),
+            "project/whitelist/private/witness.wtns"
+        );
+        assert_eq!(
+            config.public_file("verifier.sol"),
+            "project/whitelist/public/verifier.sol"
+        );
+        assert_eq!(
+            config.verification_key(),
+            "project/whitelist/public/verification_key.json"
+        );
+        assert_eq!(
+            config.proofs_file("lists/addresses.txt"),
+            "project/whitelist/public/addresses.txt.proofs.json"
+        );
+        assert_eq!(
+            config.tmp_file("input.json"),
+            "project/whitelist/tmp/input.json"
+        );
+        assert_eq!(config.log_dir(), "project/whitelist/tmp/logs");
+        assert_eq!(
+            config.layout_dirs(),
+            vec![
+                "project/whitelist/private",
+                "project/whitelist/public",
+                "project/whitelist/tmp"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_with_encrypted_secrets() {
+        let config = Config::from_toml(
+            "[secrets]\nencrypt = true\nkeyfile = \"keys/whitelist.key\"\n",
+            Path::new("project"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.secrets.keyfile,
+            Some("project/keys/whitelist.key".to_string())
+        );
+        assert_eq!(
+            config.secret_file(&config.zkey("0001")),
+            "project/circuit_0001.zkey.age"
+        );
+        assert_eq!(config.setup_secrets().len(), 5);
+    }
+
+    #[test]
+    fn test_config_with_tools() {
+        let content = r#"
+            [tools]
+            circom = "bin/circom"
+            snarkjs = "snarkjs"
+            npx_fallback = true
+            working_dir = "contracts"
+
+            timeout = 600
+
+            [tools.env]
+            NODE_OPTIONS = "--max-old-space-size=8192"
+
+            [tools.timeouts]
+            setup = 7200
+        "#;
+
+        let config = Config::from_toml(content, Path::new("project")).unwrap();
+        let absolute = config.resolve_paths(Path::new("/home/user"));
+
+        assert_eq!(config.tools.circom, Some("project/bin/circom".to_string()));
+        assert_eq!(config.tools.binary("snarkjs"), Some("snarkjs"));
+        assert_eq!(config.tools.binary("node"), None);
+        assert!(config.tools.npx_fallback);
+        assert_eq!(config.tools.timeout, Some(600));
+        assert_eq!(config.tools.timeouts.get("setup"), Some(&7200));
+        assert_eq!(
+            config.tools.env.get("NODE_OPTIONS"),
+            Some(&"--max-old-space-size=8192".to_string())
+        );
+        assert_eq!(
+            absolute.tools.working_dir,
+            Some("/home/user/project/contracts".to_string())
+        );
+        assert_eq!(
+            absolute.zkey("0001"),
+            "/home/user/project/circuit_0001.zkey"
+        );
+        assert_eq!(absolute.tools.snarkjs, Some("snarkjs".to_string()));
+    }
+
+    #[test]
+    fn test_config_from_toml_with_invalid_content() {
+        let invalid_contents = [
+            "[circuit]\nprotocol = \"plonk\"",
+            "[circuit]\nptau_power = 40",
+            "[circuits]\npath = \"circuit.circom\"",
+            "[input]\nfile = 3",
+        ];
+
+        for content in invalid_contents {
+            assert!(Config::from_toml(content, Path::new("")).is_err());
+        }
+    }
+
+    #[test]
+    fn test_discover_config() {
+        let project = std::env::temp_dir().join("zk_whitelist_test_discover_config");
+        let nested = project.join("contracts").join("src");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(
+            project.join(CONFIG_FILE_NAME),
+            "[artifacts]\ndir = \"build\"\n",
+        )
+        .unwrap();
+
+        let from_project = Config::discover(&RealFileSystemOps, &project).unwrap();
+        let from_nested = Config::discover(&RealFileSystemOps, &nested).unwrap();
+
+        assert_eq!(from_project.artifact_dir(), "build");
+        assert_eq!(
+            from_nested.artifact_dir(),
+            project.join("build").to_string_lossy()
+        );
+
+        fs::remove_dir_all(project).unwrap();
+    }
+
+    #[test]
+    fn test_config_from_file() {
+        let file_ops = MockFileSystemOps::new();
+        file_ops.stub_file_content(
+            "project/zk_whitelist.toml",
+            vec!["[artifacts]".to_string(), "dir = \"build\"".to_string()],
+        );
+
+        let config = Config::from_file(&file_ops, Path::new("project/zk_whitelist.toml"));
+        let missing = Config::from_file(&file_ops, Path::new("missing.toml"));
+
+        assert_eq!(config.unwrap().artifact_dir(), "project/build");
+        assert_eq!(
+            missing.unwrap_err(),
+            "missing.toml: the configuration file does not exist"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::utils::interrupt::INTERRUPTED_EXIT_CODE;
+use std::io;
+use thiserror::Error;
+
+/// The errors of the commands, each mapped to a distinct process exit code.
+#[derive(Debug, Error)]
+pub enum WhitelistError {
+    /// An external tool such as `circom`, `snarkjs`, `node` or `solc` is not installed.
+    #[error(
+        "{tool} is not installed or not on the PATH, run `zk_whitelist doctor` to check the tools"
+    )]
+    ToolMissing { tool: String },
+    /// An external tool ran and failed, with its command line, exit code and output.
+    #[error("{0}")]
+    SubprocessFailed(String),
+    /// An option, the configuration or the input file is invalid.
+    #[error("{0}")]
+    InvalidInput(String),
+    /// A file the command reads does not exist, usually because the step generating it did
+    /// not run yet.
+    #[error("{0} is missing, run the step generating it first or `zk_whitelist doctor`")]
+    ArtifactMissing(String),
+    /// An artifact is invalid or was not generated from the others, e.g. a verifier from
+    /// another verification key.
+    #[error("{0}")]
+    ArtifactMismatch(String),
+    /// Any other failure, such as an I/O error.
+    #[error("{0}")]
+    Other(String),
+    /// The run was stopped with Ctrl-C, after killing the running tool.
+    #[error("Interrupted while running `{0}`")]
+    Interrupted(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl WhitelistError {
+    /// Returns the process exit code of the error.
+    ///
+    /// | Code | Error |
+    /// | ---- | ----- |
+    /// | 1 | Any other failure |
+    /// | 2 | Invalid option, configuration or input, as for invalid command line arguments |
+    /// | 3 | External tool missing |
+    /// | 4 | External tool failed |
+    /// | 5 | Artifact missing |
+    /// | 6 | Artifact mismatch |
+    /// | 130 | Interrupted with Ctrl-C |
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            WhitelistError::Other(_) | WhitelistError::Io(_) => 1,
+            WhitelistError::InvalidInput(_) => 2,
+            WhitelistError::ToolMissing { .. } => 3,
+            WhitelistError::SubprocessFailed(_) => 4,
+            WhitelistError::ArtifactMissing(_) => 5,
+            WhitelistError::ArtifactMismatch(_) => 6,
+            WhitelistError::Interrupted(_) => INTERRUPTED_EXIT_CODE,
+        }
+    }
+
+    /// Converts an I/O error on a path, reporting a missing file as a missing artifact.
+    pub fn io(path: &str, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => WhitelistError::ArtifactMissing(path.to_string()),
+            kind => WhitelistError::Io(io::Error::new(kind, format!("{}: {}", path, error))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        let errors = [
+            WhitelistError::Other("failed".to_string()),
+            WhitelistError::InvalidInput("bad address".to_string()),
+            WhitelistError::ToolMissing {
+                tool: "snarkjs".to_string(),
+            },
+            WhitelistError::SubprocessFailed("snarkjs failed".to_string()),
+            WhitelistError::ArtifactMissing("circuit.r1cs".to_string()),
+            WhitelistError::ArtifactMismatch("nPublic differs".to_string()),
+            WhitelistError::Interrupted("snarkjs zkey contribute".to_string()),
+        ];
+
+        let codes: Vec<u8> = errors.iter().map(WhitelistError::exit_code).collect();
+
+        assert_eq!(codes, vec![1, 2, 3, 4, 5, 6, 130]);
+    }
+
+    #[test]
+    fn test_io() {
+        let missing = WhitelistError::io("circuit.r1cs", io::Error::from(io::ErrorKind::NotFound));
+        let denied = WhitelistError::io(
+            "circuit.r1cs",
+            io::Error::from(io::ErrorKind::PermissionDenied),
+        );
+
+        assert_eq!(
+            missing.to_string(),
+            "circuit.r1cs is missing, run the step generating it first or `zk_whitelist doctor`"
+        );
+        assert_eq!(denied.exit_code(), 1);
+        assert!(denied.to_string().starts_with("circuit.r1cs: "));
+    }
+}
@@ -1,15 +1,26 @@
+use crate::utils::error::WhitelistError;
+use sha2::{Digest, Sha256};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::{self, File, OpenOptions},
-    io::{self, BufRead, BufReader, Write},
+    io::{self, BufRead, BufReader, Cursor, Read, Write},
     path::Path,
-    sync::{Arc, Mutex},
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
 };
 
+/// The number of temporary files created by this process, which makes their names unique.
+static TEMPORARY_FILES: AtomicU64 = AtomicU64::new(0);
+
 /// A trait defining a generic file system operations interface.
 ///
 /// Implementations of this trait can be used to perform
-/// file system operations like moving files and directories.
+/// file system operations like moving files and directories. A missing file is reported as
+/// `WhitelistError::ArtifactMissing`.
 pub trait FileSystemOps {
     /// Moves a file or directory from the source path to the destination path.
     ///
@@ -19,59 +30,298 @@ pub trait FileSystemOps {
     ///
     /// # Returns
     /// A `Result` indicating the success or failure of the operation.
-    fn move_item(&self, src: &str, dst: &str) -> Result<(), String>;
-    fn read_lines(&self, path: &str) -> Result<Vec<String>, String>;
-    fn write_to_file(&self, path: &str, content: &str) -> Result<(), String>;
+    fn move_item(&self, src: &str, dst: &str) -> Result<(), WhitelistError>;
+    fn read_lines(&self, path: &str) -> Result<Vec<String>, WhitelistError>;
+    /// Reads the whole content of a file into a string.
+    fn read_to_string(&self, path: &str) -> Result<String, WhitelistError>;
+    /// Reads the whole content of a binary file.
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, WhitelistError>;
+    /// Opens a file for reading.
+    ///
+    /// # Returns
+    /// A reader streaming the file, so large files are never held in memory.
+    fn open(&self, path: &str) -> Result<Box<dyn Read>, WhitelistError>;
+    /// Returns whether a file or directory exists.
+    fn exists(&self, path: &str) -> bool;
+    /// Writes a file, replacing any previous content.
+    ///
+    /// The content is written to a temporary file renamed over the file, so an interrupted
+    /// write never leaves a partial file.
+    fn write_to_file(&self, path: &str, content: &str) -> Result<(), WhitelistError>;
+    /// Writes binary content to a file as `write_to_file` does.
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), WhitelistError>;
+    /// Creates a file only its owner can read and write, for the secrets.
+    ///
+    /// # Returns
+    /// A writer streaming into the file, so large files are never held in memory.
+    fn create_private(&self, path: &str) -> Result<Box<dyn Write>, WhitelistError>;
+    /// Opens a file for appending, creating it if it does not exist, e.g. for the logs.
+    ///
+    /// # Returns
+    /// A writer appending to the file, which can be shared between threads.
+    fn append(&self, path: &str) -> Result<Box<dyn Write + Send>, WhitelistError>;
+    /// Renames a file or directory, replacing the destination.
+    fn rename(&self, src: &str, dst: &str) -> Result<(), WhitelistError>;
+    /// Creates a directory and any missing parent directories.
+    fn create_dir_all(&self, path: &str) -> Result<(), WhitelistError>;
+    /// Returns the last modification time of a file, or `None` if it does not exist.
+    fn modified(&self, path: &str) -> Option<SystemTime>;
+    /// Returns the hex encoded SHA-256 hash of the content of a file.
+    fn hash_file(&self, path: &str) -> Result<String, WhitelistError>;
+    /// Removes a file, or a directory with its content.
+    fn remove(&self, path: &str) -> Result<(), WhitelistError>;
+    /// Overwrites the content of a file, or of every file in a directory, with zeros.
+    fn overwrite(&self, path: &str) -> Result<(), WhitelistError>;
+}
+
+/// Accesses the files through the file system operations it borrows, so that a `Project` can
+/// be built over file system operations owned elsewhere.
+impl<T: FileSystemOps + ?Sized> FileSystemOps for &T {
+    fn move_item(&self, src: &str, dst: &str) -> Result<(), WhitelistError> {
+        (**self).move_item(src, dst)
+    }
+
+    fn read_lines(&self, path: &str) -> Result<Vec<String>, WhitelistError> {
+        (**self).read_lines(path)
+    }
+
+    fn read_to_string(&self, path: &str) -> Result<String, WhitelistError> {
+        (**self).read_to_string(path)
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, WhitelistError> {
+        (**self).read_bytes(path)
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn Read>, WhitelistError> {
+        (**self).open(path)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        (**self).exists(path)
+    }
+
+    fn write_to_file(&self, path: &str, content: &str) -> Result<(), WhitelistError> {
+        (**self).write_to_file(path, content)
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), WhitelistError> {
+        (**self).write_bytes(path, content)
+    }
+
+    fn create_private(&self, path: &str) -> Result<Box<dyn Write>, WhitelistError> {
+        (**self).create_private(path)
+    }
+
+    fn append(&self, path: &str) -> Result<Box<dyn Write + Send>, WhitelistError> {
+        (**self).append(path)
+    }
+
+    fn rename(&self, src: &str, dst: &str) -> Result<(), WhitelistError> {
+        (**self).rename(src, dst)
+    }
+
+    fn create_dir_all(&self, path: &str) -> Result<(), WhitelistError> {
+        (**self).create_dir_all(path)
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        (**self).modified(path)
+    }
+
+    fn hash_file(&self, path: &str) -> Result<String, WhitelistError> {
+        (**self).hash_file(path)
+    }
+
+    fn remove(&self, path: &str) -> Result<(), WhitelistError> {
+        (**self).remove(path)
+    }
+
+    fn overwrite(&self, path: &str) -> Result<(), WhitelistError> {
+        (**self).overwrite(path)
+    }
 }
 
 /// A real implementation of the `FileSystemOps` trait that performs file system operations.
 pub struct RealFileSystemOps;
 
 impl FileSystemOps for RealFileSystemOps {
-    fn move_item(&self, src: &str, dst: &str) -> Result<(), String> {
+    fn move_item(&self, src: &str, dst: &str) -> Result<(), WhitelistError> {
         let src_path = Path::new(src);
         let dst_path = Path::new(dst);
 
-        if !src_path.exists() || !src_path.is_dir() {
-            return Err("Source directory does not exist or is not a directory".to_string());
+        if !src_path.is_dir() {
+            return Err(WhitelistError::ArtifactMissing(src.to_string()));
         }
 
-        for entry in fs::read_dir(src_path).map_err(|e| e.to_string())? {
-            let entry = entry.map_err(|e| e.to_string())?;
+        for entry in fs::read_dir(src_path).map_err(|e| WhitelistError::io(src, e))? {
+            let entry = entry.map_err(|e| WhitelistError::io(src, e))?;
             let path = entry.path();
 
             if path.is_file() {
                 let dest_path = dst_path.join(
                     path.file_name()
-                        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "No file name"))
-                        .map_err(|e| e.to_string())?,
+                        .ok_or_else(|| WhitelistError::Other("No file name".to_string()))?,
                 );
-                fs::rename(&path, &dest_path).map_err(|e| e.to_string())?;
+                fs::rename(&path, &dest_path)
+                    .map_err(|e| WhitelistError::io(&path.to_string_lossy(), e))?;
             }
         }
 
         Ok(())
     }
 
-    fn read_lines(&self, path: &str) -> Result<Vec<String>, String> {
-        let file = File::open(path).map_err(|e| e.to_string())?;
+    fn read_lines(&self, path: &str) -> Result<Vec<String>, WhitelistError> {
+        let file = File::open(path).map_err(|e| WhitelistError::io(path, e))?;
         let reader = BufReader::new(file);
         let lines = reader
             .lines()
             .collect::<Result<Vec<String>, io::Error>>()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| WhitelistError::io(path, e))?;
         Ok(lines)
     }
 
-    fn write_to_file(&self, path: &str, content: &str) -> Result<(), String> {
+    fn read_to_string(&self, path: &str) -> Result<String, WhitelistError> {
+        fs::read_to_string(path).map_err(|e| WhitelistError::io(path, e))
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, WhitelistError> {
+        fs::read(path).map_err(|e| WhitelistError::io(path, e))
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn Read>, WhitelistError> {
+        let file = File::open(path).map_err(|e| WhitelistError::io(path, e))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        Path::new(path).exists()
+    }
+
+    fn write_to_file(&self, path: &str, content: &str) -> Result<(), WhitelistError> {
+        self.write_bytes(path, content.as_bytes())
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), WhitelistError> {
+        // The temporary file is a sibling of the target, so the rename stays on one file system,
+        // and is unique to this write, so concurrent writers never rename each other's content.
+        let temporary_path = format!(
+            "{}.{}.{}.tmp",
+            path,
+            process::id(),
+            TEMPORARY_FILES.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut file =
+            File::create(&temporary_path).map_err(|e| WhitelistError::io(&temporary_path, e))?;
+        let result = file
+            .write_all(content)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| WhitelistError::io(&temporary_path, e))
+            .and_then(|_| {
+                fs::rename(&temporary_path, path).map_err(|e| WhitelistError::io(path, e))
+            });
+        if result.is_err() {
+            // A failed write leaves no temporary file behind.
+            let _ = fs::remove_file(&temporary_path);
+        }
+        result
+    }
+
+    fn create_private(&self, path: &str) -> Result<Box<dyn Write>, WhitelistError> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // The mode only applies to new files, so existing ones are restricted as well.
+            if Path::new(path).exists() {
+                fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+                    .map_err(|e| WhitelistError::io(path, e))?;
+            }
+        }
+        let file = options
+            .open(path)
+            .map_err(|e| WhitelistError::io(path, e))?;
+        Ok(Box::new(file))
+    }
+
+    fn append(&self, path: &str) -> Result<Box<dyn Write + Send>, WhitelistError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| WhitelistError::io(path, e))?;
+        Ok(Box::new(file))
+    }
+
+    fn rename(&self, src: &str, dst: &str) -> Result<(), WhitelistError> {
+        // A directory is only renamed over an empty one, so the previous one is removed first.
+        if Path::new(src).is_dir() && Path::new(dst).is_dir() {
+            fs::remove_dir_all(dst).map_err(|e| WhitelistError::io(dst, e))?;
+        }
+        fs::rename(src, dst).map_err(|e| WhitelistError::io(src, e))
+    }
+
+    fn create_dir_all(&self, path: &str) -> Result<(), WhitelistError> {
+        fs::create_dir_all(path).map_err(|e| WhitelistError::io(path, e))
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    fn hash_file(&self, path: &str) -> Result<String, WhitelistError> {
+        // The file is hashed in chunks, so ptau files of any size are never held in memory.
+        let mut reader = self.open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; 64 * 1024];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => hasher.update(&buffer[..read]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(WhitelistError::io(path, e)),
+            }
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    fn remove(&self, path: &str) -> Result<(), WhitelistError> {
+        if Path::new(path).is_dir() {
+            fs::remove_dir_all(path).map_err(|e| WhitelistError::io(path, e))
+        } else {
+            fs::remove_file(path).map_err(|e| WhitelistError::io(path, e))
+        }
+    }
+
+    fn overwrite(&self, path: &str) -> Result<(), WhitelistError> {
+        if Path::new(path).is_dir() {
+            for entry in fs::read_dir(path).map_err(|e| WhitelistError::io(path, e))? {
+                let entry = entry.map_err(|e| WhitelistError::io(path, e))?;
+                self.overwrite(&entry.path().to_string_lossy())?;
+            }
+            return Ok(());
+        }
         let mut file = OpenOptions::new()
             .write(true)
-            .create(true)
             .open(path)
-            .map_err(|e| e.to_string())?;
-        file.write_all(content.as_bytes())
-            .map_err(|e| e.to_string())?;
-        Ok(())
+            .map_err(|e| WhitelistError::io(path, e))?;
+        let mut remaining = file
+            .metadata()
+            .map_err(|e| WhitelistError::io(path, e))?
+            .len();
+        let zeros = [0u8; 8192];
+        while remaining > 0 {
+            let length = remaining.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..length])
+                .map_err(|e| WhitelistError::io(path, e))?;
+            remaining -= length as u64;
+        }
+        // Make sure the zeros reach the disk before the file is unlinked.
+        file.sync_all().map_err(|e| WhitelistError::io(path, e))
     }
 }
 
@@ -83,6 +333,8 @@ pub struct MockFileSystemOps {
     operations: Arc<Mutex<Vec<(String, String)>>>,
     written_content: Arc<Mutex<HashMap<String, String>>>,
     stubbed_file_content: Arc<Mutex<HashMap<String, Vec<String>>>>,
+    stubbed_modified: Arc<Mutex<HashMap<String, SystemTime>>>,
+    stubbed_bytes: Arc<Mutex<HashMap<String, Vec<u8>>>>,
 }
 
 impl MockFileSystemOps {
@@ -92,9 +344,27 @@ impl MockFileSystemOps {
             operations: Arc::new(Mutex::new(Vec::new())),
             written_content: Arc::new(Mutex::new(HashMap::new())),
             stubbed_file_content: Arc::new(Mutex::new(HashMap::new())),
+            stubbed_modified: Arc::new(Mutex::new(HashMap::new())),
+            stubbed_bytes: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Makes `read_bytes` return the given binary content for a path.
+    pub fn stub_bytes(&self, path: &str, content: Vec<u8>) {
+        self.stubbed_bytes
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), content);
+    }
+
+    /// Makes `modified` report the given modification time for a path.
+    pub fn stub_modified(&self, path: &str, modified: SystemTime) {
+        self.stubbed_modified
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), modified);
+    }
+
     pub fn stub_file_content(&self, path: &str, content: Vec<String>) {
         self.stubbed_file_content
             .lock()
@@ -134,8 +404,14 @@ impl MockFileSystemOps {
     }
 }
 
+impl Default for MockFileSystemOps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FileSystemOps for MockFileSystemOps {
-    fn move_item(&self, src: &str, dst: &str) -> Result<(), String> {
+    fn move_item(&self, src: &str, dst: &str) -> Result<(), WhitelistError> {
         self.operations
             .lock()
             .unwrap()
@@ -143,20 +419,240 @@ impl FileSystemOps for MockFileSystemOps {
         Ok(())
     }
 
-    fn read_lines(&self, path: &str) -> Result<Vec<String>, String> {
+    fn read_lines(&self, path: &str) -> Result<Vec<String>, WhitelistError> {
         match self.stubbed_file_content.lock().unwrap().get(path) {
             Some(content) => Ok(content.clone()),
-            None => Err("File not found".to_string()),
+            None => Err(WhitelistError::ArtifactMissing(path.to_string())),
         }
     }
 
-    fn write_to_file(&self, path: &str, content: &str) -> Result<(), String> {
+    fn read_to_string(&self, path: &str) -> Result<String, WhitelistError> {
+        self.read_lines(path).map(|lines| lines.join("\n"))
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, WhitelistError> {
+        match self.stubbed_bytes.lock().unwrap().get(path) {
+            Some(content) => Ok(content.clone()),
+            None => self.read_to_string(path).map(String::into_bytes),
+        }
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn Read>, WhitelistError> {
+        self.read_bytes(path)
+            .map(|content| Box::new(Cursor::new(content)) as Box<dyn Read>)
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.stubbed_modified.lock().unwrap().contains_key(path)
+            || self.stubbed_file_content.lock().unwrap().contains_key(path)
+            || self.stubbed_bytes.lock().unwrap().contains_key(path)
+            || self.written_content.lock().unwrap().contains_key(path)
+    }
+
+    fn write_to_file(&self, path: &str, content: &str) -> Result<(), WhitelistError> {
         self.written_content
             .lock()
             .unwrap()
             .insert(path.to_string(), content.to_string());
         Ok(())
     }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), WhitelistError> {
+        self.operations
+            .lock()
+            .unwrap()
+            .push(("write_bytes".to_string(), path.to_string()));
+        self.stub_bytes(path, content.to_vec());
+        Ok(())
+    }
+
+    fn create_private(&self, path: &str) -> Result<Box<dyn Write>, WhitelistError> {
+        self.operations
+            .lock()
+            .unwrap()
+            .push(("create_private".to_string(), path.to_string()));
+        Ok(Box::new(io::sink()))
+    }
+
+    fn append(&self, path: &str) -> Result<Box<dyn Write + Send>, WhitelistError> {
+        self.operations
+            .lock()
+            .unwrap()
+            .push(("append".to_string(), path.to_string()));
+        Ok(Box::new(io::sink()))
+    }
+
+    fn rename(&self, src: &str, dst: &str) -> Result<(), WhitelistError> {
+        self.operations
+            .lock()
+            .unwrap()
+            .push(("rename".to_string(), format!("{} {}", src, dst)));
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &str) -> Result<(), WhitelistError> {
+        self.operations
+            .lock()
+            .unwrap()
+            .push(("create_dir_all".to_string(), path.to_string()));
+        Ok(())
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        self.stubbed_modified.lock().unwrap().get(path).copied()
+    }
+
+    fn hash_file(&self, path: &str) -> Result<String, WhitelistError> {
+        self.read_to_string(path)
+            .map(|content| hex::encode(Sha256::digest(content)))
+    }
+
+    fn remove(&self, path: &str) -> Result<(), WhitelistError> {
+        self.operations
+            .lock()
+            .unwrap()
+            .push(("remove".to_string(), path.to_string()));
+        Ok(())
+    }
+
+    fn overwrite(&self, path: &str) -> Result<(), WhitelistError> {
+        self.operations
+            .lock()
+            .unwrap()
+            .push(("overwrite".to_string(), path.to_string()));
+        Ok(())
+    }
+}
+
+/// A `FileSystemOps` for `--dry-run`, printing the changes it would make instead of making them.
+///
+/// Files are read from the disk, or from the content written earlier in the run, so a command
+/// reading back what it wrote sees the new content.
+#[derive(Default)]
+pub struct DryRunFileSystemOps {
+    written: Mutex<HashMap<String, Vec<u8>>>,
+    created_dirs: Mutex<HashSet<String>>,
+}
+
+impl DryRunFileSystemOps {
+    /// Creates a new `DryRunFileSystemOps` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record(&self, path: &str, content: &[u8]) {
+        self.written
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), content.to_vec());
+    }
+
+    fn written(&self, path: &str) -> Option<Vec<u8>> {
+        self.written.lock().unwrap().get(path).cloned()
+    }
+}
+
+impl FileSystemOps for DryRunFileSystemOps {
+    fn move_item(&self, src: &str, dst: &str) -> Result<(), WhitelistError> {
+        println!("move {}/* {}", src, dst);
+        Ok(())
+    }
+
+    fn read_lines(&self, path: &str) -> Result<Vec<String>, WhitelistError> {
+        match self.written(path) {
+            Some(content) => Ok(Cursor::new(content)
+                .lines()
+                .collect::<Result<_, _>>()
+                .map_err(|e| WhitelistError::io(path, e))?),
+            None => RealFileSystemOps.read_lines(path),
+        }
+    }
+
+    fn read_to_string(&self, path: &str) -> Result<String, WhitelistError> {
+        match self.written(path) {
+            Some(content) => Ok(String::from_utf8_lossy(&content).to_string()),
+            None => RealFileSystemOps.read_to_string(path),
+        }
+    }
+
+    fn read_bytes(&self, path: &str) -> Result<Vec<u8>, WhitelistError> {
+        match self.written(path) {
+            Some(content) => Ok(content),
+            None => RealFileSystemOps.read_bytes(path),
+        }
+    }
+
+    fn open(&self, path: &str) -> Result<Box<dyn Read>, WhitelistError> {
+        match self.written(path) {
+            Some(content) => Ok(Box::new(Cursor::new(content))),
+            None => RealFileSystemOps.open(path),
+        }
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.written(path).is_some()
+            || self.created_dirs.lock().unwrap().contains(path)
+            || RealFileSystemOps.exists(path)
+    }
+
+    fn write_to_file(&self, path: &str, content: &str) -> Result<(), WhitelistError> {
+        self.write_bytes(path, content.as_bytes())
+    }
+
+    fn write_bytes(&self, path: &str, content: &[u8]) -> Result<(), WhitelistError> {
+        println!("write {} ({} bytes)", path, content.len());
+        self.record(path, content);
+        Ok(())
+    }
+
+    fn create_private(&self, path: &str) -> Result<Box<dyn Write>, WhitelistError> {
+        println!("write {} (private)", path);
+        Ok(Box::new(io::sink()))
+    }
+
+    fn append(&self, path: &str) -> Result<Box<dyn Write + Send>, WhitelistError> {
+        println!("append {}", path);
+        Ok(Box::new(io::sink()))
+    }
+
+    fn rename(&self, src: &str, dst: &str) -> Result<(), WhitelistError> {
+        println!("rename {} {}", src, dst);
+        let mut written = self.written.lock().unwrap();
+        if let Some(content) = written.remove(src) {
+            written.insert(dst.to_string(), content);
+        }
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &str) -> Result<(), WhitelistError> {
+        let created = self.created_dirs.lock().unwrap().insert(path.to_string());
+        if created && !Path::new(path).is_dir() {
+            println!("mkdir -p {}", path);
+        }
+        Ok(())
+    }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        match self.written(path) {
+            Some(_) => Some(SystemTime::now()),
+            None => RealFileSystemOps.modified(path),
+        }
+    }
+
+    fn hash_file(&self, path: &str) -> Result<String, WhitelistError> {
+        self.read_bytes(path)
+            .map(|content| hex::encode(Sha256::digest(content)))
+    }
+
+    fn remove(&self, path: &str) -> Result<(), WhitelistError> {
+        println!("remove {}", path);
+        Ok(())
+    }
+
+    fn overwrite(&self, path: &str) -> Result<(), WhitelistError> {
+        println!("overwrite {}", path);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -188,7 +684,18 @@ mod tests {
 
         let result = mock_ops.read_lines(path);
 
-        assert_eq!(result, Ok(content));
+        assert_eq!(result.unwrap(), content);
+    }
+
+    #[test]
+    fn test_read_to_string() {
+        let mock_ops = MockFileSystemOps::new();
+        let path = "test.txt";
+        mock_ops.set_readable_content(path, vec!["line 1".to_string(), "line 2".to_string()]);
+
+        let result = mock_ops.read_to_string(path);
+
+        assert_eq!(result.unwrap(), "line 1\nline 2");
     }
 
     #[test]
@@ -205,4 +712,181 @@ mod tests {
             Some(content.to_string())
         );
     }
+
+    #[test]
+    fn test_hash_file() {
+        let mock_ops = MockFileSystemOps::new();
+        mock_ops.set_readable_content("test.txt", vec!["abc".to_string()]);
+
+        assert_eq!(
+            mock_ops.hash_file("test.txt").unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(mock_ops.hash_file("missing.txt").is_err());
+    }
+
+    #[test]
+    fn test_hash_file_in_chunks() {
+        let ops = RealFileSystemOps;
+        let path = std::env::temp_dir().join("zk_whitelist_test_hash_file_in_chunks.ptau");
+        let content = vec![7u8; 200 * 1024];
+        fs::write(&path, &content).unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let hash = ops.hash_file(&path).unwrap();
+
+        assert_eq!(hash, hex::encode(Sha256::digest(&content)));
+        assert!(ops.hash_file(&format!("{}.missing", path)).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_overwrite_and_remove() {
+        let ops = RealFileSystemOps;
+        let dir = std::env::temp_dir().join("zk_whitelist_test_overwrite_and_remove");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("witness.wtns");
+        fs::write(&file, b"secret witness").unwrap();
+        let file = file.to_string_lossy().to_string();
+
+        ops.overwrite(&dir.to_string_lossy()).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), vec![0u8; 14]);
+
+        ops.remove(&dir.to_string_lossy()).unwrap();
+        assert!(!dir.exists());
+    }
+
+    /// Tests that `append` creates a file and appends to it.
+    #[test]
+    fn test_append() {
+        let ops = RealFileSystemOps;
+        let path = std::env::temp_dir().join("zk_whitelist_test_append.log");
+        let path = path.to_string_lossy().to_string();
+        let _ = fs::remove_file(&path);
+
+        ops.append(&path).unwrap().write_all(b"$ circom\n").unwrap();
+        ops.append(&path)
+            .unwrap()
+            .write_all(b"exit status: 0\n")
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "$ circom\nexit status: 0\n"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    /// Tests that rewriting a file with shorter content leaves no stale bytes behind.
+    #[test]
+    fn test_write_to_file_truncates() {
+        let ops = RealFileSystemOps;
+        let path = std::env::temp_dir().join("zk_whitelist_test_write_to_file_truncates.json");
+        let path = path.to_string_lossy().to_string();
+
+        ops.write_to_file(&path, r#"{"addressInDecimal":"123456789"}"#)
+            .unwrap();
+        ops.write_to_file(&path, r#"{"a":"1"}"#).unwrap();
+
+        assert_eq!(ops.read_to_string(&path).unwrap(), r#"{"a":"1"}"#);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_open_and_exists() {
+        let mock_ops = MockFileSystemOps::new();
+        mock_ops.stub_bytes("circuit_0001.zkey", b"zkey".to_vec());
+        let dry_run_ops = DryRunFileSystemOps::new();
+        dry_run_ops.write_to_file("input.json", "{}").unwrap();
+
+        let mut content = String::new();
+        mock_ops
+            .open("circuit_0001.zkey")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+
+        assert_eq!(content, "zkey");
+        assert!(mock_ops.exists("circuit_0001.zkey"));
+        assert!(!mock_ops.exists("circuit_0000.zkey"));
+        assert!(mock_ops.open("circuit_0000.zkey").is_err());
+        assert!(dry_run_ops.exists("input.json"));
+        assert!(!dry_run_ops.exists("zk_whitelist_missing_file"));
+    }
+
+    #[test]
+    fn test_write_and_rename() {
+        let ops = RealFileSystemOps;
+        let dir = std::env::temp_dir().join("zk_whitelist_test_write_and_rename");
+        fs::create_dir_all(dir.join("circuit_js")).unwrap();
+        fs::create_dir_all(dir.join("circuit.partial/circuit_js")).unwrap();
+        let dir_path = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        ops.write_to_file(&dir_path("proofs.json"), "[]").unwrap();
+        assert_eq!(fs::read_to_string(dir.join("proofs.json")).unwrap(), "[]");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        // A directory replaces the previous one.
+        fs::write(dir.join("circuit.partial/circuit_js/circuit.wasm"), b"wasm").unwrap();
+        ops.rename(
+            &dir_path("circuit.partial/circuit_js"),
+            &dir_path("circuit_js"),
+        )
+        .unwrap();
+        assert!(dir.join("circuit_js/circuit.wasm").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_dry_run_file_system_ops() {
+        let ops = DryRunFileSystemOps::new();
+        let path = std::env::temp_dir()
+            .join("zk_whitelist_test_dry_run.sol")
+            .to_string_lossy()
+            .to_string();
+
+        ops.write_to_file(&path, "contract A {}\ncontract B {}")
+            .unwrap();
+        ops.remove(&path).unwrap();
+
+        assert!(!Path::new(&path).exists());
+        assert_eq!(
+            ops.read_lines(&path).unwrap(),
+            vec!["contract A {}".to_string(), "contract B {}".to_string()]
+        );
+        assert!(ops.modified(&path).is_some());
+    }
+
+    #[test]
+    fn test_write_to_file_removes_the_temporary_file_on_error() {
+        let ops = RealFileSystemOps;
+        let dir = std::env::temp_dir().join("zk_whitelist_test_write_error");
+        fs::create_dir_all(dir.join("verifier.sol")).unwrap();
+        // A non-empty directory cannot be replaced by a file, so the rename fails.
+        fs::write(dir.join("verifier.sol").join("keep"), "").unwrap();
+        let path = dir.join("verifier.sol").to_string_lossy().to_string();
+
+        let result = ops.write_to_file(&path, "contract Verifier {}");
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_to_file_replaces_atomically() {
+        let ops = RealFileSystemOps;
+        let dir = std::env::temp_dir().join("zk_whitelist_test_write_atomically");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("addresses.txt.proofs.json");
+        fs::write(&file, "{\"0x1234\": {}, \"0xabcd\": {}}").unwrap();
+        let file = file.to_string_lossy().to_string();
+
+        ops.write_to_file(&file, "{}").unwrap();
+
+        assert_eq!(fs::read_to_string(&file).unwrap(), "{}");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
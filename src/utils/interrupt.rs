@@ -0,0 +1,88 @@
+use crate::utils::{
+    error::WhitelistError,
+    filesystem_operations::{FileSystemOps, RealFileSystemOps},
+};
+use std::{
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// The exit code of a process stopped by Ctrl-C, as set by shells.
+pub const INTERRUPTED_EXIT_CODE: u8 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static DEFERRED: AtomicUsize = AtomicUsize::new(0);
+static TEMPORARY_SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Installs the Ctrl-C handler of the CLI.
+///
+/// While an external tool runs or a secret is decrypted, Ctrl-C only flags the run as
+/// interrupted: the runner kills the running tool or refuses to start the next one, returning
+/// `WhitelistError::Interrupted`, so the command cleans up as on any error. Otherwise, or on a
+/// second Ctrl-C, the process exits at once, after removing the decrypted secrets.
+///
+/// # Returns
+/// An error if a handler is already installed.
+pub fn install_handler() -> Result<(), WhitelistError> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) || DEFERRED.load(Ordering::SeqCst) == 0 {
+            eprintln!("Interrupted");
+            remove_temporary_secrets();
+            process::exit(INTERRUPTED_EXIT_CODE.into());
+        }
+    })
+    .map_err(|e| WhitelistError::Other(format!("Failed to install the Ctrl-C handler: {}", e)))
+}
+
+/// Returns whether Ctrl-C was pressed.
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Registers a decrypted secret, removed by the Ctrl-C handler if the process exits before
+/// the secret is dropped.
+pub fn register_temporary_secret(path: &str) {
+    TEMPORARY_SECRETS.lock().unwrap().push(path.to_string());
+}
+
+/// Unregisters a decrypted secret once it was removed.
+pub fn unregister_temporary_secret(path: &str) {
+    TEMPORARY_SECRETS
+        .lock()
+        .unwrap()
+        .retain(|registered| registered != path);
+}
+
+/// Overwrites and removes the registered decrypted secrets.
+fn remove_temporary_secrets() {
+    // A panicking thread holding the lock must not keep the secrets on disk.
+    let paths = match TEMPORARY_SECRETS.lock() {
+        Ok(paths) => paths.clone(),
+        Err(poisoned) => poisoned.into_inner().clone(),
+    };
+    for path in paths {
+        let _ = RealFileSystemOps.overwrite(&path);
+        let _ = RealFileSystemOps.remove(&path);
+    }
+}
+
+/// Defers Ctrl-C for as long as it lives, so that the command unwinds through its normal
+/// error path: held while an external tool runs, for the runner to stop it, and while a
+/// secret is decrypted or about to be encrypted, for the command to clean it up.
+pub struct Deferred(());
+
+impl Deferred {
+    pub fn start() -> Self {
+        DEFERRED.fetch_add(1, Ordering::SeqCst);
+        Deferred(())
+    }
+}
+
+impl Drop for Deferred {
+    fn drop(&mut self) {
+        DEFERRED.fetch_sub(1, Ordering::SeqCst);
+    }
+}
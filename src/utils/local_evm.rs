@@ -0,0 +1,248 @@
+use revm::{
+    db::{CacheDB, EmptyDB},
+    primitives::{Address, Bytes, ExecutionResult, Output, TxKind, U256},
+    Evm,
+};
+
+/// The gas limit of every transaction executed on the local EVM.
+const GAS_LIMIT: u64 = 30_000_000;
+
+/// The selector of the standard `Error(string)` revert payload.
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// The outcome of a transaction executed on the local EVM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallOutcome {
+    /// Whether the transaction succeeded.
+    pub success: bool,
+    /// The gas used by the transaction.
+    pub gas_used: u64,
+    /// The data returned by the call, or the revert payload.
+    pub output: Vec<u8>,
+    /// A human readable reason for a failed transaction.
+    pub revert_reason: Option<String>,
+}
+
+/// An in-memory EVM used to execute contracts locally, without running a chain.
+///
+/// Accounts start without balance and transactions have a zero gas price, so any
+/// address can deploy contracts and send transactions.
+pub struct LocalEvm {
+    db: CacheDB<EmptyDB>,
+}
+
+impl LocalEvm {
+    /// Creates a new EVM with an empty state.
+    pub fn new() -> Self {
+        LocalEvm {
+            db: CacheDB::new(EmptyDB::default()),
+        }
+    }
+
+    /// Deploys a contract from its creation bytecode.
+    ///
+    /// # Returns
+    /// The address of the deployed contract together with the outcome of the deployment,
+    /// or an error message if the deployment failed.
+    pub fn deploy(
+        &mut self,
+        deployer: Address,
+        creation_code: Vec<u8>,
+    ) -> Result<(Address, CallOutcome), String> {
+        let (result, created) = self.transact(deployer, TxKind::Create, creation_code)?;
+        match created {
+            Some(address) if result.success => Ok((address, result)),
+            _ => Err(format!(
+                "Contract deployment failed: {}",
+                result
+                    .revert_reason
+                    .unwrap_or_else(|| "no contract created".to_string())
+            )),
+        }
+    }
+
+    /// Calls a deployed contract and commits the resulting state changes.
+    pub fn call(
+        &mut self,
+        caller: Address,
+        contract: Address,
+        calldata: Vec<u8>,
+    ) -> Result<CallOutcome, String> {
+        self.transact(caller, TxKind::Call(contract), calldata)
+            .map(|(outcome, _)| outcome)
+    }
+
+    fn transact(
+        &mut self,
+        caller: Address,
+        transact_to: TxKind,
+        data: Vec<u8>,
+    ) -> Result<(CallOutcome, Option<Address>), String> {
+        let mut evm = Evm::builder()
+            .with_db(&mut self.db)
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.transact_to = transact_to;
+                tx.data = Bytes::from(data);
+                tx.value = U256::ZERO;
+                tx.gas_limit = GAS_LIMIT;
+            })
+            .build();
+        let result = evm
+            .transact_commit()
+            .map_err(|e| format!("EVM transaction failed: {:?}", e))?;
+
+        Ok(match result {
+            ExecutionResult::Success {
+                gas_used, output, ..
+            } => match output {
+                Output::Create(bytes, address) => (
+                    CallOutcome {
+                        success: true,
+                        gas_used,
+                        output: bytes.to_vec(),
+                        revert_reason: None,
+                    },
+                    address,
+                ),
+                Output::Call(bytes) => (
+                    CallOutcome {
+                        success: true,
+                        gas_used,
+                        output: bytes.to_vec(),
+                        revert_reason: None,
+                    },
+                    None,
+                ),
+            },
+            ExecutionResult::Revert { gas_used, output } => (
+                CallOutcome {
+                    success: false,
+                    gas_used,
+                    revert_reason: Some(decode_revert_reason(&output)),
+                    output: output.to_vec(),
+                },
+                None,
+            ),
+            ExecutionResult::Halt { reason, gas_used } => (
+                CallOutcome {
+                    success: false,
+                    gas_used,
+                    output: Vec::new(),
+                    revert_reason: Some(format!("halted: {:?}", reason)),
+                },
+                None,
+            ),
+        })
+    }
+}
+
+impl Default for LocalEvm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decodes the payload of a `REVERT` into a human readable reason.
+///
+/// `Error(string)` payloads, as produced by `require(condition, "message")`, are decoded to
+/// their message. Any other payload is returned hex encoded.
+pub fn decode_revert_reason(output: &[u8]) -> String {
+    if output.is_empty() {
+        return "reverted without a reason".to_string();
+    }
+    if output.len() >= 68 && output[..4] == ERROR_STRING_SELECTOR {
+        let length = U256::from_be_slice(&output[36..68]);
+        if let Ok(length) = usize::try_from(length) {
+            if let Some(message) = 68usize
+                .checked_add(length)
+                .and_then(|end| output.get(68..end))
+            {
+                return String::from_utf8_lossy(message).to_string();
+            }
+        }
+    }
+    format!("0x{}", revm::primitives::hex::encode(output))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use revm::primitives::address;
+
+    /// Creation code of a contract whose runtime code returns the 32-byte word `1` for any call.
+    pub(crate) const RETURN_ONE_CONTRACT: &str = "600a600c600039600a6000f3600160005260206000f3";
+
+    /// Creation code of a contract whose runtime code reverts without a reason for any call.
+    pub(crate) const REVERTING_CONTRACT: &str = "6005600c60003960056000f360006000fd";
+
+    fn creation_code(hex: &str) -> Vec<u8> {
+        revm::primitives::hex::decode(hex).unwrap()
+    }
+
+    #[test]
+    fn test_deploy_and_call() {
+        let mut evm = LocalEvm::new();
+        let deployer = address!("1000000000000000000000000000000000000001");
+
+        let (contract, deployment) = evm
+            .deploy(deployer, creation_code(RETURN_ONE_CONTRACT))
+            .unwrap();
+        assert!(deployment.success);
+
+        let outcome = evm.call(deployer, contract, vec![1, 2, 3]).unwrap();
+        assert!(outcome.success);
+        assert!(outcome.gas_used > 21_000);
+        assert_eq!(outcome.output[31], 1);
+    }
+
+    #[test]
+    fn test_call_reverting_contract() {
+        let mut evm = LocalEvm::new();
+        let deployer = address!("1000000000000000000000000000000000000001");
+        let (contract, _) = evm
+            .deploy(deployer, creation_code(REVERTING_CONTRACT))
+            .unwrap();
+
+        let outcome = evm.call(deployer, contract, Vec::new()).unwrap();
+
+        assert!(!outcome.success);
+        assert_eq!(
+            outcome.revert_reason,
+            Some("reverted without a reason".to_string())
+        );
+    }
+
+    #[test]
+    fn test_deploy_failure() {
+        let mut evm = LocalEvm::new();
+        let deployer = address!("1000000000000000000000000000000000000001");
+
+        // Creation code that immediately reverts.
+        let result = evm.deploy(deployer, creation_code("60006000fd"));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_decode_revert_reason() {
+        let mut payload = ERROR_STRING_SELECTOR.to_vec();
+        payload.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+        payload.extend_from_slice(&U256::from(4).to_be_bytes::<32>());
+        payload.extend_from_slice(b"nope");
+        payload.extend_from_slice(&[0u8; 28]);
+
+        assert_eq!(decode_revert_reason(&payload), "nope");
+        assert_eq!(decode_revert_reason(&[]), "reverted without a reason");
+        assert_eq!(decode_revert_reason(&[0xde, 0xad]), "0xdead");
+    }
+
+    #[test]
+    fn test_decode_revert_reason_with_oversized_length() {
+        let mut payload = ERROR_STRING_SELECTOR.to_vec();
+        payload.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+        payload.extend_from_slice(&U256::from(usize::MAX).to_be_bytes::<32>());
+
+        assert!(decode_revert_reason(&payload).starts_with("0x08c379a0"));
+    }
+}
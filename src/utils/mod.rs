@@ -1,2 +1,15 @@
 pub mod command_runner;
+pub mod config;
+pub mod error;
 pub mod filesystem_operations;
+pub mod interrupt;
+pub mod local_evm;
+pub mod pipeline;
+pub mod progress;
+pub mod proofs_file;
+pub mod public_signals;
+pub mod r1cs;
+pub mod secrets;
+pub mod solidity;
+pub mod template_renderer;
+pub mod verification_key;
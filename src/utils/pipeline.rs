@@ -0,0 +1,423 @@
+use crate::utils::{config::Config, error::WhitelistError, filesystem_operations::FileSystemOps};
+use clap::ValueEnum;
+use std::{collections::BTreeMap, fmt, time::SystemTime};
+
+/// The name of the file recording the input hashes of the steps that ran, in the artifact directory.
+pub const PIPELINE_STATE_FILE: &str = ".pipeline.json";
+
+/// A step of the `all` pipeline.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Step {
+    /// Writes the circuit file
+    Circuit,
+    /// Compiles the circuit
+    Compile,
+    /// Runs the setup ceremony
+    Setup,
+    /// Generates the verifier contract
+    Verifier,
+    /// Generates the token contract
+    Token,
+    /// Moves the witness generator next to the other artifacts
+    Movejs,
+    /// Generates the proofs
+    Proofs,
+}
+
+impl Step {
+    /// Every step, in an order where each step comes after its dependencies.
+    pub const ALL: [Step; 7] = [
+        Step::Circuit,
+        Step::Compile,
+        Step::Setup,
+        Step::Verifier,
+        Step::Token,
+        Step::Movejs,
+        Step::Proofs,
+    ];
+
+    /// Returns the steps whose outputs this step reads.
+    pub fn dependencies(&self) -> &'static [Step] {
+        match self {
+            Step::Circuit => &[],
+            Step::Compile => &[Step::Circuit],
+            Step::Setup => &[Step::Compile],
+            Step::Verifier => &[Step::Setup],
+            Step::Token => &[Step::Compile, Step::Setup],
+            Step::Movejs => &[Step::Compile],
+            Step::Proofs => &[Step::Setup, Step::Movejs],
+        }
+    }
+
+    /// Returns whether this step reads, directly or not, the outputs of `step`.
+    pub fn depends_on(&self, step: Step) -> bool {
+        self.dependencies()
+            .iter()
+            .any(|dependency| *dependency == step || dependency.depends_on(step))
+    }
+
+    /// Returns the files this step reads.
+    ///
+    /// # Parameters
+    /// - `config`: The project configuration locating the files.
+    /// - `input_file`: The file listing the whitelisted addresses.
+    pub fn inputs(&self, config: &Config, input_file: &str) -> Vec<String> {
+        let name = config.circuit_name();
+        match self {
+            Step::Circuit => vec![],
+            Step::Compile => vec![config.circuit.path.clone()],
+            Step::Setup | Step::Movejs => vec![config.artifact(&format!("{}.r1cs", name))],
+            Step::Verifier => vec![config.verification_key()],
+            Step::Token => vec![
+                config.artifact(&format!("{}.sym", name)),
+                config.verification_key(),
+            ],
+            Step::Proofs => vec![
+                input_file.to_string(),
+                config.artifact(&format!("{}.wasm", name)),
+                config.secret_file(&config.zkey("0001")),
+            ],
+        }
+    }
+
+    /// Returns the files this step writes.
+    ///
+    /// # Parameters
+    /// - `config`: The project configuration locating the files.
+    /// - `input_file`: The file listing the whitelisted addresses.
+    pub fn outputs(&self, config: &Config, input_file: &str) -> Vec<String> {
+        let name = config.circuit_name();
+        match self {
+            Step::Circuit => vec![config.circuit.path.clone()],
+            Step::Compile => vec![
+                config.artifact(&format!("{}.r1cs", name)),
+                config.artifact(&format!("{}.sym", name)),
+            ],
+            Step::Setup => vec![
+                config.secret_file(&config.zkey("0001")),
+                config.verification_key(),
+            ],
+            Step::Verifier => vec![config.public_file("verifier.sol")],
+            Step::Token => vec![config.public_file("zkToken.sol")],
+            Step::Movejs => vec![
+                config.artifact("generate_witness.js"),
+                config.artifact(&format!("{}.wasm", name)),
+            ],
+            Step::Proofs => vec![config.proofs_file(input_file)],
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Step::Circuit => "circuit",
+            Step::Compile => "compile",
+            Step::Setup => "setup",
+            Step::Verifier => "verifier",
+            Step::Token => "token",
+            Step::Movejs => "movejs",
+            Step::Proofs => "proofs",
+        };
+        f.pad(name)
+    }
+}
+
+/// Whether a step of the plan runs, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    Run(String),
+    Skip(String),
+}
+
+/// A step of the plan together with the decision taken for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedStep {
+    pub step: Step,
+    pub decision: Decision,
+}
+
+impl PlannedStep {
+    /// Returns whether the step runs.
+    pub fn runs(&self) -> bool {
+        matches!(self.decision, Decision::Run(_))
+    }
+}
+
+impl fmt::Display for PlannedStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.decision {
+            Decision::Run(reason) => write!(f, "{:<9} run   {}", self.step, reason),
+            Decision::Skip(reason) => write!(f, "{:<9} skip  {}", self.step, reason),
+        }
+    }
+}
+
+/// The input hashes every step had when it last ran, keyed by step and then by file.
+pub type PipelineState = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Reads the pipeline state, which is empty if the pipeline never ran.
+pub fn read_state<F: FileSystemOps>(file_ops: &F, config: &Config) -> PipelineState {
+    file_ops
+        .read_to_string(&config.artifact(PIPELINE_STATE_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Records the current hashes of the inputs of a step that ran and writes the pipeline state.
+pub fn record_step<F: FileSystemOps>(
+    file_ops: &F,
+    config: &Config,
+    input_file: &str,
+    step: Step,
+    state: &mut PipelineState,
+) -> Result<(), WhitelistError> {
+    let hashes = step
+        .inputs(config, input_file)
+        .into_iter()
+        .filter_map(|input| file_ops.hash_file(&input).ok().map(|hash| (input, hash)))
+        .collect();
+    state.insert(step.to_string(), hashes);
+    let content =
+        serde_json::to_string_pretty(state).map_err(|e| WhitelistError::Other(e.to_string()))?;
+    file_ops.write_to_file(&config.artifact(PIPELINE_STATE_FILE), &content)
+}
+
+/// Decides whether a step whose dependencies do not run is up to date.
+///
+/// A step is up to date when all its outputs exist and either every output is newer than
+/// every input, or the inputs hash to the values recorded when the step last ran.
+fn check_up_to_date<F: FileSystemOps>(
+    file_ops: &F,
+    config: &Config,
+    input_file: &str,
+    step: Step,
+    state: &PipelineState,
+) -> Decision {
+    let mut oldest_output: Option<SystemTime> = None;
+    for output in step.outputs(config, input_file) {
+        match file_ops.modified(&output) {
+            Some(modified) => {
+                oldest_output = Some(oldest_output.map_or(modified, |oldest| oldest.min(modified)))
+            }
+            None => return Decision::Run(format!("{} is missing", output)),
+        }
+    }
+    let inputs = step.inputs(config, input_file);
+    if inputs.is_empty() {
+        // Steps without inputs, like writing the circuit, never overwrite their outputs.
+        return Decision::Skip(format!(
+            "{} exists",
+            step.outputs(config, input_file).join(", ")
+        ));
+    }
+    let newer_input = inputs.iter().find(|input| {
+        match (file_ops.modified(input), oldest_output) {
+            (Some(modified), Some(oldest)) => modified > oldest,
+            // A missing input cannot be compared; running the step reports it.
+            (None, _) => true,
+            (Some(_), None) => false,
+        }
+    });
+    let Some(newer_input) = newer_input else {
+        return Decision::Skip("outputs are up to date".to_string());
+    };
+    let recorded = state.get(&step.to_string());
+    let unchanged = inputs.iter().all(|input| {
+        let recorded_hash = recorded.and_then(|hashes| hashes.get(input));
+        recorded_hash.is_some() && file_ops.hash_file(input).ok().as_ref() == recorded_hash
+    });
+    if unchanged {
+        Decision::Skip("inputs are unchanged".to_string())
+    } else {
+        Decision::Run(format!("{} changed", newer_input))
+    }
+}
+
+/// Plans which steps of the pipeline run.
+///
+/// # Parameters
+/// - `file_ops`: The file system operations used to inspect the inputs and outputs.
+/// - `config`: The project configuration locating the files.
+/// - `input_file`: The file listing the whitelisted addresses.
+/// - `from`: A step that runs together with every step depending on it, whether up to date or not.
+/// - `only`: The steps that run, whether up to date or not, all others being skipped.
+///
+/// # Returns
+/// Every step in pipeline order with the decision taken for it. A step that is not forced
+/// runs when it is out of date or when one of its dependencies runs.
+pub fn plan<F: FileSystemOps>(
+    file_ops: &F,
+    config: &Config,
+    input_file: &str,
+    from: Option<Step>,
+    only: &[Step],
+) -> Vec<PlannedStep> {
+    let state = read_state(file_ops, config);
+    let mut planned: Vec<PlannedStep> = Vec::new();
+    for step in Step::ALL {
+        let running_dependency = step.dependencies().iter().find(|dependency| {
+            planned
+                .iter()
+                .any(|planned| planned.step == **dependency && planned.runs())
+        });
+        let decision = if !only.is_empty() {
+            if only.contains(&step) {
+                Decision::Run("selected with --only".to_string())
+            } else {
+                Decision::Skip("not selected with --only".to_string())
+            }
+        } else if from.is_some_and(|from| step == from || step.depends_on(from)) {
+            Decision::Run("selected with --from".to_string())
+        } else if let Some(dependency) = running_dependency {
+            Decision::Run(format!("{} runs", dependency))
+        } else {
+            check_up_to_date(file_ops, config, input_file, step, &state)
+        };
+        planned.push(PlannedStep { step, decision });
+    }
+    planned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+    use std::time::Duration;
+
+    /// Stubs every input and output of the pipeline, written in pipeline order.
+    fn stub_completed_pipeline(file_ops: &MockFileSystemOps, config: &Config) {
+        let mut time = SystemTime::UNIX_EPOCH;
+        for step in Step::ALL {
+            for file in step
+                .inputs(config, "addresses.txt")
+                .into_iter()
+                .chain(step.outputs(config, "addresses.txt"))
+            {
+                time += Duration::from_secs(1);
+                if !file_ops.exists(&file) {
+                    file_ops.stub_modified(&file, time);
+                    file_ops.stub_file_content(&file, vec![file.clone()]);
+                }
+            }
+        }
+    }
+
+    fn decisions(planned: &[PlannedStep]) -> Vec<(Step, bool)> {
+        planned.iter().map(|p| (p.step, p.runs())).collect()
+    }
+
+    #[test]
+    fn test_steps_follow_their_dependencies() {
+        for (index, step) in Step::ALL.iter().enumerate() {
+            for dependency in step.dependencies() {
+                assert!(Step::ALL[..index].contains(dependency));
+            }
+        }
+        assert!(Step::Proofs.depends_on(Step::Circuit));
+        assert!(!Step::Movejs.depends_on(Step::Setup));
+    }
+
+    #[test]
+    fn test_plan_without_artifacts_runs_every_step() {
+        let planned = plan(
+            &MockFileSystemOps::new(),
+            &Config::default(),
+            "addresses.txt",
+            None,
+            &[],
+        );
+
+        assert!(planned.iter().all(PlannedStep::runs));
+        assert_eq!(
+            planned[0].decision,
+            Decision::Run("circuit.circom is missing".to_string())
+        );
+        assert_eq!(
+            planned[1].decision,
+            Decision::Run("circuit runs".to_string())
+        );
+    }
+
+    #[test]
+    fn test_plan_skips_up_to_date_steps() {
+        let file_ops = MockFileSystemOps::new();
+        let config = Config::default();
+        stub_completed_pipeline(&file_ops, &config);
+
+        let planned = plan(&file_ops, &config, "addresses.txt", None, &[]);
+
+        assert!(planned.iter().all(|p| !p.runs()));
+    }
+
+    #[test]
+    fn test_plan_reruns_steps_after_a_changed_input() {
+        let file_ops = MockFileSystemOps::new();
+        let config = Config::default();
+        stub_completed_pipeline(&file_ops, &config);
+        file_ops.stub_modified("addresses.txt", SystemTime::now());
+
+        let planned = plan(&file_ops, &config, "addresses.txt", None, &[]);
+
+        assert_eq!(
+            planned.last().unwrap().decision,
+            Decision::Run("addresses.txt changed".to_string())
+        );
+        assert_eq!(planned.iter().filter(|p| p.runs()).count(), 1);
+    }
+
+    #[test]
+    fn test_plan_skips_steps_with_newer_but_unchanged_inputs() {
+        let file_ops = MockFileSystemOps::new();
+        let config = Config::default();
+        stub_completed_pipeline(&file_ops, &config);
+        let mut state = PipelineState::new();
+        record_step(
+            &file_ops,
+            &config,
+            "addresses.txt",
+            Step::Compile,
+            &mut state,
+        )
+        .unwrap();
+        file_ops.stub_file_content(
+            PIPELINE_STATE_FILE,
+            vec![file_ops.get_written_content(PIPELINE_STATE_FILE).unwrap()],
+        );
+        file_ops.stub_modified("circuit.circom", SystemTime::now());
+
+        let planned = plan(&file_ops, &config, "addresses.txt", None, &[]);
+
+        assert_eq!(
+            planned[1].decision,
+            Decision::Skip("inputs are unchanged".to_string())
+        );
+        assert!(planned.iter().all(|p| !p.runs()));
+    }
+
+    #[test]
+    fn test_plan_with_from_and_only() {
+        let file_ops = MockFileSystemOps::new();
+        let config = Config::default();
+        stub_completed_pipeline(&file_ops, &config);
+
+        let from_setup = plan(&file_ops, &config, "addresses.txt", Some(Step::Setup), &[]);
+        let only_proofs = plan(&file_ops, &config, "addresses.txt", None, &[Step::Proofs]);
+
+        assert_eq!(
+            decisions(&from_setup),
+            vec![
+                (Step::Circuit, false),
+                (Step::Compile, false),
+                (Step::Setup, true),
+                (Step::Verifier, true),
+                (Step::Token, true),
+                (Step::Movejs, false),
+                (Step::Proofs, true),
+            ]
+        );
+        assert_eq!(only_proofs.iter().filter(|p| p.runs()).count(), 1);
+        assert!(only_proofs[6].runs());
+    }
+}
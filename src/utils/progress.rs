@@ -0,0 +1,237 @@
+use crate::utils::{config::ProgressMode, error::WhitelistError};
+use serde_json::{json, Value};
+use std::{
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// The width of the progress bar, in characters.
+const BAR_WIDTH: usize = 30;
+
+/// What happened to the proof of an address.
+#[derive(Debug, Clone, Copy)]
+pub enum ProofEvent<'a> {
+    /// The proof of the address is being generated.
+    Started(&'a str),
+    /// The proof of the address was generated.
+    Done(&'a str),
+    /// The proof of the address failed with the given error.
+    Failed(&'a str, &'a WhitelistError),
+}
+
+impl ProofEvent<'_> {
+    /// Returns the name of the event in the JSON log.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProofEvent::Started(_) => "proof_started",
+            ProofEvent::Done(_) => "proof_done",
+            ProofEvent::Failed(_, _) => "proof_failed",
+        }
+    }
+
+    /// Returns the address of the proof.
+    pub fn address(&self) -> &str {
+        match self {
+            ProofEvent::Started(address)
+            | ProofEvent::Done(address)
+            | ProofEvent::Failed(address, _) => address,
+        }
+    }
+}
+
+/// Reports the progress of a run of proofs, as a progress bar, JSON events or not at all.
+///
+/// The progress bar is drawn on the standard error, redrawn in place on a terminal and printed
+/// once per proof otherwise. JSON events are printed one per line on the standard output.
+pub struct Progress {
+    mode: ProgressMode,
+    total: usize,
+    done: usize,
+    started: Instant,
+    terminal: bool,
+    /// Whether the bar is drawn on the current line of the terminal.
+    drawn: bool,
+}
+
+impl Progress {
+    /// Starts reporting the progress of `total` proofs.
+    pub fn new(mode: ProgressMode, total: usize) -> Self {
+        Progress {
+            mode,
+            total,
+            done: 0,
+            started: Instant::now(),
+            terminal: io::stderr().is_terminal(),
+            drawn: false,
+        }
+    }
+
+    /// Reports an event of a proof.
+    pub fn report(&mut self, event: ProofEvent) {
+        if let ProofEvent::Done(_) = event {
+            self.done += 1;
+        }
+        match self.mode {
+            ProgressMode::Quiet => {}
+            ProgressMode::Json => {
+                let line = event_json(&event, self.done, self.total, self.started.elapsed());
+                println!("{}", line);
+            }
+            ProgressMode::Bar => match event {
+                ProofEvent::Started(_) if !self.terminal => {}
+                ProofEvent::Failed(address, _) => {
+                    self.clear_line();
+                    eprintln!("Failed to prove {}", address);
+                }
+                _ => {
+                    let bar = format_bar(self.done, self.total, self.started.elapsed());
+                    match self.terminal {
+                        true => {
+                            eprint!("\r{}", bar);
+                            self.drawn = true;
+                        }
+                        false => eprintln!("{}", bar),
+                    }
+                    let _ = io::stderr().flush();
+                }
+            },
+        }
+    }
+
+    /// Prints a message about the run: on the standard output with the progress bar, on the
+    /// standard error with JSON events so that the standard output stays parsable, and not at
+    /// all when quiet.
+    pub fn message(&mut self, message: &str) {
+        match self.mode {
+            ProgressMode::Quiet => {}
+            ProgressMode::Json => eprintln!("{}", message),
+            ProgressMode::Bar => {
+                self.clear_line();
+                println!("{}", message);
+            }
+        }
+    }
+
+    /// Ends the progress bar line, so that the next output starts on its own line.
+    pub fn finish(&mut self) {
+        self.clear_line();
+    }
+
+    fn clear_line(&mut self) {
+        if self.drawn {
+            eprintln!();
+            self.drawn = false;
+        }
+    }
+}
+
+/// Formats the progress bar: the proofs done out of the total, the proofs per second and the
+/// estimated time left.
+pub fn format_bar(done: usize, total: usize, elapsed: Duration) -> String {
+    let filled = match total {
+        0 => BAR_WIDTH,
+        _ => BAR_WIDTH * done / total,
+    };
+    let mut bar = format!(
+        "[{}{}] {}/{}",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        done,
+        total
+    );
+    let rate = done as f64 / elapsed.as_secs_f64();
+    if done > 0 && rate.is_finite() {
+        let left = Duration::from_secs_f64((total - done) as f64 / rate);
+        bar.push_str(&format!(
+            " {:.2} proofs/s, ETA {}",
+            rate,
+            format_duration(left)
+        ));
+    }
+    bar
+}
+
+/// Formats a duration as `1h02m03s`, `2m03s` or `3s`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+/// Returns the JSON log line of an event, with the number of proofs done and the time since
+/// the run started.
+pub fn event_json(event: &ProofEvent, done: usize, total: usize, elapsed: Duration) -> Value {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut line = json!({
+        "event": event.name(),
+        "address": event.address(),
+        "done": done,
+        "total": total,
+        "elapsed_ms": elapsed.as_millis() as u64,
+        "timestamp_ms": timestamp.as_millis() as u64,
+    });
+    if let ProofEvent::Failed(_, error) = event {
+        line["error"] = json!(error.to_string());
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bar() {
+        assert_eq!(
+            format_bar(0, 4, Duration::from_secs(0)),
+            format!("[{}] 0/4", "-".repeat(30))
+        );
+        assert_eq!(
+            format_bar(1, 4, Duration::from_secs(2)),
+            format!(
+                "[{}{}] 1/4 0.50 proofs/s, ETA 6s",
+                "#".repeat(7),
+                "-".repeat(23)
+            )
+        );
+        assert_eq!(
+            format_bar(4, 4, Duration::from_secs(8)),
+            format!("[{}] 4/4 0.50 proofs/s, ETA 0s", "#".repeat(30))
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(3)), "3s");
+        assert_eq!(format_duration(Duration::from_secs(123)), "2m03s");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h02m03s");
+    }
+
+    #[test]
+    fn test_event_json() {
+        let address = "0x1234567890abcdef1234567890abcdef12345678";
+        let error = WhitelistError::SubprocessFailed("snarkjs failed".to_string());
+
+        let done = event_json(
+            &ProofEvent::Done(address),
+            1,
+            2,
+            Duration::from_millis(1500),
+        );
+        let failed = event_json(&ProofEvent::Failed(address, &error), 1, 2, Duration::ZERO);
+
+        assert_eq!(done["event"], "proof_done");
+        assert_eq!(done["address"], address);
+        assert_eq!(done["done"], 1);
+        assert_eq!(done["total"], 2);
+        assert_eq!(done["elapsed_ms"], 1500);
+        assert!(done.get("error").is_none());
+        assert_eq!(failed["event"], "proof_failed");
+        assert_eq!(failed["error"], "snarkjs failed");
+    }
+}
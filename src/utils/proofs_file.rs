@@ -0,0 +1,197 @@
+use crate::utils::{
+    config::{Config, OutputFormat},
+    error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+/// The columns of a CSV proofs file between the address and the public inputs.
+pub const CSV_PROOF_COLUMNS: [&str; 8] =
+    ["pA0", "pA1", "pB00", "pB01", "pB10", "pB11", "pC0", "pC1"];
+
+/// A proof entry of a `*.proofs.json` file.
+///
+/// The values are the hex encoded arguments of the Solidity verifier as printed by
+/// `snarkjs zkesc`, so `p_b` holds each G2 coordinate as `[c1, c0]`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProofEntry {
+    #[serde(rename = "pA")]
+    pub p_a: [String; 2],
+    #[serde(rename = "pB")]
+    pub p_b: [[String; 2]; 2],
+    #[serde(rename = "pC")]
+    pub p_c: [String; 2],
+    pub input: Vec<String>,
+}
+
+/// Parses the content of a `*.proofs.json` file into a map from address to proof.
+///
+/// The map is ordered by address, so iterating over it is deterministic.
+pub fn parse_proofs_file(content: &str) -> Result<BTreeMap<String, ProofEntry>, String> {
+    serde_json::from_str(content).map_err(|e| format!("Failed to parse proofs file: {}", e))
+}
+
+/// Parses the content of a `*.proofs.csv` file, as written by the `csv` output format, into a
+/// map from address to proof.
+pub fn parse_proofs_csv(content: &str) -> Result<BTreeMap<String, ProofEntry>, String> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or("Failed to parse proofs file: missing CSV header")?
+        .split(',')
+        .map(str::trim)
+        .collect();
+    if header.len() < 9 || header[0] != "address" || header[1..9] != CSV_PROOF_COLUMNS {
+        return Err(format!(
+            "Failed to parse proofs file: unexpected CSV header '{}'",
+            header.join(",")
+        ));
+    }
+
+    let mut proofs = BTreeMap::new();
+    for (index, line) in lines.enumerate() {
+        let row: Vec<String> = line.split(',').map(|v| v.trim().to_string()).collect();
+        if row.len() != header.len() {
+            return Err(format!(
+                "Failed to parse proofs file: row {} has {} columns but the header has {}",
+                index + 1,
+                row.len(),
+                header.len()
+            ));
+        }
+        let proof = ProofEntry {
+            p_a: [row[1].clone(), row[2].clone()],
+            p_b: [
+                [row[3].clone(), row[4].clone()],
+                [row[5].clone(), row[6].clone()],
+            ],
+            p_c: [row[7].clone(), row[8].clone()],
+            input: row[9..].to_vec(),
+        };
+        proofs.insert(row[0].clone(), proof);
+    }
+    Ok(proofs)
+}
+
+/// Returns the format of a proofs file: the one of its extension, or the output format of the
+/// configuration when the extension is neither `.json` nor `.csv`.
+pub fn proofs_format(config: &Config, path: &str) -> OutputFormat {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("json") => OutputFormat::Json,
+        Some("csv") => OutputFormat::Csv,
+        _ => config.output.format,
+    }
+}
+
+/// Reads a proofs file written by the `proofs` subcommand, in the format given by
+/// `proofs_format`.
+///
+/// # Returns
+/// The proofs by address, or `WhitelistError::ArtifactMismatch` if the file cannot be parsed.
+pub fn read_proofs_file<F: FileSystemOps>(
+    file_ops: &F,
+    config: &Config,
+    path: &str,
+) -> Result<BTreeMap<String, ProofEntry>, WhitelistError> {
+    let content = file_ops.read_to_string(path)?;
+    match proofs_format(config, path) {
+        OutputFormat::Json => parse_proofs_file(&content),
+        OutputFormat::Csv => parse_proofs_csv(&content),
+    }
+    .map_err(|e| WhitelistError::ArtifactMismatch(format!("{}: {}", path, e)))
+}
+
+/// Converts a `0x` prefixed hex value of a proofs file into its decimal representation.
+pub fn hex_to_decimal(value: &str) -> Result<String, String> {
+    BigUint::parse_bytes(value.trim_start_matches("0x").as_bytes(), 16)
+        .map(|number| number.to_string())
+        .ok_or_else(|| format!("Invalid hex value in proofs file: {}", value))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::utils::filesystem_operations::MockFileSystemOps;
+
+    /// A proofs file with the shape produced by the `proofs` subcommand.
+    pub(crate) const SAMPLE_PROOFS_FILE: &str = r#"{
+        "0xabcdef1234567890abcdef1234567890abcdef12": {
+            "pA": ["0x01", "0x02"],
+            "pB": [["0x03", "0x04"], ["0x05", "0x06"]],
+            "pC": ["0x07", "0x08"],
+            "input": ["0x0000000000000000000000000000000000000000000000000000000000000001", "0xabcdef1234567890abcdef1234567890abcdef12"]
+        },
+        "0x1234567890abcdef1234567890abcdef12345678": {
+            "pA": ["0x11", "0x12"],
+            "pB": [["0x13", "0x14"], ["0x15", "0x16"]],
+            "pC": ["0x17", "0x18"],
+            "input": ["0x0000000000000000000000000000000000000000000000000000000000000001", "0x1234567890abcdef1234567890abcdef12345678"]
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_proofs_file() {
+        let proofs = parse_proofs_file(SAMPLE_PROOFS_FILE).unwrap();
+
+        assert_eq!(proofs.len(), 2);
+        let (address, proof) = proofs.iter().next().unwrap();
+        assert_eq!(address, "0x1234567890abcdef1234567890abcdef12345678");
+        assert_eq!(proof.p_a, ["0x11".to_string(), "0x12".to_string()]);
+        assert_eq!(proof.p_b[1][0], "0x15");
+        assert_eq!(proof.input.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_proofs_file_with_invalid_content() {
+        assert!(parse_proofs_file("{\"0x12\": {\"pA\": []}}").is_err());
+    }
+
+    #[test]
+    fn test_parse_proofs_csv_with_invalid_content() {
+        assert!(parse_proofs_csv("").is_err());
+        assert!(parse_proofs_csv("address,pA0\n0x12,0x01\n").is_err());
+        assert!(parse_proofs_csv(
+            "address,pA0,pA1,pB00,pB01,pB10,pB11,pC0,pC1,input0\n0x12,0x01,0x02\n"
+        )
+        .is_err());
+    }
+
+    /// Tests that `read_proofs_file` picks the parser from the extension of the file.
+    #[test]
+    fn test_read_proofs_file() {
+        let file_ops = MockFileSystemOps::new();
+        file_ops.stub_file_content(
+            "input.txt.proofs.json",
+            SAMPLE_PROOFS_FILE.lines().map(str::to_string).collect(),
+        );
+        file_ops.stub_file_content(
+            "input.txt.proofs.csv",
+            vec![
+                "address,pA0,pA1,pB00,pB01,pB10,pB11,pC0,pC1,input0".to_string(),
+                "0x1234,0x01,0x02,0x03,0x04,0x05,0x06,0x07,0x08,0x09".to_string(),
+            ],
+        );
+        let config = Config::default();
+
+        let json = read_proofs_file(&file_ops, &config, "input.txt.proofs.json").unwrap();
+        let csv = read_proofs_file(&file_ops, &config, "input.txt.proofs.csv").unwrap();
+        let missing = read_proofs_file(&file_ops, &config, "other.proofs.csv");
+
+        assert_eq!(json.len(), 2);
+        assert_eq!(
+            csv["0x1234"].p_b,
+            [["0x03", "0x04"], ["0x05", "0x06"]].map(|r| r.map(String::from))
+        );
+        assert_eq!(csv["0x1234"].input, vec!["0x09".to_string()]);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_hex_to_decimal() {
+        assert_eq!(hex_to_decimal("0x1f"), Ok("31".to_string()));
+        assert_eq!(hex_to_decimal("ff"), Ok("255".to_string()));
+        assert!(hex_to_decimal("0xzz").is_err());
+    }
+}
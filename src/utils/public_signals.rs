@@ -0,0 +1,171 @@
+use serde::Serialize;
+
+/// The public signals of the circuit generated by the `circuit` subcommand, in verifier order.
+///
+/// Outputs come before public inputs, so the output `c` is followed by the address.
+pub const DEFAULT_PUBLIC_SIGNALS: &[&str] = &["c", "sameAddressButPublic"];
+
+/// A public signal of the circuit, at the position the verifier expects it in `_pubSignals`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PublicSignal {
+    /// The name of the signal in the main component, e.g. `sameAddressButPublic` or `out[0]`.
+    pub name: String,
+    /// The name of the Solidity constant holding the index, e.g. `SAME_ADDRESS_BUT_PUBLIC_INDEX`.
+    pub constant: String,
+    /// The index of the signal in `_pubSignals`.
+    pub index: usize,
+}
+
+impl PublicSignal {
+    fn new(name: &str, index: usize) -> Self {
+        PublicSignal {
+            name: name.to_string(),
+            constant: constant_name(name),
+            index,
+        }
+    }
+}
+
+/// Returns the public signals of the circuit generated by the `circuit` subcommand.
+pub fn default_public_signals() -> Vec<PublicSignal> {
+    DEFAULT_PUBLIC_SIGNALS
+        .iter()
+        .enumerate()
+        .map(|(index, name)| PublicSignal::new(name, index))
+        .collect()
+}
+
+/// Parses the public signals of a circuit from its `circuit.sym` file.
+///
+/// Every line of the symbols file reads `<signal>,<wire>,<component>,<name>`. The witness
+/// starts with the constant `1` on wire 0, followed by the outputs and then the public inputs
+/// of the main component, which are exactly the `nPublic` signals the verifier takes.
+///
+/// # Parameters
+/// - `sym`: The content of the `circuit.sym` file written by `circom --sym`.
+/// - `n_public`: The number of public signals, as found in `verification_key.json`.
+///
+/// # Returns
+/// The public signals ordered by their index in `_pubSignals`, or an error message if the
+/// symbols file does not name every one of them, or if two names map to the same constant.
+pub fn parse_public_signals(sym: &str, n_public: usize) -> Result<Vec<PublicSignal>, String> {
+    let mut names: Vec<Option<&str>> = vec![None; n_public];
+    for line in sym.lines().filter(|line| !line.trim().is_empty()) {
+        let fields: Vec<&str> = line.trim().splitn(4, ',').collect();
+        let [_, wire, _, name] = fields[..] else {
+            return Err(format!("Invalid line in circuit.sym: {}", line));
+        };
+        let wire: i64 = wire
+            .parse()
+            .map_err(|_| format!("Invalid wire in circuit.sym: {}", line))?;
+        // Signals of subcomponents can share a wire with a public signal, so only the
+        // signals declared in the main component itself are considered.
+        let Some(name) = name
+            .strip_prefix("main.")
+            .filter(|name| !name.contains('.'))
+        else {
+            continue;
+        };
+        if wire >= 1 && wire as usize <= n_public {
+            names[wire as usize - 1].get_or_insert(name);
+        }
+    }
+
+    let signals: Vec<PublicSignal> = names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| match name {
+            Some(name) => Ok(PublicSignal::new(name, index)),
+            None => Err(format!(
+                "circuit.sym names no signal of the main component for public signal {}",
+                index
+            )),
+        })
+        .collect::<Result<_, _>>()?;
+    for (index, signal) in signals.iter().enumerate() {
+        if let Some(other) = signals[..index]
+            .iter()
+            .find(|other| other.constant == signal.constant)
+        {
+            return Err(format!(
+                "The public signals {} and {} both map to the Solidity constant {}, rename one of them",
+                other.name, signal.name, signal.constant
+            ));
+        }
+    }
+    Ok(signals)
+}
+
+/// Converts a signal name into the name of a Solidity constant, e.g. `out[0]` into `OUT_0_INDEX`.
+fn constant_name(name: &str) -> String {
+    let mut constant = String::new();
+    let mut previous: Option<char> = None;
+    for character in name.chars() {
+        match character {
+            '[' => constant.push('_'),
+            ']' => {}
+            _ => {
+                if character.is_ascii_uppercase()
+                    && previous.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+                {
+                    constant.push('_');
+                }
+                constant.push(character.to_ascii_uppercase());
+            }
+        }
+        previous = Some(character);
+    }
+    constant.push_str("_INDEX");
+    constant
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The symbols of the default circuit, as written by `circom --sym`.
+    const SAMPLE_SYM: &str = "1,1,1,main.c\n\
+        2,2,1,main.sameAddressButPublic\n\
+        3,3,1,main.addressInDecimal\n\
+        4,1,0,main.ise.out\n\
+        5,2,0,main.ise.in[0]\n\
+        6,-1,0,main.ise.isz.inv\n";
+
+    #[test]
+    fn test_parse_public_signals() {
+        let signals = parse_public_signals(SAMPLE_SYM, 2).unwrap();
+
+        assert_eq!(signals, default_public_signals());
+        assert_eq!(signals[1].name, "sameAddressButPublic");
+        assert_eq!(signals[1].constant, "SAME_ADDRESS_BUT_PUBLIC_INDEX");
+        assert_eq!(signals[1].index, 1);
+    }
+
+    #[test]
+    fn test_parse_public_signals_with_reordered_circuit() {
+        let sym = "1,1,0,main.out[0]\n2,2,0,main.out[1]\n3,3,0,main.account\n4,4,0,main.secret\n";
+
+        let signals = parse_public_signals(sym, 3).unwrap();
+
+        let constants: Vec<&str> = signals.iter().map(|s| s.constant.as_str()).collect();
+        assert_eq!(constants, ["OUT_0_INDEX", "OUT_1_INDEX", "ACCOUNT_INDEX"]);
+    }
+
+    #[test]
+    fn test_parse_public_signals_with_colliding_constants() {
+        let sym = "1,1,0,main.fooBar\n2,2,0,main.foo_bar\n";
+
+        let error = parse_public_signals(sym, 2).unwrap_err();
+
+        assert!(
+            error.contains("fooBar and foo_bar both map to the Solidity constant FOO_BAR_INDEX")
+        );
+    }
+
+    #[test]
+    fn test_parse_public_signals_with_invalid_content() {
+        assert!(parse_public_signals(SAMPLE_SYM, 4).is_err());
+        assert!(parse_public_signals("1,main.c", 1).is_err());
+        assert!(parse_public_signals("1,x,1,main.c", 1).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+/// The header of a compiled circuit, as written by `circom --r1cs`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct R1csHeader {
+    pub n_wires: u32,
+    pub n_pub_out: u32,
+    pub n_pub_in: u32,
+    pub n_prv_in: u32,
+    pub n_constraints: u32,
+}
+
+impl R1csHeader {
+    /// Returns the number of public signals, which the verifier takes as `_pubSignals`.
+    pub fn n_public(&self) -> usize {
+        (self.n_pub_out + self.n_pub_in) as usize
+    }
+}
+
+/// Reads little endian integers from a byte slice, failing instead of panicking at its end.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, length: usize) -> Result<&[u8], String> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("Unexpected end of the r1cs file")?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let mut word = [0; 4];
+        word.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(word))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        let mut word = [0; 8];
+        word.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(word))
+    }
+}
+
+/// Parses the header section of an r1cs file.
+///
+/// The file starts with the `r1cs` magic, a version and the number of sections, each section
+/// being a type, a byte length and its content. The header is the section of type 1.
+///
+/// # Returns
+/// The header, or an error message if the content is not an r1cs file.
+pub fn parse_r1cs_header(bytes: &[u8]) -> Result<R1csHeader, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != b"r1cs" {
+        return Err("Not an r1cs file".to_string());
+    }
+    let _version = reader.u32()?;
+    let n_sections = reader.u32()?;
+    for _ in 0..n_sections {
+        let section_type = reader.u32()?;
+        let size = usize::try_from(reader.u64()?).map_err(|e| e.to_string())?;
+        if section_type != 1 {
+            reader.take(size)?;
+            continue;
+        }
+        let field_size = reader.u32()? as usize;
+        let _prime = reader.take(field_size)?;
+        let n_wires = reader.u32()?;
+        let n_pub_out = reader.u32()?;
+        let n_pub_in = reader.u32()?;
+        let n_prv_in = reader.u32()?;
+        let _n_labels = reader.u64()?;
+        let n_constraints = reader.u32()?;
+        return Ok(R1csHeader {
+            n_wires,
+            n_pub_out,
+            n_pub_in,
+            n_prv_in,
+            n_constraints,
+        });
+    }
+    Err("The r1cs file has no header section".to_string())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Builds an r1cs file with an empty constraints section followed by the header.
+    pub fn sample_r1cs(n_pub_out: u32, n_pub_in: u32) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend(32u32.to_le_bytes());
+        header.extend([0xff; 32]);
+        for value in [6, n_pub_out, n_pub_in, 1] {
+            header.extend(u32::to_le_bytes(value));
+        }
+        header.extend(7u64.to_le_bytes());
+        header.extend(3u32.to_le_bytes());
+
+        let mut bytes = b"r1cs".to_vec();
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend(4u64.to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend((header.len() as u64).to_le_bytes());
+        bytes.extend(header);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_r1cs_header() {
+        let header = parse_r1cs_header(&sample_r1cs(1, 1)).unwrap();
+
+        assert_eq!(
+            header,
+            R1csHeader {
+                n_wires: 6,
+                n_pub_out: 1,
+                n_pub_in: 1,
+                n_prv_in: 1,
+                n_constraints: 3,
+            }
+        );
+        assert_eq!(header.n_public(), 2);
+    }
+
+    #[test]
+    fn test_parse_r1cs_header_with_invalid_content() {
+        let r1cs = sample_r1cs(1, 1);
+
+        assert!(parse_r1cs_header(b"circuit").is_err());
+        assert!(parse_r1cs_header(&r1cs[..r1cs.len() - 2]).is_err());
+        assert!(parse_r1cs_header(&r1cs[..28]).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+use crate::utils::{
+    config::{Config, SecretsConfig},
+    error::WhitelistError,
+    filesystem_operations::FileSystemOps,
+    interrupt::{self, Deferred},
+};
+use age::{
+    secrecy::SecretString,
+    x25519::{Identity, Recipient},
+};
+use std::{env, io, iter, path::Path};
+
+/// The environment variable holding the passphrase, for non-interactive use.
+pub const PASSPHRASE_ENV: &str = "ZK_WHITELIST_PASSPHRASE";
+
+/// The scrypt work factor of passphrase encryption, the default of `age`.
+const SCRYPT_WORK_FACTOR: u8 = 18;
+
+/// The key encrypting the zkeys and ptau files.
+pub enum SecretKey {
+    /// A passphrase, stretched with scrypt at the given work factor.
+    Passphrase(SecretString, u8),
+    /// An age X25519 identity, read from a keyfile.
+    Identity(Identity),
+}
+
+impl SecretKey {
+    /// Loads the key configured in the `[secrets]` table.
+    ///
+    /// The keyfile is used when set, otherwise the passphrase is read from
+    /// `ZK_WHITELIST_PASSPHRASE` or prompted for.
+    ///
+    /// # Returns
+    /// The key, or `WhitelistError::InvalidInput` if the keyfile is invalid or the passphrase
+    /// is empty.
+    pub fn load<F: FileSystemOps>(
+        file_ops: &F,
+        secrets: &SecretsConfig,
+    ) -> Result<Self, WhitelistError> {
+        SecretKey::load_with(file_ops, secrets, true)
+    }
+
+    /// Loads the key configured in the `[secrets]` table as `load` does, without prompting for
+    /// the passphrase, for commands running unattended such as `serve`.
+    ///
+    /// # Returns
+    /// The key, or `WhitelistError::InvalidInput` if there is neither a keyfile nor
+    /// `ZK_WHITELIST_PASSPHRASE`, or if either is invalid.
+    pub fn load_without_prompt<F: FileSystemOps>(
+        file_ops: &F,
+        secrets: &SecretsConfig,
+    ) -> Result<Self, WhitelistError> {
+        SecretKey::load_with(file_ops, secrets, false)
+    }
+
+    fn load_with<F: FileSystemOps>(
+        file_ops: &F,
+        secrets: &SecretsConfig,
+        prompt: bool,
+    ) -> Result<Self, WhitelistError> {
+        if let Some(keyfile) = &secrets.keyfile {
+            let content = file_ops
+                .read_to_string(keyfile)
+                .map_err(|e| WhitelistError::InvalidInput(format!("Keyfile {}: {}", keyfile, e)))?;
+            return SecretKey::from_keyfile(&content)
+                .map_err(|e| WhitelistError::InvalidInput(format!("{}: {}", keyfile, e)));
+        }
+        let passphrase = match env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) => passphrase,
+            Err(_) if prompt => {
+                rpassword::prompt_password("Passphrase of the zkey and ptau files: ")?
+            }
+            Err(_) => {
+                return Err(WhitelistError::InvalidInput(format!(
+                    "The zkey is encrypted, set a keyfile in [secrets] or {}",
+                    PASSPHRASE_ENV
+                )))
+            }
+        };
+        if passphrase.is_empty() {
+            return Err(WhitelistError::InvalidInput(
+                "The passphrase of the zkey and ptau files must not be empty".to_string(),
+            ));
+        }
+        Ok(SecretKey::Passphrase(
+            SecretString::from(passphrase),
+            SCRYPT_WORK_FACTOR,
+        ))
+    }
+
+    /// Parses an age identity file, as written by `age-keygen`.
+    pub fn from_keyfile(content: &str) -> Result<Self, String> {
+        content
+            .lines()
+            .map(str::trim)
+            .find(|line| line.starts_with("AGE-SECRET-KEY-"))
+            .ok_or("No AGE-SECRET-KEY found in the keyfile")?
+            .parse::<Identity>()
+            .map(SecretKey::Identity)
+            .map_err(|e| format!("Invalid age identity: {}", e))
+    }
+
+    fn encryptor(&self) -> Result<age::Encryptor, WhitelistError> {
+        match self {
+            SecretKey::Passphrase(passphrase, work_factor) => {
+                let mut recipient = age::scrypt::Recipient::new(passphrase.clone());
+                recipient.set_work_factor(*work_factor);
+                age::Encryptor::with_recipients(iter::once(&recipient as &dyn age::Recipient))
+            }
+            SecretKey::Identity(identity) => {
+                let recipient: Recipient = identity.to_public();
+                age::Encryptor::with_recipients(iter::once(&recipient as &dyn age::Recipient))
+            }
+        }
+        .map_err(|e| WhitelistError::Other(e.to_string()))
+    }
+
+    fn decrypt<R: io::Read>(
+        &self,
+        path: &str,
+        input: R,
+    ) -> Result<age::stream::StreamReader<R>, WhitelistError> {
+        let decryptor = age::Decryptor::new(input).map_err(|e| {
+            WhitelistError::ArtifactMismatch(format!("{} is not an age file: {}", path, e))
+        })?;
+        match self {
+            SecretKey::Passphrase(passphrase, _) => {
+                let identity = age::scrypt::Identity::new(passphrase.clone());
+                decryptor.decrypt(iter::once(&identity as &dyn age::Identity))
+            }
+            SecretKey::Identity(identity) => {
+                decryptor.decrypt(iter::once(identity as &dyn age::Identity))
+            }
+        }
+        .map_err(|e| {
+            WhitelistError::InvalidInput(format!(
+                "Failed to decrypt {}, wrong passphrase or keyfile? {}",
+                path, e
+            ))
+        })
+    }
+}
+
+/// Encrypts a file to `<path>.age` with 0600 permissions, then overwrites and removes the
+/// plaintext.
+///
+/// The file is streamed, so ptau files of any size are encrypted without loading them in memory.
+pub fn encrypt_file<F: FileSystemOps>(
+    file_ops: &F,
+    key: &SecretKey,
+    path: &str,
+) -> Result<String, WhitelistError> {
+    let encrypted_path = format!("{}.age", path);
+    // The ciphertext only replaces a previous one once complete.
+    let partial_path = format!("{}.partial", encrypted_path);
+    let mut plaintext = file_ops.open(path)?;
+    let output = file_ops.create_private(&partial_path)?;
+    let mut writer = key
+        .encryptor()?
+        .wrap_output(output)
+        .map_err(|e| WhitelistError::io(&partial_path, e))?;
+    io::copy(&mut plaintext, &mut writer).map_err(|e| WhitelistError::io(path, e))?;
+    writer
+        .finish()
+        .map_err(|e| WhitelistError::io(&partial_path, e))?;
+    file_ops.rename(&partial_path, &encrypted_path)?;
+    file_ops.overwrite(path)?;
+    file_ops.remove(path)?;
+    Ok(encrypted_path)
+}
+
+/// Encrypts the ptau files and zkeys the setup wrote, leaving no plaintext copy behind.
+///
+/// # Returns
+/// The paths of the encrypted files.
+pub fn encrypt_setup_secrets<F: FileSystemOps>(
+    file_ops: &F,
+    key: &SecretKey,
+    config: &Config,
+) -> Result<Vec<String>, WhitelistError> {
+    config
+        .setup_secrets()
+        .iter()
+        .filter(|path| file_ops.exists(path))
+        .map(|path| encrypt_file(file_ops, key, path))
+        .collect()
+}
+
+/// A path to a secret in plaintext, overwritten and removed when dropped if it was decrypted
+/// for the occasion.
+///
+/// A decrypted secret defers Ctrl-C while it lives and is registered with the Ctrl-C handler,
+/// so it is removed even when the process exits at once.
+pub struct PlaintextSecret<'a> {
+    path: String,
+    /// The file system operations removing a decrypted plaintext, `None` for the secret itself.
+    temporary: Option<&'a dyn FileSystemOps>,
+    _deferred: Option<Deferred>,
+}
+
+impl PlaintextSecret<'_> {
+    /// Returns the path of the plaintext.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl Drop for PlaintextSecret<'_> {
+    fn drop(&mut self) {
+        if let Some(file_ops) = self.temporary {
+            let _ = file_ops.overwrite(&self.path);
+            let _ = file_ops.remove(&self.path);
+            interrupt::unregister_temporary_secret(&self.path);
+        }
+    }
+}
+
+/// Decrypts an encrypted secret into a file with 0600 permissions, removed once the returned
+/// value is dropped.
+pub fn decrypt_to_temporary_file<'a, F: FileSystemOps>(
+    file_ops: &'a F,
+    key: &SecretKey,
+    encrypted_path: &str,
+    plaintext_path: &str,
+) -> Result<PlaintextSecret<'a>, WhitelistError> {
+    let mut reader = key.decrypt(encrypted_path, file_ops.open(encrypted_path)?)?;
+    let secret = PlaintextSecret {
+        path: plaintext_path.to_string(),
+        temporary: Some(file_ops),
+        _deferred: Some(Deferred::start()),
+    };
+    interrupt::register_temporary_secret(plaintext_path);
+    let mut output = file_ops.create_private(plaintext_path)?;
+    io::copy(&mut reader, &mut output).map_err(|e| WhitelistError::io(encrypted_path, e))?;
+    Ok(secret)
+}
+
+/// Opens a secret of the setup for reading by an external tool.
+///
+/// Without encryption this is the file itself. Otherwise `<path>.age` is decrypted into the
+/// scratch directory for as long as the returned value lives, with `key` or, when it is `None`,
+/// the key loaded from the configuration.
+pub fn open_secret<'a, F: FileSystemOps>(
+    file_ops: &'a F,
+    config: &Config,
+    key: Option<&SecretKey>,
+    path: &str,
+) -> Result<PlaintextSecret<'a>, WhitelistError> {
+    if !config.secrets.encrypt {
+        return Ok(PlaintextSecret {
+            path: path.to_string(),
+            temporary: None,
+            _deferred: None,
+        });
+    }
+    let loaded;
+    let key = match key {
+        Some(key) => key,
+        None => {
+            loaded = SecretKey::load(file_ops, &config.secrets)?;
+            &loaded
+        }
+    };
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    decrypt_to_temporary_file(
+        file_ops,
+        key,
+        &config.secret_file(path),
+        &config.tmp_file(&file_name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::filesystem_operations::RealFileSystemOps;
+    use std::fs;
+
+    fn temp_file(name: &str, content: &[u8]) -> String {
+        let path = env::temp_dir().join(name).to_string_lossy().to_string();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn round_trip(key: &SecretKey, name: &str) {
+        let path = temp_file(name, b"zkey content");
+        let plaintext_path = format!("{}.plain", path);
+
+        let encrypted_path = encrypt_file(&RealFileSystemOps, key, &path).unwrap();
+        assert!(!Path::new(&path).exists());
+        assert_ne!(fs::read(&encrypted_path).unwrap(), b"zkey content");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&encrypted_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        {
+            let secret = decrypt_to_temporary_file(
+                &RealFileSystemOps,
+                key,
+                &encrypted_path,
+                &plaintext_path,
+            )
+            .unwrap();
+            assert_eq!(fs::read(secret.path()).unwrap(), b"zkey content");
+        }
+        assert!(!Path::new(&plaintext_path).exists());
+
+        fs::remove_file(encrypted_path).unwrap();
+    }
+
+    #[test]
+    fn test_encrypt_with_keyfile() {
+        let identity = Identity::generate();
+        let keyfile = format!(
+            "# created: 2024-01-01T00:00:00Z\n# public key: {}\n{}\n",
+            identity.to_public(),
+            age::secrecy::ExposeSecret::expose_secret(&identity.to_string())
+        );
+        let key = SecretKey::from_keyfile(&keyfile).unwrap();
+
+        round_trip(&key, "zk_whitelist_test_keyfile.zkey");
+    }
+
+    #[test]
+    fn test_encrypt_with_passphrase() {
+        // A low work factor keeps the test fast, the real one is set by `SecretKey::load`.
+        let key = SecretKey::Passphrase(SecretString::from("correct horse".to_string()), 4);
+
+        round_trip(&key, "zk_whitelist_test_passphrase.zkey");
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_key() {
+        let path = temp_file("zk_whitelist_test_wrong_key.zkey", b"zkey content");
+        let key = SecretKey::Identity(Identity::generate());
+        let encrypted_path = encrypt_file(&RealFileSystemOps, &key, &path).unwrap();
+
+        let result = decrypt_to_temporary_file(
+            &RealFileSystemOps,
+            &SecretKey::Identity(Identity::generate()),
+            &encrypted_path,
+            &format!("{}.plain", path),
+        );
+
+        assert!(result.is_err());
+        fs::remove_file(encrypted_path).unwrap();
+    }
+
+    #[test]
+    fn test_from_keyfile_without_identity() {
+        assert!(SecretKey::from_keyfile("# public key: age1...\n").is_err());
+    }
+
+    #[test]
+    fn test_open_secret_without_encryption() {
+        let secret = open_secret(
+            &RealFileSystemOps,
+            &Config::default(),
+            None,
+            "circuit_0001.zkey",
+        )
+        .unwrap();
+
+        assert_eq!(secret.path(), "circuit_0001.zkey");
+    }
+}
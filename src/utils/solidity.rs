@@ -0,0 +1,232 @@
+/// Keywords that cannot be used as Solidity identifiers.
+const RESERVED_KEYWORDS: &[&str] = &[
+    "abstract",
+    "address",
+    "after",
+    "alias",
+    "anonymous",
+    "apply",
+    "as",
+    "assembly",
+    "auto",
+    "bool",
+    "break",
+    "byte",
+    "bytes",
+    "calldata",
+    "case",
+    "catch",
+    "constant",
+    "constructor",
+    "continue",
+    "contract",
+    "copyof",
+    "default",
+    "define",
+    "delete",
+    "do",
+    "else",
+    "emit",
+    "enum",
+    "error",
+    "event",
+    "external",
+    "fallback",
+    "false",
+    "final",
+    "fixed",
+    "for",
+    "function",
+    "if",
+    "immutable",
+    "implements",
+    "import",
+    "in",
+    "indexed",
+    "inline",
+    "interface",
+    "internal",
+    "is",
+    "let",
+    "library",
+    "macro",
+    "mapping",
+    "match",
+    "memory",
+    "modifier",
+    "mutable",
+    "new",
+    "null",
+    "of",
+    "override",
+    "partial",
+    "payable",
+    "pragma",
+    "private",
+    "promise",
+    "public",
+    "pure",
+    "receive",
+    "reference",
+    "relocatable",
+    "return",
+    "returns",
+    "revert",
+    "sealed",
+    "sizeof",
+    "static",
+    "storage",
+    "string",
+    "struct",
+    "super",
+    "supports",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "type",
+    "typedef",
+    "typeof",
+    "ufixed",
+    "uint",
+    "unchecked",
+    "unicode",
+    "using",
+    "var",
+    "view",
+    "virtual",
+    "while",
+];
+
+/// Validates that `value` can be used as a Solidity identifier, e.g. a contract name.
+///
+/// # Parameters
+/// - `what`: A human readable description of the value, used in the error message.
+/// - `value`: The identifier to validate.
+///
+/// # Returns
+/// `Ok(())` if the identifier is valid, or an error message describing the problem.
+pub fn validate_identifier(what: &str, value: &str) -> Result<(), String> {
+    let mut chars = value.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$');
+    let valid_rest = chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+    if !valid_start || !valid_rest {
+        return Err(format!(
+            "Invalid {} '{}': must start with a letter, '_' or '$' and contain only letters, digits, '_' or '$'",
+            what, value
+        ));
+    }
+    if RESERVED_KEYWORDS.contains(&value) {
+        return Err(format!(
+            "Invalid {} '{}': it is a reserved Solidity keyword",
+            what, value
+        ));
+    }
+    Ok(())
+}
+
+/// Validates a Solidity version pragma such as `^0.8.20` or `>=0.7.0 <0.9.0`.
+///
+/// Only the version constraint is expected, without the leading `pragma solidity`
+/// and the trailing semicolon.
+pub fn validate_pragma(value: &str) -> Result<(), String> {
+    let valid = !value.trim().is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || " .^~<>=|-xX*".contains(c));
+    if !valid {
+        return Err(format!(
+            "Invalid Solidity pragma '{}': expected a version constraint such as '^0.8.20'",
+            value
+        ));
+    }
+    Ok(())
+}
+
+/// Validates an SPDX license identifier such as `MIT` or `GPL-3.0-or-later`.
+pub fn validate_license(value: &str) -> Result<(), String> {
+    let valid = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " .-+()".contains(c));
+    if !valid {
+        return Err(format!("Invalid SPDX license identifier '{}'", value));
+    }
+    Ok(())
+}
+
+/// The SPDX identifiers compatible with the GPL-3.0 notice of the snarkJS-derived verifier.
+pub const VERIFIER_LICENSES: [&str; 3] = ["GPL-3.0", "GPL-3.0-only", "GPL-3.0-or-later"];
+
+/// Validates that the license of the verifier agrees with the GPL-3.0 notice of the template.
+pub fn validate_verifier_license(value: &str) -> Result<(), String> {
+    if !VERIFIER_LICENSES.contains(&value) {
+        return Err(format!(
+            "Invalid verifier license '{}': the verifier is derived from snarkJS and must be one of {}",
+            value,
+            VERIFIER_LICENSES.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Validates that `value` can be embedded in a Solidity string literal, e.g. a token name.
+///
+/// Only printable ASCII is accepted, without quotes or backslashes, so the value never
+/// needs escaping.
+pub fn validate_string_literal(what: &str, value: &str) -> Result<(), String> {
+    let valid = !value.trim().is_empty()
+        && value
+            .chars()
+            .all(|c| (c.is_ascii_graphic() || c == ' ') && c != '"' && c != '\\');
+    if !valid {
+        return Err(format!(
+            "Invalid {} '{}': must be printable ASCII without quotes or backslashes",
+            what, value
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_identifier() {
+        assert!(validate_identifier("contract name", "Groth16Verifier").is_ok());
+        assert!(validate_identifier("contract name", "_Verifier$1").is_ok());
+        assert!(validate_identifier("contract name", "").is_err());
+        assert!(validate_identifier("contract name", "1Verifier").is_err());
+        assert!(validate_identifier("contract name", "My Verifier").is_err());
+        assert!(validate_identifier("contract name", "contract").is_err());
+    }
+
+    #[test]
+    fn test_validate_pragma() {
+        assert!(validate_pragma("^0.8.20").is_ok());
+        assert!(validate_pragma(">=0.7.0 <0.9.0").is_ok());
+        assert!(validate_pragma("").is_err());
+        assert!(validate_pragma("^0.8.20; contract X {}").is_err());
+    }
+
+    #[test]
+    fn test_validate_license() {
+        assert!(validate_license("MIT").is_ok());
+        assert!(validate_license("GPL-3.0-or-later").is_ok());
+        assert!(validate_license("MIT\n*/ contract X {}").is_err());
+    }
+
+    #[test]
+    fn test_validate_string_literal() {
+        assert!(validate_string_literal("token name", "Your Token").is_ok());
+        assert!(validate_string_literal("token name", "").is_err());
+        assert!(validate_string_literal("token name", "Your\"Token").is_err());
+        assert!(validate_string_literal("token name", "Your\\Token").is_err());
+        assert!(validate_string_literal("token name", "Your\nToken").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use minijinja::Environment;
+use serde::Serialize;
+
+/// Partial templates that any template can pull in with `{% include "<name>" %}`.
+const PARTIALS: &[(&str, &str)] = &[
+    (
+        "public_signal_indices.sol",
+        include_str!("../../templates/partials/public_signal_indices.sol.jinja"),
+    ),
+    (
+        "whitelist_check.sol",
+        include_str!("../../templates/partials/whitelist_check.sol.jinja"),
+    ),
+];
+
+/// Renders a template source with the given context.
+///
+/// Templates are written in Jinja syntax and are compiled into the binary with
+/// `include_str!`, so no template files need to exist at runtime. Block tags
+/// swallow their own line, which keeps generated contracts free of stray blank lines,
+/// and the trailing newline of a template is kept so partials end their last line.
+/// The shared partials in `templates/partials` are available to every template.
+///
+/// # Parameters
+/// - `name`: The name of the template, used in error messages.
+/// - `source`: The template source.
+/// - `context`: Any serializable value exposing the variables used in the template.
+///
+/// # Returns
+/// A `Result` with the rendered text, or an error message if rendering fails.
+pub fn render_template<S: Serialize>(
+    name: &str,
+    source: &str,
+    context: S,
+) -> Result<String, String> {
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.set_keep_trailing_newline(true);
+    env.set_undefined_behavior(minijinja::UndefinedBehavior::Strict);
+    for (partial_name, partial_source) in PARTIALS {
+        env.add_template(partial_name, partial_source)
+            .map_err(|e| format!("Invalid template {}: {}", partial_name, e))?;
+    }
+    env.add_template(name, source)
+        .map_err(|e| format!("Invalid template {}: {}", name, e))?;
+    env.get_template(name)
+        .and_then(|template| template.render(context))
+        .map_err(|e| format!("Failed to render template {}: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_template() {
+        let result = render_template(
+            "greeting",
+            "{% for name in names %}\nHello {{ name }}!\n{% endfor %}\n",
+            json!({ "names": ["Alice", "Bob"] }),
+        );
+
+        assert_eq!(result, Ok("Hello Alice!\nHello Bob!\n".to_string()));
+    }
+
+    #[test]
+    fn test_render_template_with_partial() {
+        let result = render_template(
+            "contract",
+            "function mint() public {\n    {% include \"whitelist_check.sol\" %}\n}\n",
+            json!({ "address_signal": "ACCOUNT_INDEX" }),
+        )
+        .unwrap();
+
+        assert!(
+            result.contains("        require(!claimed[msg.sender], \"Tokens already claimed\");\n")
+        );
+        assert!(result.contains("senderDecimalAddress == _pubSignals[ACCOUNT_INDEX]"));
+        assert!(result.ends_with("        claimed[msg.sender] = true;\n}\n"));
+    }
+
+    #[test]
+    fn test_render_template_with_missing_variable() {
+        let result = render_template("greeting", "Hello {{ name }}!", json!({}));
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// A point on the G1 curve, with decimal coordinates.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct G1Point {
+    pub x: String,
+    pub y: String,
+}
+
+/// A point on the G2 curve, with decimal coordinates.
+///
+/// Each coordinate is an element of the quadratic extension field, stored as `[c0, c1]`
+/// in the same order as `snarkjs` writes them to `verification_key.json`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct G2Point {
+    pub x: [String; 2],
+    pub y: [String; 2],
+}
+
+/// A Groth16 verification key as exported by `snarkjs zkey export verificationkey`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct VerificationKey {
+    pub curve: String,
+    pub n_public: usize,
+    pub alpha: G1Point,
+    pub beta: G2Point,
+    pub gamma: G2Point,
+    pub delta: G2Point,
+    pub ic: Vec<G1Point>,
+}
+
+impl VerificationKey {
+    /// Parses the contents of a `verification_key.json` file.
+    ///
+    /// # Returns
+    /// The parsed key, or an error message if the content is not a valid Groth16 key.
+    pub fn from_json(content: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(content)
+            .map_err(|e| format!("Failed to parse verification key: {}", e))?;
+
+        let protocol = json["protocol"].as_str().unwrap_or_default();
+        if protocol != "groth16" {
+            return Err(format!(
+                "Unsupported verification key protocol '{}', only groth16 is supported",
+                protocol
+            ));
+        }
+
+        let n_public = json["nPublic"]
+            .as_u64()
+            .ok_or("Verification key is missing nPublic")? as usize;
+        let ic = json["IC"]
+            .as_array()
+            .ok_or("Verification key is missing IC")?
+            .iter()
+            .map(parse_g1)
+            .collect::<Result<Vec<_>, _>>()?;
+        if ic.len() != n_public + 1 {
+            return Err(format!(
+                "Verification key has {} IC points but nPublic is {}",
+                ic.len(),
+                n_public
+            ));
+        }
+
+        Ok(VerificationKey {
+            curve: json["curve"].as_str().unwrap_or("bn128").to_string(),
+            n_public,
+            alpha: parse_g1(&json["vk_alpha_1"])?,
+            beta: parse_g2(&json["vk_beta_2"])?,
+            gamma: parse_g2(&json["vk_gamma_2"])?,
+            delta: parse_g2(&json["vk_delta_2"])?,
+            ic,
+        })
+    }
+}
+
+fn parse_coordinate(value: &Value) -> Result<String, String> {
+    value
+        .as_str()
+        .filter(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string)
+        .ok_or_else(|| format!("Invalid coordinate in verification key: {}", value))
+}
+
+fn parse_g1(value: &Value) -> Result<G1Point, String> {
+    Ok(G1Point {
+        x: parse_coordinate(&value[0])?,
+        y: parse_coordinate(&value[1])?,
+    })
+}
+
+fn parse_g2(value: &Value) -> Result<G2Point, String> {
+    Ok(G2Point {
+        x: [
+            parse_coordinate(&value[0][0])?,
+            parse_coordinate(&value[0][1])?,
+        ],
+        y: [
+            parse_coordinate(&value[1][0])?,
+            parse_coordinate(&value[1][1])?,
+        ],
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A verification key with the shape produced by `snarkjs` for the template circuit.
+    pub(crate) const SAMPLE_VERIFICATION_KEY: &str = r#"{
+        "protocol": "groth16",
+        "curve": "bn128",
+        "nPublic": 2,
+        "vk_alpha_1": ["11", "12", "1"],
+        "vk_beta_2": [["21", "22"], ["23", "24"], ["1", "0"]],
+        "vk_gamma_2": [["31", "32"], ["33", "34"], ["1", "0"]],
+        "vk_delta_2": [["41", "42"], ["43", "44"], ["1", "0"]],
+        "vk_alphabeta_12": [],
+        "IC": [["51", "52", "1"], ["53", "54", "1"], ["55", "56", "1"]]
+    }"#;
+
+    #[test]
+    fn test_from_json() {
+        let vk = VerificationKey::from_json(SAMPLE_VERIFICATION_KEY).unwrap();
+
+        assert_eq!(vk.n_public, 2);
+        assert_eq!(vk.curve, "bn128");
+        assert_eq!(
+            vk.alpha,
+            G1Point {
+                x: "11".to_string(),
+                y: "12".to_string()
+            }
+        );
+        assert_eq!(vk.beta.x, ["21".to_string(), "22".to_string()]);
+        assert_eq!(vk.delta.y, ["43".to_string(), "44".to_string()]);
+        assert_eq!(vk.ic.len(), 3);
+    }
+
+    #[test]
+    fn test_from_json_rejects_other_protocols() {
+        let content = SAMPLE_VERIFICATION_KEY.replace("groth16", "plonk");
+
+        assert!(VerificationKey::from_json(&content).is_err());
+    }
+
+    #[test]
+    fn test_from_json_rejects_mismatched_ic() {
+        let content = SAMPLE_VERIFICATION_KEY.replace("\"nPublic\": 2", "\"nPublic\": 3");
+
+        assert!(VerificationKey::from_json(&content).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "@openzeppelin/contracts/token/ERC20/ERC20.sol";
+
+/// @title TestToken Contract
+/// @notice An ERC20 token minting its whole supply to the deployer, which funds an airdrop under test.
+contract TestToken is ERC20 {
+    constructor() ERC20("Test Token", "TEST") {
+        _mint(msg.sender, type(uint128).max);
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::fs;
+
+    #[test]
+    fn test_all_command_dry_run() {
+        // Arrange
+        let project_dir = std::env::temp_dir().join("zk_whitelist_test_all_dry_run");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("circuit.circom"), "// customized circuit").unwrap();
+
+        // Act
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd
+            .current_dir(&project_dir)
+            .args(["all", "--dry-run"])
+            .assert();
+
+        // Assert
+        assert
+            .success()
+            .stdout(predicate::str::contains(
+                "circuit   skip  circuit.circom exists",
+            ))
+            .stdout(predicate::str::contains(
+                "compile   run   circuit.r1cs is missing",
+            ))
+            .stdout(predicate::str::contains("proofs    run   setup runs"))
+            .stdout(predicate::str::contains(
+                "$ circom circuit.circom --r1cs --sym --wasm -o circuit.partial",
+            ))
+            .stdout(predicate::str::contains(
+                "skip verifier: verification_key.json is written by a tool",
+            ))
+            .stdout(predicate::str::contains(
+                "skip proofs: the input file addresses.txt is missing",
+            ))
+            .stdout(predicate::str::contains("==> circuit").count(1))
+            .stdout(predicate::str::contains("circuit   skip").count(1));
+        let circuit = fs::read_to_string(project_dir.join("circuit.circom")).unwrap();
+        assert_eq!(circuit, "// customized circuit");
+
+        // Clean up
+        fs::remove_dir_all(project_dir).unwrap();
+    }
+}
@@ -7,24 +7,25 @@ mod tests {
     #[test]
     fn test_circuit_command() {
         // Arrange
-        let current_dir = std::env::current_dir().unwrap();
-        let circuit_path = current_dir.join("circuit.circom");
+        let project_dir = std::env::temp_dir().join("zk_whitelist_test_circuit");
+        fs::create_dir_all(&project_dir).unwrap();
+        let circuit_path = project_dir.join("circuit.circom");
         let expected_content = include_bytes!("../templates/circuit.circom");
 
         // Act
-        execute_circuit_command();
+        execute_circuit_command(&project_dir);
 
         // Assert
         let actual_content = fs::read(&circuit_path).unwrap();
         assert_files_match(expected_content, &actual_content, &circuit_path);
 
-        // Clean up: Remove the created circuit.circom file
-        fs::remove_file(circuit_path).unwrap();
+        // Clean up: Remove the project directory with the created circuit.circom file
+        fs::remove_dir_all(project_dir).unwrap();
     }
 
-    fn execute_circuit_command() {
+    fn execute_circuit_command(dir: &Path) {
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        cmd.arg("circuit").assert().success();
+        cmd.current_dir(dir).arg("circuit").assert().success();
     }
 
     fn assert_files_match(expected_content: &[u8], actual_content: &[u8], file_path: &Path) {
@@ -37,4 +37,14 @@ mod tests {
             .failure()
             .stderr(contains("error: unrecognized subcommand 'unrecognized'"));
     }
+
+    /// Tests that `--quiet` and `--json-log` cannot be combined.
+    #[test]
+    fn test_quiet_conflicts_with_json_log() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["--quiet", "--json-log", "proofs"])
+            .assert()
+            .failure()
+            .stderr(contains("cannot be used with"));
+    }
 }
@@ -7,7 +7,8 @@ mod tests {
     #[test]
     fn test_compile_command() {
         // Arrange
-        let current_dir = std::env::current_dir().unwrap();
+        let current_dir = std::env::temp_dir().join("zk_whitelist_test_compile");
+        fs::create_dir_all(&current_dir).unwrap();
         create_circuit_file(&current_dir);
 
         // Act
@@ -40,9 +41,6 @@ mod tests {
     }
 
     fn clean_up_files(dir: &Path) {
-        fs::remove_file(dir.join("circuit.circom")).unwrap();
-        fs::remove_file(dir.join("circuit.r1cs")).unwrap();
-        fs::remove_file(dir.join("circuit.sym")).unwrap();
-        fs::remove_dir_all(dir.join("circuit_js")).unwrap();
+        fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,39 @@
+#[cfg(test)]
+mod tests {
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::fs;
+
+    #[test]
+    fn test_dry_run_prints_commands_and_writes() {
+        // Arrange
+        let project_dir = std::env::temp_dir().join("zk_whitelist_test_dry_run");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        // Act
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let circuit = cmd
+            .current_dir(&project_dir)
+            .args(["--dry-run", "circuit"])
+            .assert();
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let compile = cmd
+            .current_dir(&project_dir)
+            .args(["compile", "--dry-run", "--workdir", "whitelist"])
+            .assert();
+
+        // Assert
+        circuit
+            .success()
+            .stdout(predicate::str::starts_with("write circuit.circom ("));
+        compile.success().stdout(
+            predicate::str::contains("mkdir -p whitelist/private\n").and(predicate::str::contains(
+                "$ circom circuit.circom --r1cs --sym --wasm -o whitelist/private",
+            )),
+        );
+        assert_eq!(fs::read_dir(&project_dir).unwrap().count(), 0);
+
+        // Clean up
+        fs::remove_dir_all(project_dir).unwrap();
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::fs;
+
+    #[test]
+    fn test_invalid_address_exit_code() {
+        // Arrange
+        let project_dir = std::env::temp_dir().join("zk_whitelist_test_exit_invalid_address");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(
+            project_dir.join("addresses.txt"),
+            "0x1234567890abcdef1234567890abcdef12345678\nnot an address\n",
+        )
+        .unwrap();
+
+        // Act
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.current_dir(&project_dir).arg("proofs").assert();
+
+        // Assert
+        assert.code(2).stderr(predicate::str::contains(
+            "Error: addresses.txt: Invalid address \"not an address\" on line 2",
+        ));
+
+        // Clean up
+        fs::remove_dir_all(project_dir).unwrap();
+    }
+
+    #[test]
+    fn test_artifact_missing_exit_code() {
+        // Arrange
+        let project_dir = std::env::temp_dir().join("zk_whitelist_test_exit_artifact_missing");
+        fs::create_dir_all(&project_dir).unwrap();
+
+        // Act
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.current_dir(&project_dir).arg("verifier").assert();
+
+        // Assert
+        assert.code(5).stderr(predicate::str::contains(
+            "Error: verification_key.json is missing",
+        ));
+
+        // Clean up
+        fs::remove_dir_all(project_dir).unwrap();
+    }
+}
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::fs;
+
+    #[test]
+    fn test_export_command_refuses_secrets() {
+        // Arrange
+        let project_dir = std::env::temp_dir().join("zk_whitelist_test_export_secrets");
+        fs::create_dir_all(&project_dir).unwrap();
+        for file in [
+            "verifier.sol",
+            "verification_key.json",
+            "addresses.txt.proofs.json",
+        ] {
+            fs::write(project_dir.join(file), "{}").unwrap();
+        }
+        fs::write(project_dir.join("circuit_0001.zkey"), "zkey").unwrap();
+
+        // Act
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd
+            .current_dir(&project_dir)
+            .args(["export", "--include", "circuit_0001.zkey"])
+            .assert();
+
+        // Assert
+        assert.failure().stderr(predicate::str::contains(
+            "Refusing to export circuit_0001.zkey, which must stay private",
+        ));
+        assert!(!project_dir.join("release.tar.gz").exists());
+
+        // Clean up
+        fs::remove_dir_all(project_dir).unwrap();
+    }
+}
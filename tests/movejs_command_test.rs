@@ -5,7 +5,6 @@
 /// are executed, and that the `movejs` command performs the expected file moving operations.
 /// Additionally, it provides cleanup functionality to remove generated files and directories
 /// after the test.
-
 #[cfg(test)]
 mod movejs_tests {
     use assert_cmd::Command;
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::process::{Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    const PROOFS: &str = r#"{"0xabcdef1234567890abcdef1234567890abcdef12": {
+        "pA": ["0x01", "0x02"],
+        "pB": [["0x03", "0x04"], ["0x05", "0x06"]],
+        "pC": ["0x07", "0x08"],
+        "input": ["0x01", "0xabcdef1234567890abcdef1234567890abcdef12"]
+    }}"#;
+
+    /// Sends a request to the server, retrying while it starts.
+    fn get(port: u16, path: &str) -> String {
+        for _ in 0..50 {
+            if let Ok(mut stream) = TcpStream::connect(("127.0.0.1", port)) {
+                write!(
+                    stream,
+                    "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                    path
+                )
+                .unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).unwrap();
+                return response;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        panic!("the server did not start");
+    }
+
+    #[test]
+    fn test_serve_command() {
+        // Arrange
+        let project_dir = std::env::temp_dir().join("zk_whitelist_test_serve");
+        fs::create_dir_all(&project_dir).unwrap();
+        fs::write(project_dir.join("addresses.txt.proofs.json"), PROOFS).unwrap();
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        // Act
+        let mut server = Command::new(assert_cmd::cargo::cargo_bin(env!("CARGO_PKG_NAME")))
+            .current_dir(&project_dir)
+            .args(["serve", "--addr", &format!("127.0.0.1:{}", port)])
+            .env_remove("ZK_WHITELIST_ADMIN_TOKEN")
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let health = get(port, "/health");
+        let proof = get(port, "/proof/0xABCDEF1234567890abcdef1234567890abcdef12");
+        server.kill().unwrap();
+        server.wait().unwrap();
+
+        // Assert
+        assert!(health.starts_with("HTTP/1.1 200"), "{}", health);
+        assert!(health.contains(r#"{"proofs":1,"status":"ok"}"#));
+        assert!(proof.contains("Access-Control-Allow-Origin: *"));
+        assert!(proof.contains(r#""pC":["0x07","0x08"]"#));
+
+        // Clean up
+        fs::remove_dir_all(project_dir).unwrap();
+    }
+}
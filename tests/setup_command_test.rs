@@ -20,15 +20,19 @@ mod tests {
     #[test]
     fn test_setup_command() {
         // Arrange: Ensure `circuit` and `compile` commands are executed
-        let current_dir = std::env::current_dir().unwrap();
-        execute_circuit_and_compile_commands();
+        let current_dir = std::env::temp_dir().join("zk_whitelist_test_setup");
+        fs::create_dir_all(&current_dir).unwrap();
+        execute_circuit_and_compile_commands(&current_dir);
 
         // Act: Execute the `setup` command
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        cmd.arg("setup").assert().success();
+        cmd.current_dir(&current_dir)
+            .arg("setup")
+            .assert()
+            .success();
 
         // Assert: Check that the expected output files are created
-        assert_files_created();
+        assert_files_created(&current_dir);
 
         // Clean up generated files
         cleanup_files(&current_dir);
@@ -38,18 +42,18 @@ mod tests {
     ///
     /// This function ensures that the preliminary `circuit` and `compile`
     /// commands are executed successfully before testing the `setup` command.
-    fn execute_circuit_and_compile_commands() {
+    fn execute_circuit_and_compile_commands(dir: &Path) {
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        cmd.arg("circuit").assert().success();
+        cmd.current_dir(dir).arg("circuit").assert().success();
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        cmd.arg("compile").assert().success();
+        cmd.current_dir(dir).arg("compile").assert().success();
     }
 
     /// Checks that the expected output files are created.
     ///
     /// This function verifies that all the expected output files are created
     /// by the `setup` command.
-    fn assert_files_created() {
+    fn assert_files_created(dir: &Path) {
         let output_files = vec![
             "circuit.circom",
             "circuit.r1cs",
@@ -62,7 +66,7 @@ mod tests {
             "verification_key.json",
         ];
         for file in output_files {
-            assert!(Path::new(file).exists(), "File {} does not exist", file);
+            assert!(dir.join(file).exists(), "File {} does not exist", file);
         }
     }
 
@@ -83,7 +87,7 @@ mod tests {
             "verification_key.json",
         ];
         for file in output_files {
-            let _ = fs::remove_file(file); // Ignore any deletion errors
+            let _ = fs::remove_file(dir.join(file)); // Ignore any deletion errors
         }
         // Also clean up any generated directories
         fs::remove_dir_all(dir.join("circuit_js")).unwrap();
@@ -20,15 +20,19 @@ mod verifier_tests {
     #[test]
     fn test_verifier_command() {
         // Arrange: Ensure `circuit`, `compile`, and `setup` commands are executed
-        let current_dir = std::env::current_dir().unwrap();
-        execute_preliminary_commands();
+        let current_dir = std::env::temp_dir().join("zk_whitelist_test_verifier");
+        fs::create_dir_all(&current_dir).unwrap();
+        execute_preliminary_commands(&current_dir);
 
         // Act: Execute the `verifier` command
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        cmd.arg("verifier").assert().success();
+        cmd.current_dir(&current_dir)
+            .arg("verifier")
+            .assert()
+            .success();
 
         // Assert: Check that the expected output file is created
-        assert_file_created("verifier.sol");
+        assert_file_created(&current_dir, "verifier.sol");
 
         // Clean up generated files
         cleanup_files(&current_dir);
@@ -38,21 +42,21 @@ mod verifier_tests {
     ///
     /// This function ensures that the preliminary `circuit`, `compile`, and `setup`
     /// commands are executed successfully before testing the `verifier` command.
-    fn execute_preliminary_commands() {
+    fn execute_preliminary_commands(dir: &Path) {
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        cmd.arg("circuit").assert().success();
+        cmd.current_dir(dir).arg("circuit").assert().success();
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        cmd.arg("compile").assert().success();
+        cmd.current_dir(dir).arg("compile").assert().success();
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
-        cmd.arg("setup").assert().success();
+        cmd.current_dir(dir).arg("setup").assert().success();
     }
 
     /// Checks that the expected output file is created.
     ///
     /// This function verifies that the expected output file is created
     /// by the `verifier` command.
-    fn assert_file_created(file: &str) {
-        assert!(Path::new(file).exists(), "File {} does not exist", file);
+    fn assert_file_created(dir: &Path, file: &str) {
+        assert!(dir.join(file).exists(), "File {} does not exist", file);
     }
 
     /// Cleans up the generated files.
@@ -74,7 +78,7 @@ mod verifier_tests {
             "verifier.sol",
         ];
         for file in output_files {
-            let _ = fs::remove_file(file); // Ignore any deletion errors
+            let _ = fs::remove_file(dir.join(file)); // Ignore any deletion errors
         }
         // Also clean up any generated directories
         fs::remove_dir_all(dir.join("circuit_js")).unwrap();